@@ -0,0 +1,22 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use balthazar::mapcheck::check_map_file;
+
+// Validate Tiled maps without starting the game:
+//   cargo run --bin balthazar-mapcheck -- assets/map.tmx [more.tmx ...]
+fn main() -> ExitCode {
+    let mut paths: Vec<PathBuf> = std::env::args().skip(1).map(PathBuf::from).collect();
+    if paths.is_empty() {
+        paths.push(PathBuf::from("assets/map.tmx"));
+    }
+
+    let mut ok = true;
+    for path in &paths {
+        let report = check_map_file(path);
+        println!("{report}\n");
+        ok &= report.is_ok();
+    }
+
+    if ok { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+}
@@ -1,50 +1,338 @@
+use bevy::camera::CameraProjection;
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
 use bevy::prelude::*;
-use crate::components::{Player, SystemToggles};
+use bevy::window::PrimaryWindow;
+use bevy_ecs_tiled::prelude::*;
+use crate::components::{GridMovement, Player, SystemToggles};
+use crate::isometric::MapGrid;
+use crate::picking::viewport_to_world;
+use crate::tiled_map::map_tile_bounds;
 
+/// How the camera follows the robot
+#[derive(Resource, Clone, Debug)]
+pub struct CameraFollow {
+    /// How quickly the camera catches up, per second. The same fraction of the
+    /// gap is closed in a given time whatever the frame rate.
+    pub damping: f32,
+    /// Half-size of the rectangle around the camera's focus that the robot can
+    /// move around in without the camera following
+    pub deadzone: Vec2,
+    /// How far ahead of a moving robot the camera looks, in world units
+    pub look_ahead: f32,
+    /// Round the camera position to whole screen pixels so pixel-art tiles don't shimmer
+    pub pixel_snap: bool,
+    /// Where the camera looks, before snapping to pixels. `None` until the first frame.
+    pub focus: Option<Vec2>,
+}
+
+impl Default for CameraFollow {
+    fn default() -> Self {
+        Self {
+            damping: 8.0,
+            deadzone: Vec2::new(24.0, 16.0),
+            look_ahead: 48.0,
+            pixel_snap: true,
+            focus: None,
+        }
+    }
+}
+
+/// World-space rectangle the camera's view is kept inside. `None` until a map has loaded.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct CameraBounds {
+    pub rect: Option<Rect>,
+}
+
+/// Camera centre closest to `center` that keeps a view of `half_view` inside
+/// `bounds`. On an axis where the view is bigger than the map, the map is centred.
+pub fn clamp_to_bounds(center: Vec2, half_view: Vec2, bounds: Rect) -> Vec2 {
+    let min = bounds.min + half_view;
+    let max = bounds.max - half_view;
+    Vec2::new(
+        if min.x <= max.x { center.x.clamp(min.x, max.x) } else { bounds.center().x },
+        if min.y <= max.y { center.y.clamp(min.y, max.y) } else { bounds.center().y },
+    )
+}
+
+/// Largest orthographic scale at which a view of `unit_half_view` (its half-size
+/// at scale 1) still fits inside `bounds`
+pub fn max_scale_for_bounds(unit_half_view: Vec2, bounds: Rect) -> f32 {
+    let fit = bounds.half_size() / unit_half_view;
+    fit.x.min(fit.y)
+}
+
+/// Half-size of the world area an orthographic camera sees at scale 1. `None`
+/// until the camera knows the size of its viewport.
+pub fn unit_half_view(camera: &Camera, ortho: &OrthographicProjection) -> Option<Vec2> {
+    let viewport = camera.logical_viewport_size()?;
+    let mut unit = OrthographicProjection { scale: 1.0, ..ortho.clone() };
+    unit.update(viewport.x, viewport.y);
+    Some(unit.area.half_size())
+}
+
+/// Work out the world-space extent of a map whenever it finishes loading or is modified
+pub fn update_camera_bounds(
+    mut asset_events: MessageReader<AssetEvent<TiledMapAsset>>,
+    map_assets: Res<Assets<TiledMapAsset>>,
+    grid: Res<MapGrid>,
+    mut bounds: ResMut<CameraBounds>,
+) {
+    for event in asset_events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event else {
+            continue;
+        };
+        let Some(map_asset) = map_assets.get(*id) else { continue; };
+        bounds.rect = Some(grid.tiles_world_rect(map_tile_bounds(&map_asset.map)));
+    }
+}
+
+/// Where the camera should look so that `point` is inside the deadzone around it
+pub fn deadzone_target(focus: Vec2, point: Vec2, deadzone: Vec2) -> Vec2 {
+    focus.clamp(point - deadzone, point + deadzone)
+}
+
+/// Move `current` towards `target` with exponential damping over `delta_secs`
+pub fn damp_towards(current: Vec2, target: Vec2, damping: f32, delta_secs: f32) -> Vec2 {
+    current.lerp(target, 1.0 - (-damping * delta_secs).exp())
+}
+
+/// Round a camera position to whole screen pixels, given world units per pixel
+pub fn snap_to_pixels(position: Vec2, world_per_pixel: f32) -> Vec2 {
+    if world_per_pixel <= 0.0 {
+        return position;
+    }
+    (position / world_per_pixel).round() * world_per_pixel
+}
+
+/// Follow the robot: the camera stays put while it moves within the deadzone,
+/// looks ahead in the direction it is walking and eases after it. The view never
+/// leaves the map.
 pub fn camera_follow_player(
-    player_query: Query<&Transform, (With<Player>, Without<Camera2d>)>,
-    mut camera_query: Query<&mut Transform, (With<Camera2d>, Without<Player>)>,
+    time: Res<Time>,
+    mut follow: ResMut<CameraFollow>,
+    bounds: Res<CameraBounds>,
+    player_query: Query<(&Transform, Option<&GridMovement>), (With<Player>, Without<Camera2d>)>,
+    mut camera_query: Query<(&mut Transform, Option<&Projection>, Option<&Camera>), (With<Camera2d>, Without<Player>)>,
     toggles: Res<SystemToggles>,
 ) {
     if !toggles.camera_follow { return; }
-    if let (Ok(player_transform), Ok(mut camera_transform)) = (player_query.single(), camera_query.single_mut()) {
-        // Smoothly follow the player with some offset for better view
-        camera_transform.translation.x = player_transform.translation.x;
-        camera_transform.translation.y = player_transform.translation.y;
-        // Keep camera Z position fixed for 2D view
+    let (Ok((player_transform, grid_movement)), Ok((mut camera_transform, projection, camera))) =
+        (player_query.single(), camera_query.single_mut())
+    else {
+        return;
+    };
+
+    let player = player_transform.translation.truncate();
+    let heading = grid_movement
+        .and_then(|grid_movement| grid_movement.target_position)
+        .map(|target| (target - player).normalize_or_zero() * follow.look_ahead)
+        .unwrap_or(Vec2::ZERO);
+
+    let focus = follow.focus.unwrap_or(player);
+    let target = deadzone_target(focus, player + heading, follow.deadzone);
+    let mut focus = damp_towards(focus, target, follow.damping, time.delta_secs());
+
+    let ortho = match projection {
+        Some(Projection::Orthographic(ortho)) => Some(ortho),
+        _ => None,
+    };
+    let half_view = ortho.zip(camera).and_then(|(ortho, camera)| Some(unit_half_view(camera, ortho)? * ortho.scale));
+    if let (Some(rect), Some(half_view)) = (bounds.rect, half_view) {
+        focus = clamp_to_bounds(focus, half_view, rect);
     }
+    follow.focus = Some(focus);
+
+    let world_per_pixel = ortho.map_or(1.0, |ortho| ortho.scale);
+    let position = if follow.pixel_snap { snap_to_pixels(focus, world_per_pixel) } else { focus };
+    // Keep camera Z position fixed for 2D view
+    camera_transform.translation.x = position.x;
+    camera_transform.translation.y = position.y;
+}
+
+/// Zoom in one step: `=` or `+` on the keyboard, the right bumper on a gamepad
+const ZOOM_IN_KEYS: [KeyCode; 2] = [KeyCode::Equal, KeyCode::NumpadAdd];
+const ZOOM_OUT_KEYS: [KeyCode; 2] = [KeyCode::Minus, KeyCode::NumpadSubtract];
+const ZOOM_IN_BUTTON: GamepadButton = GamepadButton::RightTrigger;
+const ZOOM_OUT_BUTTON: GamepadButton = GamepadButton::LeftTrigger;
+
+const MIN_ZOOM_SCALE: f32 = 0.1;
+const MAX_ZOOM_SCALE: f32 = 10.0;
+/// Scale factor of one free zoom step
+const ZOOM_STEP_FACTOR: f32 = 1.1;
+/// Trackpad scrolling that counts as one zoom step
+const PIXELS_PER_ZOOM_STEP: f32 = 100.0;
+
+/// How the camera zooms
+#[derive(Resource, Clone, Debug)]
+pub struct CameraZoom {
+    /// Only stop at scales where a world pixel covers a whole number of screen
+    /// pixels (or the other way round), so pixel art stays sharp
+    pub pixel_perfect: bool,
+    /// How quickly the scale eases towards its target, per second
+    pub speed: f32,
+    /// Scale being eased towards. `None` until the first zoom.
+    pub target_scale: Option<f32>,
+    /// World point that stays under the cursor while zooming. `None` zooms around
+    /// the centre of the view.
+    pub anchor: Option<Vec2>,
+    /// Trackpad scrolling not yet used up by whole zoom steps
+    pub scroll_pixels: f32,
 }
 
+impl Default for CameraZoom {
+    fn default() -> Self {
+        Self {
+            pixel_perfect: true,
+            speed: 12.0,
+            target_scale: None,
+            anchor: None,
+            scroll_pixels: 0.0,
+        }
+    }
+}
+
+/// Pixel-perfect zoom level of a scale, rounded to the nearest one. Level 0 is
+/// scale 1, level n is scale 1/(n + 1) and level -n is scale n + 1.
+pub fn pixel_perfect_level(scale: f32) -> i32 {
+    if scale <= 1.0 {
+        (1.0 / scale).round() as i32 - 1
+    } else {
+        1 - scale.round() as i32
+    }
+}
+
+/// Scale of a pixel-perfect zoom level
+pub fn pixel_perfect_scale(level: i32) -> f32 {
+    if level >= 0 {
+        1.0 / (level + 1) as f32
+    } else {
+        (1 - level) as f32
+    }
+}
+
+/// Largest pixel-perfect scale no bigger than `limit`, or `limit` itself below the closest level
+pub fn pixel_perfect_scale_at_most(limit: f32) -> f32 {
+    let scale = if limit >= 1.0 { limit.floor() } else { 1.0 / (1.0 / limit).ceil() };
+    scale.max(MIN_ZOOM_SCALE).min(limit)
+}
+
+/// Scale after zooming in by `steps` (out if negative). Pixel-perfect zooming
+/// moves between whole levels, rounding `steps` and the starting scale.
+pub fn step_zoom_scale(scale: f32, steps: f32, pixel_perfect: bool) -> f32 {
+    if pixel_perfect {
+        let level = pixel_perfect_level(scale) + steps.round() as i32;
+        let level = level.clamp(pixel_perfect_level(MAX_ZOOM_SCALE), pixel_perfect_level(MIN_ZOOM_SCALE));
+        pixel_perfect_scale(level)
+    } else {
+        (scale / ZOOM_STEP_FACTOR.powf(steps)).clamp(MIN_ZOOM_SCALE, MAX_ZOOM_SCALE)
+    }
+}
+
+/// Ease a scale towards `target`. Zooming is even in ratios, so this is
+/// exponential damping of the logarithm; close enough lands exactly on target.
+pub fn ease_scale(scale: f32, target: f32, speed: f32, delta_secs: f32) -> f32 {
+    let t = 1.0 - (-speed * delta_secs).exp();
+    let eased = (scale.ln() + (target.ln() - scale.ln()) * t).exp();
+    if (eased / target - 1.0).abs() < 1e-3 { target } else { eased }
+}
+
+/// Camera position that keeps `anchor` at the same spot on screen when the scale
+/// changes from `from_scale` to `to_scale`
+pub fn zoom_about(camera: Vec2, anchor: Vec2, from_scale: f32, to_scale: f32) -> Vec2 {
+    anchor + (camera - anchor) * (to_scale / from_scale)
+}
+
+/// Zoom in steps with the mouse wheel (towards the cursor), `+`/`-` or the
+/// gamepad bumpers, easing between scales. The view never gets bigger than the
+/// map or leaves it.
+#[allow(clippy::too_many_arguments)]
 pub fn camera_zoom(
-    mut scroll_events: MessageReader<bevy::input::mouse::MouseWheel>,
-    mut camera_query: Query<&mut Projection, With<Camera2d>>,
+    time: Res<Time>,
+    mut scroll_events: MessageReader<MouseWheel>,
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    bounds: Res<CameraBounds>,
+    mut zoom: ResMut<CameraZoom>,
+    mut follow: ResMut<CameraFollow>,
+    mut camera_query: Query<(&mut Projection, &mut Transform, &Camera), With<Camera2d>>,
     toggles: Res<SystemToggles>,
 ) {
     if !toggles.camera_zoom { return; }
+    let Ok((mut projection, mut transform, camera)) = camera_query.single_mut() else { return; };
+    let Projection::Orthographic(ortho) = &*projection else { return; };
+
+    let mut scroll_steps = 0.0;
     for event in scroll_events.read() {
-        if let Ok(mut projection) = camera_query.single_mut() {
-            if let Projection::Orthographic(ref mut ortho) = projection.as_mut() {
-                // Zoom in/out based on scroll direction
-                let zoom_factor = 1.1;
-                match event.unit {
-                    bevy::input::mouse::MouseScrollUnit::Line => {
-                        if event.y > 0.0 {
-                            // Scroll up - zoom in
-                            ortho.scale /= zoom_factor;
-                        } else {
-                            // Scroll down - zoom out
-                            ortho.scale *= zoom_factor;
-                        }
-                    }
-                    bevy::input::mouse::MouseScrollUnit::Pixel => {
-                        let zoom_change = -event.y * 0.001;
-                        ortho.scale *= (1.0 + zoom_change).max(0.1);
-                    }
-                }
-                
-                // Clamp zoom to reasonable limits
-                ortho.scale = ortho.scale.clamp(0.1, 10.0);
-            }
+        match event.unit {
+            MouseScrollUnit::Line => scroll_steps += event.y,
+            MouseScrollUnit::Pixel => zoom.scroll_pixels += event.y,
+        }
+    }
+    let pixel_steps = zoom.scroll_pixels / PIXELS_PER_ZOOM_STEP;
+    // Pixel-perfect zooming only takes whole steps; keep the rest for later
+    let pixel_steps = if zoom.pixel_perfect { pixel_steps.trunc() } else { pixel_steps };
+    zoom.scroll_pixels -= pixel_steps * PIXELS_PER_ZOOM_STEP;
+    scroll_steps += pixel_steps;
+
+    let pressed = |in_pressed: bool, out_pressed: bool| in_pressed as i32 - out_pressed as i32;
+    let mut button_steps = pressed(keys.any_just_pressed(ZOOM_IN_KEYS), keys.any_just_pressed(ZOOM_OUT_KEYS));
+    for gamepad in gamepads.iter() {
+        button_steps += pressed(gamepad.just_pressed(ZOOM_IN_BUTTON), gamepad.just_pressed(ZOOM_OUT_BUTTON));
+    }
+
+    let camera_transform = GlobalTransform::from(*transform);
+    let cursor = window_query.single().ok().and_then(Window::cursor_position);
+    let steps = scroll_steps + button_steps as f32;
+    if steps != 0.0 {
+        let from = zoom.target_scale.unwrap_or(ortho.scale);
+        zoom.target_scale = Some(step_zoom_scale(from, steps, zoom.pixel_perfect));
+        // The wheel zooms towards the cursor, buttons towards the centre
+        zoom.anchor = match (scroll_steps != 0.0, cursor, camera.logical_viewport_rect()) {
+            (true, Some(cursor), Some(viewport)) => Some(viewport_to_world(cursor, viewport, ortho, &camera_transform)),
+            _ => None,
+        };
+    }
+
+    // Never show more than the map once it's loaded
+    let unit_half = unit_half_view(camera, ortho);
+    let mut max_scale = MAX_ZOOM_SCALE;
+    if let (Some(unit_half), Some(rect)) = (unit_half, bounds.rect) {
+        max_scale = max_scale_for_bounds(unit_half, rect).clamp(MIN_ZOOM_SCALE, MAX_ZOOM_SCALE);
+        if zoom.pixel_perfect {
+            max_scale = pixel_perfect_scale_at_most(max_scale);
+        }
+    }
+    let current = ortho.scale.clamp(MIN_ZOOM_SCALE, max_scale);
+    let target = zoom.target_scale.unwrap_or(current).clamp(MIN_ZOOM_SCALE, max_scale);
+    zoom.target_scale = Some(target);
+    let scale = ease_scale(current, target, zoom.speed, time.delta_secs());
+    if scale == target {
+        zoom.anchor = None;
+    }
+
+    let center = transform.translation.truncate();
+    let mut position = zoom_about(center, zoom.anchor.unwrap_or(center), ortho.scale, scale);
+    if let (Some(unit_half), Some(rect)) = (unit_half, bounds.rect) {
+        position = clamp_to_bounds(position, unit_half * scale, rect);
+    }
+    if position != center {
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+        // Carry the followed focus along so following doesn't snap back
+        if let Some(focus) = follow.focus.as_mut() {
+            *focus += position - center;
+        }
+    }
+    set_scale(&mut projection, scale);
+}
+
+/// Change an orthographic projection's scale, leaving it untouched if it's already right
+fn set_scale(projection: &mut Mut<Projection>, scale: f32) {
+    if let Projection::Orthographic(ortho) = projection.bypass_change_detection() {
+        if ortho.scale != scale {
+            ortho.scale = scale;
+            projection.set_changed();
         }
     }
 }
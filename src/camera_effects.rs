@@ -0,0 +1,372 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy_ecs_tiled::prelude::*;
+
+use crate::camera::{clamp_to_bounds, snap_to_pixels, unit_half_view, CameraBounds, CameraFollow};
+use crate::components::{Battery, CordSystem, Player, Pole, PowerRoute};
+use crate::isometric::MapGrid;
+use crate::map_objects::{float_property, short_type_name};
+use crate::tiled_map::{for_each_map_object, object_world_position};
+
+// Effects sit on top of whatever placed the camera this frame (following, the
+// free camera, zooming): that position is the base, shots blend away from it
+// and back, and the shake is added last.
+
+/// Time taken to blend into a shot and back out of it
+pub const SHOT_BLEND_SECS: f32 = 0.6;
+/// How long a focus pan rests on its target unless told otherwise
+pub const DEFAULT_PAN_HOLD_SECS: f32 = 1.0;
+/// Rail speed when the rail object has no `speed` property, in world units per second
+const DEFAULT_RAIL_SPEED: f32 = 200.0;
+
+const BATTERY_EMPTY_TRAUMA: f32 = 0.8;
+const CORD_SHORT_TRAUMA: f32 = 0.6;
+
+/// Shake the camera. Trauma adds up to at most 1 and wears off over time.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct CameraTrauma {
+    pub amount: f32,
+}
+
+/// Pan the camera to a point of interest, rest there for `hold` seconds and come back
+#[derive(Message, Clone, Copy, Debug)]
+pub struct CameraFocusPan {
+    pub target: Vec2,
+    pub hold: f32,
+}
+
+/// Play the camera rail of that name from the loaded map
+#[derive(Message, Clone, Debug)]
+pub struct PlayCameraRail {
+    pub name: String,
+}
+
+/// Camera path drawn in Tiled as a polyline object with this class
+/// (`balthazar::camera_effects::CameraRail`). Optional properties: `speed`
+/// (world units per second) and `intro` (play it when the map loads).
+#[derive(Clone, Debug, PartialEq)]
+pub struct CameraRail {
+    pub name: String,
+    pub points: Vec<Vec2>,
+    pub speed: f32,
+    pub intro: bool,
+}
+
+/// Camera rails of the loaded map
+#[derive(Resource, Default, Debug)]
+pub struct CameraRails {
+    pub rails: Vec<CameraRail>,
+}
+
+impl CameraRails {
+    pub fn from_tiled_map(map: &tiled::Map, grid: &MapGrid) -> Self {
+        let mut rails = Vec::new();
+        for_each_map_object(map, |object| {
+            if short_type_name(&object.user_type) != "CameraRail" {
+                return;
+            }
+            let tiled::ObjectShape::Polyline { points } = &object.shape else {
+                warn!("Camera rail '{}' isn't a polyline; skipping it", object.name);
+                return;
+            };
+            let points = points
+                .iter()
+                .map(|(x, y)| object_world_position(map, grid, object.x + x, object.y + y))
+                .collect();
+            rails.push(CameraRail {
+                name: object.name.clone(),
+                points,
+                speed: float_property(&object.properties, "speed").unwrap_or(DEFAULT_RAIL_SPEED),
+                intro: matches!(object.properties.get("intro"), Some(tiled::PropertyValue::BoolValue(true))),
+            });
+        });
+        Self { rails }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CameraRail> {
+        self.rails.iter().find(|rail| rail.name == name)
+    }
+}
+
+/// Total length of a polyline
+pub fn polyline_length(points: &[Vec2]) -> f32 {
+    points.windows(2).map(|pair| pair[0].distance(pair[1])).sum()
+}
+
+/// Point `distance` along a polyline, stopping at its ends
+pub fn point_along(points: &[Vec2], distance: f32) -> Vec2 {
+    let mut left = distance.max(0.0);
+    for pair in points.windows(2) {
+        let length = pair[0].distance(pair[1]);
+        if left <= length && length > 0.0 {
+            return pair[0].lerp(pair[1], left / length);
+        }
+        left -= length;
+    }
+    points.last().copied().unwrap_or(Vec2::ZERO)
+}
+
+/// Where a shot looks
+#[derive(Clone, Debug, PartialEq)]
+pub enum ShotPath {
+    Point(Vec2),
+    Rail { points: Vec<Vec2>, speed: f32 },
+}
+
+/// A camera move that blends in from the base position, plays and blends back out
+#[derive(Clone, Debug, PartialEq)]
+pub struct CameraShot {
+    pub path: ShotPath,
+    /// Time between blending in and blending out: the hold of a pan, the run of a rail
+    pub hold: f32,
+    pub elapsed: f32,
+}
+
+impl CameraShot {
+    pub fn focus_pan(target: Vec2, hold: f32) -> Self {
+        Self { path: ShotPath::Point(target), hold, elapsed: 0.0 }
+    }
+
+    pub fn rail(rail: &CameraRail) -> Self {
+        let speed = rail.speed.max(1.0);
+        Self {
+            hold: polyline_length(&rail.points) / speed,
+            path: ShotPath::Rail { points: rail.points.clone(), speed },
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn duration(&self) -> f32 {
+        SHOT_BLEND_SECS * 2.0 + self.hold
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration()
+    }
+
+    /// How far the camera has moved from its base onto the shot, eased in and out
+    pub fn weight(&self) -> f32 {
+        let blend_in = self.elapsed / SHOT_BLEND_SECS;
+        let blend_out = (self.duration() - self.elapsed) / SHOT_BLEND_SECS;
+        let x = blend_in.min(blend_out).clamp(0.0, 1.0);
+        x * x * (3.0 - 2.0 * x)
+    }
+
+    /// Where the shot looks right now
+    pub fn target(&self) -> Vec2 {
+        match &self.path {
+            ShotPath::Point(target) => *target,
+            ShotPath::Rail { points, speed } => point_along(points, (self.elapsed - SHOT_BLEND_SECS) * speed),
+        }
+    }
+}
+
+/// Smooth noise in -1..1, different for each `seed`
+fn shake_noise(time: f32, seed: f32) -> f32 {
+    ((time * 1.0 + seed).sin() + (time * 2.3 + seed * 1.7).sin() * 0.5 + (time * 4.1 + seed * 2.9).sin() * 0.25)
+        / 1.75
+}
+
+/// Camera shake for a given trauma. The offset grows with its square, so small
+/// knocks stay subtle and big ones hit hard.
+pub fn shake_offset(trauma: f32, max_offset: Vec2, frequency: f32, time: f32) -> Vec2 {
+    let strength = trauma.clamp(0.0, 1.0).powi(2);
+    let t = time * frequency;
+    Vec2::new(shake_noise(t, 0.0), shake_noise(t, 10.0)) * max_offset * strength
+}
+
+/// Shake and shots layered on top of the camera's position
+#[derive(Resource, Clone, Debug)]
+pub struct CameraEffects {
+    pub trauma: f32,
+    /// Trauma lost per second
+    pub trauma_decay: f32,
+    /// Offset at full trauma, in world units
+    pub max_shake: Vec2,
+    /// How fast the shake wobbles
+    pub shake_frequency: f32,
+    pub shot: Option<CameraShot>,
+    /// Shots waiting for the current one to finish
+    pub queue: VecDeque<CameraShot>,
+    /// Seconds the effects have run, which drives the shake
+    pub clock: f32,
+    /// Base position and shown position of the last frame, so a base nothing
+    /// moved since isn't shaken twice
+    pub applied: Option<(Vec2, Vec2)>,
+}
+
+impl Default for CameraEffects {
+    fn default() -> Self {
+        Self {
+            trauma: 0.0,
+            trauma_decay: 1.2,
+            max_shake: Vec2::new(12.0, 8.0),
+            shake_frequency: 20.0,
+            shot: None,
+            queue: VecDeque::new(),
+            clock: 0.0,
+            applied: None,
+        }
+    }
+}
+
+impl CameraEffects {
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+    }
+
+    /// Advance time and work out where the camera should be, given the base
+    /// position that following or the free camera chose
+    pub fn advance(&mut self, base: Vec2, delta_secs: f32) -> Vec2 {
+        self.clock += delta_secs;
+        let offset = shake_offset(self.trauma, self.max_shake, self.shake_frequency, self.clock);
+        self.trauma = (self.trauma - self.trauma_decay * delta_secs).max(0.0);
+
+        if self.shot.is_none() {
+            self.shot = self.queue.pop_front();
+        }
+        let mut position = base;
+        if let Some(shot) = self.shot.as_mut() {
+            shot.elapsed += delta_secs;
+            position = base.lerp(shot.target(), shot.weight());
+            if shot.is_finished() {
+                self.shot = None;
+            }
+        }
+        position + offset
+    }
+}
+
+/// Read the camera rails of a map whenever it finishes loading or is modified,
+/// and queue the intro rails of a newly loaded one
+pub fn load_camera_rails(
+    mut asset_events: MessageReader<AssetEvent<TiledMapAsset>>,
+    map_assets: Res<Assets<TiledMapAsset>>,
+    grid: Res<MapGrid>,
+    mut rails: ResMut<CameraRails>,
+    mut effects: ResMut<CameraEffects>,
+) {
+    for event in asset_events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event else {
+            continue;
+        };
+        let Some(map_asset) = map_assets.get(*id) else { continue; };
+        *rails = CameraRails::from_tiled_map(&map_asset.map, &grid);
+        if matches!(event, AssetEvent::LoadedWithDependencies { .. }) {
+            effects.queue.extend(rails.rails.iter().filter(|rail| rail.intro).map(CameraShot::rail));
+        }
+    }
+}
+
+/// Turn effect requests into trauma and queued shots
+pub fn queue_camera_effects(
+    mut trauma_events: MessageReader<CameraTrauma>,
+    mut pan_events: MessageReader<CameraFocusPan>,
+    mut rail_events: MessageReader<PlayCameraRail>,
+    rails: Res<CameraRails>,
+    mut effects: ResMut<CameraEffects>,
+) {
+    for event in trauma_events.read() {
+        effects.add_trauma(event.amount);
+    }
+    for event in pan_events.read() {
+        effects.queue.push_back(CameraShot::focus_pan(event.target, event.hold));
+    }
+    for event in rail_events.read() {
+        match rails.get(&event.name) {
+            Some(rail) => effects.queue.push_back(CameraShot::rail(rail)),
+            None => warn!("No camera rail called '{}' on this map", event.name),
+        }
+    }
+}
+
+/// Shake the camera when the battery runs flat
+pub fn shake_on_battery_empty(
+    mut was_empty: Local<bool>,
+    battery_query: Query<&Battery, With<Player>>,
+    mut trauma: MessageWriter<CameraTrauma>,
+) {
+    let Ok(battery) = battery_query.single() else { return; };
+    let empty = battery.current_charge <= 0.0;
+    if empty && !*was_empty {
+        trauma.write(CameraTrauma { amount: BATTERY_EMPTY_TRAUMA });
+    }
+    *was_empty = empty;
+}
+
+/// Shake the camera when the cord shorts: the robot lays it across a tile it
+/// already runs over (walking straight back reels it in instead)
+pub fn shake_on_cord_short(
+    mut trail_length: Local<usize>,
+    cord_system: Option<Res<CordSystem>>,
+    mut trauma: MessageWriter<CameraTrauma>,
+) {
+    let Some(cord_system) = cord_system else { return; };
+    let trail = &cord_system.trail_path;
+    if trail.len() > *trail_length {
+        if let Some((last, before)) = trail.split_last() {
+            if before.iter().any(|point| point.distance(*last) < 0.1) {
+                trauma.write(CameraTrauma { amount: CORD_SHORT_TRAUMA });
+            }
+        }
+    }
+    *trail_length = trail.len();
+}
+
+/// Show a pole when a switch turns its power route on
+pub fn pan_to_powered_poles(
+    route_query: Query<(Ref<PowerRoute>, &Transform), With<Pole>>,
+    mut pans: MessageWriter<CameraFocusPan>,
+) {
+    for (route, transform) in route_query.iter() {
+        if route.is_changed() && !route.is_added() && route.enabled {
+            pans.write(CameraFocusPan {
+                target: transform.translation.truncate(),
+                hold: DEFAULT_PAN_HOLD_SECS,
+            });
+        }
+    }
+}
+
+/// Move the camera from where following, the free camera and zooming put it to
+/// where the effects want it, inside the map
+pub fn apply_camera_effects(
+    time: Res<Time>,
+    bounds: Res<CameraBounds>,
+    follow: Res<CameraFollow>,
+    mut effects: ResMut<CameraEffects>,
+    mut camera_query: Query<(&mut Transform, Option<&Projection>, Option<&Camera>), With<Camera2d>>,
+) {
+    let Ok((mut transform, projection, camera)) = camera_query.single_mut() else { return; };
+    let current = transform.translation.truncate();
+    let base = match effects.applied {
+        Some((base, shown)) if shown == current => base,
+        _ => current,
+    };
+    if effects.applied.is_none() && effects.trauma == 0.0 && effects.shot.is_none() && effects.queue.is_empty() {
+        return;
+    }
+
+    let mut position = effects.advance(base, time.delta_secs());
+    let ortho = match projection {
+        Some(Projection::Orthographic(ortho)) => Some(ortho),
+        _ => None,
+    };
+    let half_view = ortho.zip(camera).and_then(|(ortho, camera)| Some(unit_half_view(camera, ortho)? * ortho.scale));
+    if let (Some(rect), Some(half_view)) = (bounds.rect, half_view) {
+        position = clamp_to_bounds(position, half_view, rect);
+    }
+    if follow.pixel_snap {
+        position = snap_to_pixels(position, ortho.map_or(1.0, |ortho| ortho.scale));
+    }
+
+    let idle = effects.trauma == 0.0 && effects.shot.is_none() && effects.queue.is_empty();
+    if idle && position == base {
+        effects.applied = None;
+    } else {
+        effects.applied = Some((base, position));
+    }
+    transform.translation.x = position.x;
+    transform.translation.y = position.y;
+}
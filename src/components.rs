@@ -23,11 +23,6 @@ pub struct GridMovement {
     pub is_moving: bool,
 }
 
-#[derive(Resource)]
-pub struct IsometricGrid {
-    pub tile_size: f32, // Size of each grid tile
-}
-
 #[derive(Component, Reflect)]
 #[reflect(Component)]
 pub struct Battery {
@@ -45,6 +40,14 @@ pub struct PowerSource {
     pub max_output: f32, // Power per second (infinite source for now)
 }
 
+// Lets a switch cut a pole off from the power network (see `grid_objects::Switch`)
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct PowerRoute {
+    pub channel: String,
+    pub enabled: bool,
+}
+
 #[derive(Component, Reflect)]
 #[reflect(Component)]
 pub struct SolarPanel {
@@ -1,8 +1,29 @@
 use bevy::prelude::*;
 
-use crate::components::{Player, CordSystem, CordMaterial, IsometricGrid};
+use crate::components::{Player, CordSystem, CordMaterial};
+use crate::isometric::MapGrid;
+use crate::elevation::{elevate_cord_path, HeightMap};
 use crate::setup::Z_CORD_LAYER;
 
+/// How much cord the robot carries. The HUD shows the laid-out cord against it;
+/// it doesn't hold the robot back.
+#[derive(Resource, Clone, Debug)]
+pub struct CordReel {
+    /// Grid steps of cord on the reel
+    pub max_tiles: usize,
+}
+
+impl Default for CordReel {
+    fn default() -> Self {
+        Self { max_tiles: 40 }
+    }
+}
+
+/// Grid steps of cord laid out from the pole to the robot
+pub fn cord_tiles_used(cord_system: &CordSystem) -> usize {
+    cord_system.trail_path.len().saturating_sub(1)
+}
+
 // Component to mark visual cord mesh segments
 #[derive(Component)]
 pub struct CordMeshSegment;
@@ -13,6 +34,8 @@ pub fn render_cord_meshes(
     mut meshes: ResMut<Assets<Mesh>>,
     cord_system: Option<ResMut<CordSystem>>,
     cord_material: Res<CordMaterial>,
+    grid: Res<MapGrid>,
+    height_map: Res<HeightMap>,
     player_query: Query<&Transform, With<Player>>,
     mesh_query: Query<Entity, With<CordMeshSegment>>,
 ) {
@@ -29,18 +52,28 @@ pub fn render_cord_meshes(
         if let Ok(player_transform) = player_query.single() {
             let player_pos = player_transform.translation.truncate();
             
-            // Build the complete path from attachment through trail to player
-            let mut full_path = cord_system.trail_path.clone();
-            
             // If trail is empty, still need to render direct line from attachment to player
             // The trail should at least have the attachment point
-            if full_path.is_empty() {
+            if cord_system.trail_path.is_empty() {
                 // This shouldn't happen if update_cord_trail runs first, but handle it anyway
                 // Just render nothing for now - next frame will have the attachment point
                 return;
             }
             
-            full_path.push(player_pos);
+            // Build the complete path from attachment through trail to player,
+            // lifted to the height of each tile and hanging down any cliffs, drawn
+            // along the ground below the tile centres
+            let mut ground_path = cord_system.trail_path.clone();
+            ground_path.push(player_pos);
+            let mut full_path: Vec<Vec2> = elevate_cord_path(&ground_path, &grid, &height_map)
+                .into_iter()
+                .map(|point| point + grid.ground_offset())
+                .collect();
+            
+            // The last point connects to the robot itself rather than the ground
+            if let Some(last) = full_path.last_mut() {
+                *last -= grid.ground_offset();
+            }
             
             // Render cord segments between consecutive points in the trail
             let cord_width = 8.0;
@@ -167,28 +200,10 @@ pub fn attach_cord_to_pole(
     cord_system.trail_path.clear();
 }
 
-fn snap_to_tile_center(world_pos: Vec2, tile_size: f32, ground_offset: f32) -> Vec2 {
-    // Convert world to grid coordinates
-    let y = world_pos.y - ground_offset;
-    let grid_x = (world_pos.x / (tile_size / 2.0) + y / (tile_size / 4.0)) / 2.0;
-    let grid_y = (y / (tile_size / 4.0) - world_pos.x / (tile_size / 2.0)) / 2.0;
-    
-    // Round to nearest grid cell
-    let grid_x_rounded = grid_x.round();
-    let grid_y_rounded = grid_y.round();
-    
-    // Convert back to world coordinates (tile center at ground plane)
-    let x = (grid_x_rounded - grid_y_rounded) * tile_size / 2.0;
-    let y = (grid_x_rounded + grid_y_rounded) * tile_size / 4.0;
-    
-    // Player is already at ground plane, no offset needed
-    Vec2::new(x, y - 16.0)
-}
-
 // Track player movement and build cord trail
 pub fn update_cord_trail(
     cord_system: Option<ResMut<CordSystem>>,
-    grid: Res<IsometricGrid>,
+    grid: Res<MapGrid>,
     player_query: Query<&Transform, With<Player>>,
     pole_query: Query<&Transform, With<crate::components::Pole>>,
 ) {
@@ -202,8 +217,9 @@ pub fn update_cord_trail(
             let pole_pos = pole_transform.translation.truncate();
             
             // Snap both positions to tile centers at ground plane
-            let player_tile_center = snap_to_tile_center(player_pos, grid.tile_size, 0.0);
-            let pole_tile_center = snap_to_tile_center(pole_pos, grid.tile_size, 32.0);
+            // (map objects such as poles already stand on their tile centre)
+            let player_tile_center = grid.snap_to_tile_center(player_pos);
+            let pole_tile_center = grid.snap_to_tile_center(pole_pos);
             
             // Initialize trail with pole tile center if empty
             if cord_system.trail_path.is_empty() {
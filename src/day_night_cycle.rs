@@ -27,6 +27,12 @@ impl DayNightCycle {
         self.time_of_day / self.day_duration
     }
     
+    /// Time of day on a 24-hour clock, as hours and minutes
+    pub fn clock(&self) -> (u32, u32) {
+        let minutes = (self.normalized_time().rem_euclid(1.0) * 24.0 * 60.0).round() as u32 % (24 * 60);
+        (minutes / 60, minutes % 60)
+    }
+
     /// Check if it's currently day time
     pub fn is_day(&self) -> bool {
         let normalized = self.normalized_time();
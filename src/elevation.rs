@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy_ecs_tiled::prelude::*;
+
+use crate::components::Player;
+use crate::isometric::{GridDirection, GridLayout, MapGrid, TilePos};
+use crate::setup::PLAYER_SPRITE_SIZE;
+use crate::tiled_map::for_each_map_tile;
+
+/// World-space height of a single elevation level, in pixels
+pub const LEVEL_HEIGHT: f32 = 16.0;
+
+/// How fast the drawn height of an entity catches up with the ground it stands on, in pixels per second
+pub const ELEVATION_CHANGE_SPEED: f32 = 96.0;
+
+/// Height information for a single tile
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TileElevation {
+    pub level: i32,
+    /// For ramps, the direction pointing uphill
+    pub ramp: Option<GridDirection>,
+}
+
+/// Height level of every tile in the loaded map, keyed by Tiled tile coordinates
+#[derive(Resource, Default)]
+pub struct HeightMap {
+    pub tiles: HashMap<TilePos, TileElevation>,
+}
+
+impl HeightMap {
+    pub fn get(&self, tile: TilePos) -> TileElevation {
+        self.tiles.get(&tile).copied().unwrap_or_default()
+    }
+
+    pub fn level(&self, tile: TilePos) -> i32 {
+        self.get(tile).level
+    }
+
+    /// Highest level of any tile, 0 for a flat map
+    pub fn max_level(&self) -> i32 {
+        self.tiles.values().map(|elevation| elevation.level).max().unwrap_or(0).max(0)
+    }
+
+    /// Vertical world offset for things standing on a tile
+    pub fn height_offset(&self, tile: TilePos) -> f32 {
+        self.level(tile) as f32 * LEVEL_HEIGHT
+    }
+
+    /// Whether a single grid step between neighbouring tiles is possible.
+    /// Tiles on the same level are always connected; one level of difference
+    /// needs a ramp on the lower tile that rises towards the higher one. Anything
+    /// else is a cliff. Ramps are matched by neighbour rather than by tile step,
+    /// since on staggered and hexagonal maps the step changes from row to row.
+    pub fn can_step(&self, layout: &GridLayout, from: TilePos, to: TilePos) -> bool {
+        let from_elevation = self.get(from);
+        let to_elevation = self.get(to);
+        let rises_to = |lower: TilePos, ramp: Option<GridDirection>, higher: TilePos| {
+            ramp.is_some_and(|uphill| layout.neighbor_in(lower, uphill) == higher)
+        };
+
+        match to_elevation.level - from_elevation.level {
+            0 => true,
+            1 => rises_to(from, from_elevation.ramp, to),
+            -1 => rises_to(to, to_elevation.ramp, from),
+            _ => false,
+        }
+    }
+
+    /// Build the height map from a Tiled map. A tile's level comes from its own
+    /// `height` property, then the layer's `height` property, then the layer's
+    /// vertical pixel offset. Where layers overlap, the highest level wins.
+    pub fn from_tiled_map(map: &tiled::Map) -> Self {
+        let mut tiles: HashMap<TilePos, TileElevation> = HashMap::new();
+        for_each_map_tile(map, |layer, pos, layer_tile| {
+            let layer_level = match layer.properties.get("height") {
+                Some(tiled::PropertyValue::IntValue(level)) => *level,
+                _ => (-layer.offset_y / LEVEL_HEIGHT).round() as i32,
+            };
+
+            let tile = layer_tile.get_tile();
+            let tile_level = tile.as_ref().and_then(|tile| match tile.properties.get("height") {
+                Some(tiled::PropertyValue::IntValue(level)) => Some(*level),
+                _ => None,
+            });
+            let ramp = tile.as_ref().and_then(|tile| match tile.properties.get("ramp") {
+                Some(tiled::PropertyValue::StringValue(name)) => GridDirection::from_name(name),
+                _ => None,
+            });
+
+            let elevation = TileElevation {
+                level: tile_level.unwrap_or(layer_level),
+                ramp,
+            };
+            let covered = tiles
+                .get(&pos)
+                .is_some_and(|existing| existing.level > elevation.level);
+            if !covered {
+                tiles.insert(pos, elevation);
+            }
+        });
+        Self { tiles }
+    }
+}
+
+/// Drawn height of an entity above the ground plane. The entity's `Transform`
+/// stays on the ground plane; only its sprite is lifted.
+#[derive(Component, Default)]
+pub struct Elevation {
+    pub height: f32,
+}
+
+/// Rebuild the `HeightMap` whenever a Tiled map finishes loading or is modified
+pub fn build_height_map(
+    mut asset_events: MessageReader<AssetEvent<TiledMapAsset>>,
+    map_assets: Res<Assets<TiledMapAsset>>,
+    mut height_map: ResMut<HeightMap>,
+) {
+    for event in asset_events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event else {
+            continue;
+        };
+        if let Some(map_asset) = map_assets.get(*id) {
+            *height_map = HeightMap::from_tiled_map(&map_asset.map);
+        }
+    }
+}
+
+/// Move the player's drawn height towards the level of the tile it stands on
+/// and lift its sprite accordingly
+pub fn update_player_elevation(
+    time: Res<Time>,
+    grid: Res<MapGrid>,
+    height_map: Res<HeightMap>,
+    mut player_query: Query<(&Transform, &mut Elevation, &mut Anchor), With<Player>>,
+) {
+    for (transform, mut elevation, mut anchor) in player_query.iter_mut() {
+        let tile = grid.world_to_tile(transform.translation.truncate());
+        let target_height = height_map.height_offset(tile);
+
+        let max_change = ELEVATION_CHANGE_SPEED * time.delta_secs();
+        let difference = target_height - elevation.height;
+        elevation.height += difference.clamp(-max_change, max_change);
+
+        // Anchors are normalized to the sprite size, and a lower anchor draws the sprite higher
+        anchor.0.y = -elevation.height / PLAYER_SPRITE_SIZE;
+    }
+}
+
+/// Lift a ground-plane cord path to the height of the tiles it lies on. Where the
+/// path steps off a cliff the cord hangs straight down the face before carrying on
+/// along the lower level; across ramps it simply follows the slope.
+pub fn elevate_cord_path(path: &[Vec2], grid: &MapGrid, height_map: &HeightMap) -> Vec<Vec2> {
+    let mut elevated = Vec::with_capacity(path.len());
+    let mut previous: Option<(Vec2, TilePos)> = None;
+
+    for &point in path {
+        let tile = grid.world_to_tile(point);
+        let height = height_map.height_offset(tile);
+
+        if let Some((previous_point, previous_tile)) = previous {
+            let previous_height = height_map.height_offset(previous_tile);
+            if height != previous_height && !height_map.can_step(&grid.layout, previous_tile, tile) {
+                // Cross to the tile edge, then drop (or climb) vertically down the cliff face
+                let edge = (previous_point + point) / 2.0;
+                elevated.push(edge + Vec2::Y * previous_height);
+                elevated.push(edge + Vec2::Y * height);
+            }
+        }
+
+        elevated.push(point + Vec2::Y * height);
+        previous = Some((point, tile));
+    }
+
+    elevated
+}
@@ -0,0 +1,291 @@
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+use bevy::asset::RenderAssetUsages;
+use bevy::mesh::{Indices, PrimitiveTopology};
+use bevy::prelude::*;
+use bevy_ecs_tiled::prelude::*;
+use bevy_light_2d::prelude::*;
+
+use crate::components::Player;
+use crate::elevation::HeightMap;
+use crate::isometric::{MapGrid, TilePos};
+use crate::levels::LevelRegistry;
+use crate::setup::Z_FOG_LAYER;
+use crate::tiled_map::for_each_map_tile;
+
+/// Folder the explored tiles of every level are saved in
+pub const FOG_SAVE_DIR: &str = "saves";
+
+const FOG_SAVE_HEADER: &str = "balthazar fog v1";
+
+const UNEXPLORED_COLOR: Color = Color::BLACK;
+const EXPLORED_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.6);
+
+/// How much of the map the robot has seen at a tile
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FogState {
+    #[default]
+    Unexplored,
+    /// Seen before, but not lit by the robot right now
+    Explored,
+    Visible,
+}
+
+/// What the robot has seen of the current level
+#[derive(Resource, Default)]
+pub struct FogOfWar {
+    /// Level the explored tiles belong to; they are saved under its name
+    pub level: Option<String>,
+    pub explored: HashSet<TilePos>,
+    pub visible: HashSet<TilePos>,
+    /// Robot tile and light radius the visible tiles were worked out from
+    pub viewpoint: Option<(TilePos, f32)>,
+}
+
+impl FogOfWar {
+    pub fn state(&self, tile: TilePos) -> FogState {
+        if self.visible.contains(&tile) {
+            FogState::Visible
+        } else if self.explored.contains(&tile) {
+            FogState::Explored
+        } else {
+            FogState::Unexplored
+        }
+    }
+
+    /// Replace the visible tiles. Everything that has been visible stays explored.
+    pub fn reveal(&mut self, visible: HashSet<TilePos>) {
+        self.explored.extend(visible.iter().copied());
+        self.visible = visible;
+    }
+
+    /// Explored tiles in the save file format: a header, then one `x,y` per line
+    pub fn to_save_string(&self) -> String {
+        let mut tiles: Vec<TilePos> = self.explored.iter().copied().collect();
+        tiles.sort();
+        let mut text = format!("{FOG_SAVE_HEADER}\n");
+        for tile in tiles {
+            text.push_str(&format!("{},{}\n", tile.x, tile.y));
+        }
+        text
+    }
+
+    /// Read the explored tiles back from a save file. `None` if it isn't one.
+    pub fn parse_explored(text: &str) -> Option<HashSet<TilePos>> {
+        let mut lines = text.lines();
+        if lines.next()?.trim() != FOG_SAVE_HEADER {
+            return None;
+        }
+        let explored = lines
+            .filter_map(|line| {
+                let (x, y) = line.trim().split_once(',')?;
+                Some(TilePos::new(x.trim().parse().ok()?, y.trim().parse().ok()?))
+            })
+            .collect();
+        Some(explored)
+    }
+}
+
+/// Where the explored tiles of a level are saved
+pub fn fog_save_path(level: &str) -> PathBuf {
+    Path::new(FOG_SAVE_DIR).join(format!("{level}.fog"))
+}
+
+fn save_fog(fog: &FogOfWar) {
+    let Some(level) = &fog.level else { return; };
+    let path = fog_save_path(level);
+    let result = std::fs::create_dir_all(FOG_SAVE_DIR).and_then(|_| std::fs::write(&path, fog.to_save_string()));
+    if let Err(err) = result {
+        warn!("Couldn't save the fog of war to '{}': {err}", path.display());
+    }
+}
+
+fn load_fog(level: &str) -> HashSet<TilePos> {
+    let path = fog_save_path(level);
+    let Ok(text) = std::fs::read_to_string(&path) else {
+        return HashSet::new();
+    };
+    FogOfWar::parse_explored(&text).unwrap_or_else(|| {
+        warn!("Ignoring '{}': not a fog of war save", path.display());
+        HashSet::new()
+    })
+}
+
+/// Whether nothing taller than `eye_level` stands between two tiles. The tiles
+/// at either end don't count, so the face of a wall can be seen.
+pub fn has_line_of_sight(
+    grid: &MapGrid,
+    height_map: &HeightMap,
+    eye_level: i32,
+    from: TilePos,
+    to: TilePos,
+) -> bool {
+    let start = grid.tile_to_world(from);
+    let end = grid.tile_to_world(to);
+    // Sample often enough that no tile along the way is skipped
+    let sample_spacing = grid.tile_width.min(grid.tile_height) / 4.0;
+    let samples = (start.distance(end) / sample_spacing).ceil() as i32;
+    (1..samples)
+        .map(|sample| grid.world_to_tile(start.lerp(end, sample as f32 / samples as f32)))
+        .filter(|tile| *tile != from && *tile != to)
+        .all(|tile| height_map.level(tile) <= eye_level)
+}
+
+/// Tiles lit by a light of `radius` (in world units) carried by the robot on `from`
+pub fn visible_tiles(grid: &MapGrid, height_map: &HeightMap, from: TilePos, radius: f32) -> HashSet<TilePos> {
+    let eye = grid.tile_to_world(from);
+    let eye_level = height_map.level(from);
+
+    let mut in_range = HashSet::from([from]);
+    let mut queue = VecDeque::from([from]);
+    while let Some(tile) = queue.pop_front() {
+        for neighbor in grid.neighbors(tile) {
+            if grid.tile_to_world(neighbor).distance(eye) <= radius && in_range.insert(neighbor) {
+                queue.push_back(neighbor);
+            }
+        }
+    }
+
+    in_range
+        .into_iter()
+        .filter(|tile| has_line_of_sight(grid, height_map, eye_level, from, *tile))
+        .collect()
+}
+
+/// Fog overlay covering a single map tile
+#[derive(Component)]
+pub struct FogTile {
+    pub tile: TilePos,
+}
+
+#[derive(Resource)]
+pub struct FogMaterials {
+    pub unexplored: Handle<ColorMaterial>,
+    pub explored: Handle<ColorMaterial>,
+}
+
+pub fn setup_fog_of_war(mut commands: Commands, mut materials: ResMut<Assets<ColorMaterial>>) {
+    commands.insert_resource(FogMaterials {
+        unexplored: materials.add(UNEXPLORED_COLOR),
+        explored: materials.add(EXPLORED_COLOR),
+    });
+}
+
+/// Mesh in the shape of a tile, centred on the origin
+fn tile_mesh(grid: &MapGrid) -> Mesh {
+    let center = grid.tile_to_world(TilePos::ZERO);
+    let outline = grid.tile_outline(TilePos::ZERO);
+    let positions: Vec<[f32; 3]> = outline
+        .iter()
+        .map(|corner| (*corner - center).extend(0.0).to_array())
+        .collect();
+    // The outline runs clockwise, so fan out from its first corner the other way round
+    let indices = (1..outline.len() as u32 - 1).flat_map(|i| [0, i + 1, i]).collect();
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::RENDER_WORLD)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_indices(Indices::U32(indices))
+}
+
+/// Cover every tile of a map with fog whenever it finishes loading or is modified
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_fog_tiles(
+    mut commands: Commands,
+    mut asset_events: MessageReader<AssetEvent<TiledMapAsset>>,
+    map_assets: Res<Assets<TiledMapAsset>>,
+    grid: Res<MapGrid>,
+    height_map: Res<HeightMap>,
+    fog_materials: Res<FogMaterials>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut fog: ResMut<FogOfWar>,
+    fog_query: Query<Entity, With<FogTile>>,
+) {
+    for event in asset_events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event else {
+            continue;
+        };
+        let Some(map_asset) = map_assets.get(*id) else { continue; };
+
+        for entity in fog_query.iter() {
+            commands.entity(entity).despawn();
+        }
+        let mut tiles = HashSet::new();
+        for_each_map_tile(&map_asset.map, |_layer, tile, _| {
+            tiles.insert(tile);
+        });
+
+        let mesh = meshes.add(tile_mesh(&grid));
+        for tile in tiles {
+            let position = grid.tile_to_world(tile) + Vec2::Y * height_map.height_offset(tile);
+            commands.spawn((
+                FogTile { tile },
+                Mesh2d(mesh.clone()),
+                MeshMaterial2d(fog_materials.unexplored.clone()),
+                Transform::from_translation(position.extend(Z_FOG_LAYER)),
+            ));
+        }
+        // Work the visible tiles out again for the new overlay
+        fog.viewpoint = None;
+    }
+}
+
+/// Light up the tiles around the robot, as far as its light reaches
+pub fn update_fog_of_war(
+    grid: Res<MapGrid>,
+    height_map: Res<HeightMap>,
+    mut fog: ResMut<FogOfWar>,
+    player_query: Query<(&Transform, &PointLight2d), With<Player>>,
+) {
+    let Ok((transform, light)) = player_query.single() else { return; };
+    let viewpoint = (grid.world_to_tile(transform.translation.truncate()), light.radius);
+    if fog.viewpoint == Some(viewpoint) && !height_map.is_changed() {
+        return;
+    }
+    fog.viewpoint = Some(viewpoint);
+    let visible = visible_tiles(&grid, &height_map, viewpoint.0, viewpoint.1);
+    fog.reveal(visible);
+}
+
+/// Hide unexplored tiles, dim explored ones and clear the ones in sight
+pub fn update_fog_tiles(
+    fog: Res<FogOfWar>,
+    fog_materials: Res<FogMaterials>,
+    mut fog_query: Query<(Ref<FogTile>, &mut MeshMaterial2d<ColorMaterial>, &mut Visibility)>,
+) {
+    for (fog_tile, mut material, mut visibility) in fog_query.iter_mut() {
+        if !fog.is_changed() && !fog_tile.is_added() {
+            continue;
+        }
+        let (handle, shown) = match fog.state(fog_tile.tile) {
+            FogState::Unexplored => (&fog_materials.unexplored, Visibility::Inherited),
+            FogState::Explored => (&fog_materials.explored, Visibility::Inherited),
+            FogState::Visible => (&fog_materials.unexplored, Visibility::Hidden),
+        };
+        if material.0 != *handle {
+            material.0 = handle.clone();
+        }
+        visibility.set_if_neq(shown);
+    }
+}
+
+/// Keep each level's explored tiles on disk: save them when the robot leaves a
+/// level and load them when it arrives
+pub fn persist_fog_of_war(registry: Res<LevelRegistry>, mut fog: ResMut<FogOfWar>) {
+    let level = &registry.current_level().name;
+    if fog.level.as_ref() == Some(level) {
+        return;
+    }
+    save_fog(&fog);
+    *fog = FogOfWar {
+        level: Some(level.clone()),
+        explored: load_fog(level),
+        ..default()
+    };
+}
+
+/// Save the explored tiles when the game closes
+pub fn save_fog_on_exit(mut exit_events: MessageReader<AppExit>, fog: Res<FogOfWar>) {
+    if exit_events.read().count() > 0 {
+        save_fog(&fog);
+    }
+}
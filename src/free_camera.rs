@@ -0,0 +1,152 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::camera::{clamp_to_bounds, damp_towards, snap_to_pixels, unit_half_view, CameraBounds, CameraFollow};
+use crate::components::{Player, SystemToggles};
+use crate::player_movement::movement_keys_direction;
+
+/// Glide the camera back to the robot while it is set free
+pub const RETURN_TO_ROBOT_KEY: KeyCode = KeyCode::KeyF;
+const DRAG_BUTTON: MouseButton = MouseButton::Middle;
+
+/// How the camera moves while `SystemToggles::camera_follow` is off
+#[derive(Resource, Clone, Debug)]
+pub struct FreeCamera {
+    /// Panning speed for the movement keys and edge scrolling, in screen pixels per second
+    pub pan_speed: f32,
+    /// Width of the band along the window edges that scrolls the view, in pixels
+    pub edge_margin: f32,
+    /// Cursor position while dragging with the middle mouse button
+    pub drag_from: Option<Vec2>,
+    /// Gliding back to the robot after the hotkey was pressed
+    pub returning: bool,
+    /// Point the camera is gliding to, such as a spot clicked on the minimap
+    pub pan_to: Option<Vec2>,
+}
+
+impl Default for FreeCamera {
+    fn default() -> Self {
+        Self {
+            pan_speed: 600.0,
+            edge_margin: 16.0,
+            drag_from: None,
+            returning: false,
+            pan_to: None,
+        }
+    }
+}
+
+/// Screen direction to scroll in with the cursor near the edges of a window of
+/// `window_size` (cursor coordinates start at the top left)
+pub fn edge_scroll_direction(cursor: Vec2, window_size: Vec2, margin: f32) -> Vec2 {
+    let axis = |position: f32, size: f32| {
+        if position < margin {
+            -1.0
+        } else if position > size - margin {
+            1.0
+        } else {
+            0.0
+        }
+    };
+    // Screen y points down, world y up
+    Vec2::new(axis(cursor.x, window_size.x), -axis(cursor.y, window_size.y))
+}
+
+/// World movement of the camera that keeps the point under a dragging cursor
+/// under it, for a cursor movement in screen pixels
+pub fn drag_pan(cursor_delta: Vec2, world_per_pixel: f32) -> Vec2 {
+    Vec2::new(-cursor_delta.x, cursor_delta.y) * world_per_pixel
+}
+
+/// Move the camera by hand while it isn't following the robot: drag with the
+/// middle mouse button, push the cursor against a window edge or use the
+/// movement keys. The return hotkey glides it back to the robot and turns
+/// following back on once there, and `pan_to` glides it to a point.
+#[allow(clippy::too_many_arguments)]
+pub fn free_camera(
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    bounds: Res<CameraBounds>,
+    mut free: ResMut<FreeCamera>,
+    mut follow: ResMut<CameraFollow>,
+    player_query: Query<&Transform, (With<Player>, Without<Camera2d>)>,
+    mut camera_query: Query<(&mut Transform, Option<&Projection>, Option<&Camera>), (With<Camera2d>, Without<Player>)>,
+    mut toggles: ResMut<SystemToggles>,
+) {
+    if toggles.camera_follow {
+        free.drag_from = None;
+        free.returning = false;
+        free.pan_to = None;
+        return;
+    }
+    let Ok((mut camera_transform, projection, camera)) = camera_query.single_mut() else { return; };
+    let ortho = match projection {
+        Some(Projection::Orthographic(ortho)) => Some(ortho),
+        _ => None,
+    };
+    let world_per_pixel = ortho.map_or(1.0, |ortho| ortho.scale);
+    let mut position = follow.focus.unwrap_or(camera_transform.translation.truncate());
+
+    let window = window_query.single().ok();
+    let cursor = window.and_then(Window::cursor_position);
+    let mut panned = false;
+
+    if mouse.pressed(DRAG_BUTTON) {
+        if let (Some(from), Some(cursor)) = (free.drag_from, cursor) {
+            position += drag_pan(cursor - from, world_per_pixel);
+            panned |= cursor != from;
+        }
+        free.drag_from = cursor;
+    } else {
+        free.drag_from = None;
+    }
+
+    let mut direction = movement_keys_direction(&keys, ButtonInput::pressed);
+    // Edge scrolling only while the cursor is in the window and not dragging
+    if let (Some(window), Some(cursor), None) = (window, cursor, free.drag_from) {
+        direction += edge_scroll_direction(cursor, window.size(), free.edge_margin);
+    }
+    if direction != Vec2::ZERO {
+        position += direction.normalize() * free.pan_speed * world_per_pixel * time.delta_secs();
+        panned = true;
+    }
+
+    if keys.just_pressed(RETURN_TO_ROBOT_KEY) {
+        free.returning = true;
+        free.pan_to = None;
+    }
+    if panned {
+        free.returning = false;
+        free.pan_to = None;
+    }
+    let glide_target = if free.returning {
+        player_query.single().ok().map(|player_transform| player_transform.translation.truncate())
+    } else {
+        free.pan_to
+    };
+    if let Some(target) = glide_target {
+        position = damp_towards(position, target, follow.damping, time.delta_secs());
+        if position.distance(target) < 0.5 {
+            position = target;
+            // Back on the robot, the camera follows it again and the keys move it
+            if free.returning {
+                toggles.camera_follow = true;
+            }
+            free.returning = false;
+            free.pan_to = None;
+        }
+    }
+
+    let half_view = ortho.zip(camera).and_then(|(ortho, camera)| Some(unit_half_view(camera, ortho)? * ortho.scale));
+    if let (Some(rect), Some(half_view)) = (bounds.rect, half_view) {
+        position = clamp_to_bounds(position, half_view, rect);
+    }
+    // Following picks up from here when it's turned back on
+    follow.focus = Some(position);
+
+    let position = if follow.pixel_snap { snap_to_pixels(position, world_per_pixel) } else { position };
+    camera_transform.translation.x = position.x;
+    camera_transform.translation.y = position.y;
+}
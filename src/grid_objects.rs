@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::components::{CordSystem, GridMovement, Player, PowerRoute};
+use crate::elevation::HeightMap;
+use crate::isometric::{MapGrid, TilePos};
+use crate::player_movement::GridStepCompleted;
+
+// Grid objects are placed in Tiled as point objects at tile centres on an object
+// layer, with one of the classes below (e.g. `balthazar::grid_objects::Door`).
+
+/// A crate that slides one tile when the robot walks into it
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct PushableCrate;
+
+/// Pressure switch that toggles every door and power route on its channel
+/// when the robot steps onto it
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct Switch {
+    pub channel: String,
+}
+
+/// Door that blocks movement and the cord while closed
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct Door {
+    pub channel: String,
+    pub open: bool,
+}
+
+/// Marks map objects that have been snapped onto the tile grid
+#[derive(Component)]
+pub struct GridPlaced;
+
+// Placeholder visuals for objects that don't come with a tile sprite
+const CRATE_COLOR: Color = Color::srgb(0.55, 0.35, 0.15);
+const SWITCH_COLOR: Color = Color::srgb(0.9, 0.8, 0.2);
+const DOOR_CLOSED_COLOR: Color = Color::srgb(0.4, 0.4, 0.45);
+const DOOR_OPEN_COLOR: Color = Color::srgba(0.4, 0.4, 0.45, 0.25);
+
+/// Give a grid object spawned from the map what it needs to live on the grid:
+/// crates can be moved, and everything gets a placeholder sprite unless the
+/// Tiled object already brought one
+pub fn prepare_grid_object(
+    entity_commands: &mut EntityCommands,
+    is_crate: bool,
+    is_switch: bool,
+    door: Option<&Door>,
+) {
+    if is_crate {
+        entity_commands.insert(GridMovement {
+            target_position: None,
+            move_speed: 400.0,
+            is_moving: false,
+        });
+        entity_commands.insert_if_new(Sprite::from_color(CRATE_COLOR, Vec2::new(32.0, 32.0)));
+    } else if is_switch {
+        entity_commands.insert_if_new(Sprite::from_color(SWITCH_COLOR, Vec2::new(16.0, 8.0)));
+    } else if let Some(door) = door {
+        let color = if door.open { DOOR_OPEN_COLOR } else { DOOR_CLOSED_COLOR };
+        entity_commands.insert_if_new(Sprite::from_color(color, Vec2::new(32.0, 48.0)));
+    }
+}
+
+/// Read-write access to everything that can stand in the way of a grid step
+#[derive(SystemParam)]
+pub struct GridObstacles<'w, 's> {
+    crates: Query<
+        'w,
+        's,
+        (Entity, &'static Transform, &'static mut GridMovement),
+        (With<PushableCrate>, Without<Player>),
+    >,
+    doors: Query<'w, 's, (&'static Transform, &'static Door), Without<Player>>,
+}
+
+impl GridObstacles<'_, '_> {
+    /// Tiles that are currently solid. Moving crates occupy the tile they are heading to.
+    pub fn blocked_tiles(&self, grid: &MapGrid) -> HashMap<TilePos, Option<Entity>> {
+        let mut blocked = HashMap::new();
+        for (entity, transform, grid_movement) in self.crates.iter() {
+            let position = grid_movement
+                .target_position
+                .unwrap_or(transform.translation.truncate());
+            blocked.insert(grid.world_to_tile(position), Some(entity));
+        }
+        for (transform, door) in self.doors.iter() {
+            if !door.open {
+                blocked.insert(grid.world_to_tile(transform.translation.truncate()), None);
+            }
+        }
+        blocked
+    }
+
+    /// Check whether a step from `from` to `to` is possible, pushing a crate
+    /// out of the way if there is one on `to` and the tile beyond it is free.
+    pub fn try_enter(
+        &mut self,
+        grid: &MapGrid,
+        height_map: &HeightMap,
+        from: TilePos,
+        to: TilePos,
+    ) -> bool {
+        let blocked = self.blocked_tiles(grid);
+        match blocked.get(&to) {
+            None => true,
+            // Closed door
+            Some(None) => false,
+            Some(Some(crate_entity)) => {
+                let beyond = grid.continue_line(from, to);
+                if blocked.contains_key(&beyond) || !height_map.can_step(&grid.layout, to, beyond) {
+                    return false;
+                }
+                let Ok((_, _, mut grid_movement)) = self.crates.get_mut(*crate_entity) else {
+                    return false;
+                };
+                if grid_movement.is_moving {
+                    return false;
+                }
+                grid_movement.target_position = Some(grid.tile_to_world(beyond));
+                grid_movement.is_moving = true;
+                true
+            }
+        }
+    }
+}
+
+/// Toggle doors and power routes when the robot steps onto a switch. Doors that
+/// the cord runs through are jammed open.
+pub fn activate_switches(
+    mut step_events: MessageReader<GridStepCompleted>,
+    grid: Res<MapGrid>,
+    cord_system: Option<Res<CordSystem>>,
+    player_query: Query<(), With<Player>>,
+    switch_query: Query<(&Transform, &Switch)>,
+    mut door_query: Query<(&Transform, &mut Door)>,
+    mut route_query: Query<&mut PowerRoute>,
+) {
+    for event in step_events.read() {
+        if !player_query.contains(event.entity) {
+            continue;
+        }
+        let tile = grid.world_to_tile(event.position);
+
+        for (switch_transform, switch) in switch_query.iter() {
+            if grid.world_to_tile(switch_transform.translation.truncate()) != tile {
+                continue;
+            }
+
+            for (door_transform, mut door) in door_query.iter_mut() {
+                if door.channel != switch.channel {
+                    continue;
+                }
+                let door_tile = grid.world_to_tile(door_transform.translation.truncate());
+                let cord_through_door = cord_system.as_ref().is_some_and(|cord_system| {
+                    cord_system
+                        .trail_path
+                        .iter()
+                        .any(|point| grid.world_to_tile(*point) == door_tile)
+                });
+                if door.open && cord_through_door {
+                    println!("Door on channel '{}' is jammed open by the cord!", door.channel);
+                    continue;
+                }
+                door.open = !door.open;
+            }
+
+            for mut route in route_query.iter_mut() {
+                if route.channel == switch.channel {
+                    route.enabled = !route.enabled;
+                }
+            }
+        }
+    }
+}
+
+/// Show doors as solid while closed and faded while open
+pub fn update_door_visuals(mut door_query: Query<(&Door, &mut Sprite), Changed<Door>>) {
+    for (door, mut sprite) in door_query.iter_mut() {
+        sprite.color = if door.open { DOOR_OPEN_COLOR } else { DOOR_CLOSED_COLOR };
+    }
+}
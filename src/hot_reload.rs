@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_ecs_tiled::prelude::*;
+
+use crate::components::{CordSystem, GridMovement, Player, Pole, PowerRoute};
+use crate::grid_objects::{Door, GridPlaced, PushableCrate};
+use crate::isometric::{MapGrid, TilePos};
+use crate::map_objects::{MapObjectId, MapObjectIndex, PlayerSpawn};
+use crate::setup::Z_GAME_LAYER;
+use crate::undo::UndoHistory;
+
+// When the map file changes on disk (with Bevy's `file_watcher` feature),
+// bevy_ecs_tiled respawns the map's entities, so every `Entity` pointing at a map
+// object goes stale. Before the reload the game state is saved in terms that
+// survive it, Tiled object ids and tile coordinates; once the new objects are
+// placed it is put back and the cord is plugged into the new pole entity.
+
+/// Classes whose entities are spawned again by a reload
+const RESPAWNED_CLASSES: [&str; 7] = [
+    "Pole",
+    "PowerSource",
+    "SolarPanel",
+    "PushableCrate",
+    "Switch",
+    "Door",
+    "PlayerSpawn",
+];
+
+/// A map object as it can be found again after a reload: by its Tiled id, or by
+/// its tile if it had none
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SavedObject {
+    pub id: Option<u32>,
+    pub tile: TilePos,
+}
+
+/// Game state taken just before the map reloads
+#[derive(Clone, Debug)]
+pub struct ReloadSnapshot {
+    /// Grid the saved world positions were measured on
+    pub grid: MapGrid,
+    pub player_tile: TilePos,
+    /// Every pole, keyed by its entity before the reload
+    pub poles: HashMap<Entity, SavedObject>,
+    pub attached_pole: Option<Entity>,
+    pub trail: Vec<TilePos>,
+    pub doors: HashMap<u32, bool>,
+    pub power_routes: HashMap<u32, bool>,
+    pub crates: HashMap<u32, TilePos>,
+    /// Tiled id of every crate, keyed by its entity before the reload, so the
+    /// undo history can follow crates to their new entities
+    pub crate_ids: HashMap<Entity, u32>,
+}
+
+/// State waiting to be restored once the reloaded map's objects are in
+#[derive(Resource, Default)]
+pub struct MapReload {
+    pub pending: Option<ReloadSnapshot>,
+}
+
+impl MapReload {
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+}
+
+/// Find a pole again after a reload, preferring its Tiled id over its tile
+pub fn relink_pole(saved: SavedObject, poles: &[(Entity, Option<u32>, TilePos)]) -> Option<Entity> {
+    let by_id = saved
+        .id
+        .and_then(|id| poles.iter().find(|(_, pole_id, _)| *pole_id == Some(id)));
+    by_id
+        .or_else(|| poles.iter().find(|(_, _, tile)| *tile == saved.tile))
+        .map(|(entity, _, _)| *entity)
+}
+
+/// Save the game state when the loaded map is modified, and clear out the
+/// objects placed from the old version: they have left the map hierarchy, so the
+/// reload wouldn't despawn them.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn save_state_for_reload(
+    mut commands: Commands,
+    mut asset_events: MessageReader<AssetEvent<TiledMapAsset>>,
+    mut reload: ResMut<MapReload>,
+    grid: Res<MapGrid>,
+    cord_system: Option<Res<CordSystem>>,
+    map_query: Query<&TiledMap>,
+    player_query: Query<&Transform, With<Player>>,
+    placed_query: Query<
+        (
+            Entity,
+            &Transform,
+            Option<&MapObjectId>,
+            (Has<Pole>, Has<PushableCrate>, Option<&Door>, Option<&PowerRoute>),
+        ),
+        With<GridPlaced>,
+    >,
+) {
+    let modified = asset_events.read().any(|event| match event {
+        AssetEvent::Modified { id } => map_query.iter().any(|map| map.0.id() == *id),
+        _ => false,
+    });
+    // A reload that lands before the previous one was restored keeps the older state
+    if !modified || reload.is_pending() {
+        return;
+    }
+
+    let player_tile = player_query
+        .single()
+        .map(|transform| grid.world_to_tile(transform.translation.truncate()))
+        .unwrap_or_default();
+    let mut snapshot = ReloadSnapshot {
+        grid: grid.clone(),
+        player_tile,
+        poles: HashMap::new(),
+        attached_pole: cord_system.as_ref().and_then(|cord_system| cord_system.attached_pole),
+        trail: cord_system
+            .as_ref()
+            .map(|cord_system| cord_system.trail_path.iter().map(|point| grid.world_to_tile(*point)).collect())
+            .unwrap_or_default(),
+        doors: HashMap::new(),
+        power_routes: HashMap::new(),
+        crates: HashMap::new(),
+        crate_ids: HashMap::new(),
+    };
+
+    for (entity, transform, object_id, (is_pole, is_crate, door, route)) in placed_query.iter() {
+        let tile = grid.world_to_tile(transform.translation.truncate());
+        let id = object_id.map(|object_id| object_id.0);
+        if is_pole {
+            snapshot.poles.insert(entity, SavedObject { id, tile });
+        }
+        if let Some(id) = id {
+            if is_crate {
+                snapshot.crates.insert(id, tile);
+                snapshot.crate_ids.insert(entity, id);
+            }
+            if let Some(door) = door {
+                snapshot.doors.insert(id, door.open);
+            }
+            if let Some(route) = route {
+                snapshot.power_routes.insert(id, route.enabled);
+            }
+        }
+        commands.entity(entity).despawn();
+    }
+
+    info!("Map changed on disk, reloading");
+    reload.pending = Some(snapshot);
+}
+
+/// Put the saved state back once the reloaded map's objects have been placed:
+/// the robot returns to its tile, crates, doors and power routes to theirs, and
+/// the cord (along with every undo step) is re-linked to the new pole entities.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn restore_state_after_reload(
+    mut reload: ResMut<MapReload>,
+    grid: Res<MapGrid>,
+    index: Res<MapObjectIndex>,
+    mut history: ResMut<UndoHistory>,
+    cord_system: Option<ResMut<CordSystem>>,
+    added_query: Query<(), Or<(Added<MapObjectId>, Added<PlayerSpawn>)>>,
+    mut player_query: Query<(&mut Transform, &mut GridMovement), (With<Player>, Without<GridPlaced>)>,
+    mut placed_query: Query<
+        (Entity, Option<&MapObjectId>, &mut Transform, Has<Pole>, Option<&mut Door>, Option<&mut PowerRoute>),
+        With<GridPlaced>,
+    >,
+) {
+    if !reload.is_pending() {
+        return;
+    }
+    // Wait for the new objects, unless the new version of the map has none
+    let expects_objects = index
+        .objects
+        .iter()
+        .any(|object| RESPAWNED_CLASSES.contains(&object.short_class()));
+    if expects_objects && added_query.is_empty() {
+        return;
+    }
+    let Some(snapshot) = reload.pending.take() else { return; };
+
+    for (mut transform, mut grid_movement) in player_query.iter_mut() {
+        let position = grid.tile_to_world(snapshot.player_tile);
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+        grid_movement.target_position = None;
+        grid_movement.is_moving = false;
+    }
+
+    let mut poles = Vec::new();
+    let mut new_crates = HashMap::new();
+    for (entity, object_id, mut transform, is_pole, door, route) in placed_query.iter_mut() {
+        let id = object_id.map(|object_id| object_id.0);
+        if is_pole {
+            poles.push((entity, id, grid.world_to_tile(transform.translation.truncate())));
+        }
+        let Some(id) = id else { continue; };
+        if let Some(tile) = snapshot.crates.get(&id) {
+            transform.translation = grid.tile_to_world(*tile).extend(Z_GAME_LAYER - 1.0);
+            new_crates.insert(id, entity);
+        }
+        if let (Some(mut door), Some(open)) = (door, snapshot.doors.get(&id)) {
+            if door.open != *open {
+                door.open = *open;
+            }
+        }
+        if let (Some(mut route), Some(enabled)) = (route, snapshot.power_routes.get(&id)) {
+            route.enabled = *enabled;
+        }
+    }
+
+    let relink = |old: Entity| snapshot.poles.get(&old).and_then(|saved| relink_pole(*saved, &poles));
+    let move_point = |point: Vec2| grid.tile_to_world(snapshot.grid.world_to_tile(point));
+
+    if let Some(mut cord_system) = cord_system {
+        match snapshot.attached_pole.map(&relink) {
+            Some(Some(pole)) => {
+                cord_system.attached_pole = Some(pole);
+                cord_system.trail_path = snapshot.trail.iter().map(|tile| grid.tile_to_world(*tile)).collect();
+            }
+            Some(None) => {
+                println!("The pole the cord was plugged into is gone from the map!");
+                cord_system.attached_pole = None;
+                cord_system.trail_path.clear();
+            }
+            None => {}
+        }
+    }
+
+    // Undo steps point at old entities and old world positions too
+    history.pending_restore = None;
+    for step in history.snapshots.iter_mut() {
+        if let Some(old_pole) = step.attached_pole {
+            step.attached_pole = relink(old_pole);
+            if step.attached_pole.is_none() {
+                step.trail_path.clear();
+            }
+        }
+        for point in step.trail_path.iter_mut() {
+            *point = move_point(*point);
+        }
+        step.crates = step
+            .crates
+            .iter()
+            .filter_map(|(old_crate, tile)| {
+                let id = snapshot.crate_ids.get(old_crate)?;
+                new_crates.get(id).map(|new_crate| (*new_crate, *tile))
+            })
+            .collect();
+    }
+    info!("Map reloaded");
+}
@@ -0,0 +1,636 @@
+use std::ops::{Add, Sub};
+
+use bevy::prelude::*;
+use bevy_ecs_tiled::prelude::*;
+
+use crate::components::Player;
+use crate::tiled_map::map_tile_bounds;
+
+/// Position of a tile in Tiled tile coordinates. On isometric maps +x runs
+/// down-right on screen and +y down-left; on the other layouts they run right
+/// and down. Infinite maps can have negative coordinates.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Reflect)]
+pub struct TilePos {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl TilePos {
+    pub const ZERO: Self = Self::new(0, 0);
+
+    pub const fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+
+    pub fn as_ivec2(self) -> IVec2 {
+        IVec2::new(self.x, self.y)
+    }
+
+    /// The four tiles sharing an edge with this one, in `GridDirection::ALL` order.
+    /// These are a tile's neighbours on isometric and orthogonal maps; see
+    /// `GridLayout::neighbors` for the others.
+    pub fn neighbors(self) -> impl Iterator<Item = TilePos> {
+        GridDirection::ALL.into_iter().map(move |direction| self + direction.step())
+    }
+
+    /// Number of grid steps between two tiles
+    pub fn manhattan_distance(self, other: TilePos) -> i32 {
+        let difference = other - self;
+        difference.x.abs() + difference.y.abs()
+    }
+}
+
+impl From<IVec2> for TilePos {
+    fn from(value: IVec2) -> Self {
+        Self::new(value.x, value.y)
+    }
+}
+
+impl From<TilePos> for IVec2 {
+    fn from(value: TilePos) -> Self {
+        value.as_ivec2()
+    }
+}
+
+impl Add<IVec2> for TilePos {
+    type Output = TilePos;
+
+    fn add(self, step: IVec2) -> TilePos {
+        TilePos::new(self.x + step.x, self.y + step.y)
+    }
+}
+
+impl Sub for TilePos {
+    type Output = IVec2;
+
+    fn sub(self, other: TilePos) -> IVec2 {
+        IVec2::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+/// One grid step, named after the screen diagonal it moves along
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Reflect)]
+pub enum GridDirection {
+    NorthEast,
+    SouthEast,
+    SouthWest,
+    NorthWest,
+}
+
+impl GridDirection {
+    pub const ALL: [GridDirection; 4] = [
+        GridDirection::NorthEast,
+        GridDirection::SouthEast,
+        GridDirection::SouthWest,
+        GridDirection::NorthWest,
+    ];
+
+    /// Change in tile coordinates for a step in this direction
+    pub fn step(self) -> IVec2 {
+        match self {
+            GridDirection::NorthEast => IVec2::new(0, -1),
+            GridDirection::SouthEast => IVec2::new(1, 0),
+            GridDirection::SouthWest => IVec2::new(0, 1),
+            GridDirection::NorthWest => IVec2::new(-1, 0),
+        }
+    }
+
+    pub fn from_step(step: IVec2) -> Option<Self> {
+        GridDirection::ALL.into_iter().find(|direction| direction.step() == step)
+    }
+
+    /// Parse a direction as written in Tiled properties (`ne`, `north_east`, ...)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "ne" | "north_east" | "northeast" => Some(GridDirection::NorthEast),
+            "se" | "south_east" | "southeast" => Some(GridDirection::SouthEast),
+            "sw" | "south_west" | "southwest" => Some(GridDirection::SouthWest),
+            "nw" | "north_west" | "northwest" => Some(GridDirection::NorthWest),
+            _ => None,
+        }
+    }
+}
+
+/// Which way staggered and hexagonal maps shift every other row or column
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Reflect)]
+pub enum StaggerAxis {
+    /// Columns are shifted down (flat-topped hexagons)
+    X,
+    /// Rows are shifted right (pointy-topped hexagons)
+    Y,
+}
+
+/// Row or column shifting of a staggered or hexagonal map, as set in Tiled
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct Stagger {
+    pub axis: StaggerAxis,
+    /// Whether the odd (rather than the even) rows or columns are shifted
+    pub odd: bool,
+    /// Length of a hexagon's sides along the stagger axis; 0 for staggered isometric maps
+    pub side_length: f32,
+}
+
+impl Stagger {
+    fn from_map(map: &tiled::Map) -> Self {
+        Self {
+            axis: match map.stagger_axis {
+                tiled::StaggerAxis::X => StaggerAxis::X,
+                tiled::StaggerAxis::Y => StaggerAxis::Y,
+            },
+            odd: matches!(map.stagger_index, tiled::StaggerIndex::Odd),
+            side_length: map.hex_side_length.unwrap_or(0) as f32,
+        }
+    }
+
+    fn is_shifted(&self, tile: TilePos) -> bool {
+        let index = match self.axis {
+            StaggerAxis::X => tile.x,
+            StaggerAxis::Y => tile.y,
+        };
+        (index.rem_euclid(2) == 1) == self.odd
+    }
+
+    /// Neighbours in the rows (or columns) either side, clockwise from the top right
+    fn diagonal_neighbors(&self, tile: TilePos) -> [TilePos; 4] {
+        let (back, forward) = if self.is_shifted(tile) { (0, 1) } else { (-1, 0) };
+        match self.axis {
+            StaggerAxis::Y => [
+                tile + IVec2::new(forward, -1),
+                tile + IVec2::new(forward, 1),
+                tile + IVec2::new(back, 1),
+                tile + IVec2::new(back, -1),
+            ],
+            StaggerAxis::X => [
+                tile + IVec2::new(1, back),
+                tile + IVec2::new(1, forward),
+                tile + IVec2::new(-1, forward),
+                tile + IVec2::new(-1, back),
+            ],
+        }
+    }
+}
+
+/// How the tiles of a map are laid out, read from the Tiled map's `orientation`.
+/// Each layout knows where its tiles are and which tiles touch, so movement, the
+/// cord and pathfinding work the same on every kind of map.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Reflect)]
+pub enum GridLayout {
+    /// Diamonds, with +x running down-right and +y down-left
+    #[default]
+    Isometric,
+    /// Rectangles in rows and columns. With `diagonal_moves` (a map property of
+    /// the same name) the robot can also step to the four corner neighbours.
+    Orthogonal { diagonal_moves: bool },
+    /// Diamonds in rows (or columns) that are shifted by half a tile in turn
+    Staggered(Stagger),
+    Hexagonal(Stagger),
+}
+
+impl GridLayout {
+    pub fn from_map(map: &tiled::Map) -> Self {
+        match map.orientation {
+            tiled::Orientation::Orthogonal => GridLayout::Orthogonal {
+                diagonal_moves: matches!(
+                    map.properties.get("diagonal_moves"),
+                    Some(tiled::PropertyValue::BoolValue(true))
+                ),
+            },
+            tiled::Orientation::Isometric => GridLayout::Isometric,
+            tiled::Orientation::Staggered => GridLayout::Staggered(Stagger::from_map(map)),
+            tiled::Orientation::Hexagonal => GridLayout::Hexagonal(Stagger::from_map(map)),
+        }
+    }
+
+    /// Tiles the robot can reach in a single step, clockwise
+    pub fn neighbors(&self, tile: TilePos) -> Vec<TilePos> {
+        match self {
+            GridLayout::Isometric | GridLayout::Orthogonal { diagonal_moves: false } => tile.neighbors().collect(),
+            GridLayout::Orthogonal { diagonal_moves: true } => [
+                IVec2::new(0, -1),
+                IVec2::new(1, -1),
+                IVec2::new(1, 0),
+                IVec2::new(1, 1),
+                IVec2::new(0, 1),
+                IVec2::new(-1, 1),
+                IVec2::new(-1, 0),
+                IVec2::new(-1, -1),
+            ]
+            .into_iter()
+            .map(|step| tile + step)
+            .collect(),
+            GridLayout::Staggered(stagger) => stagger.diagonal_neighbors(tile).to_vec(),
+            GridLayout::Hexagonal(stagger) => {
+                let [up_right, down_right, down_left, up_left] = stagger.diagonal_neighbors(tile);
+                match stagger.axis {
+                    StaggerAxis::Y => vec![
+                        up_right,
+                        tile + IVec2::X,
+                        down_right,
+                        down_left,
+                        tile - IVec2::X,
+                        up_left,
+                    ],
+                    StaggerAxis::X => vec![
+                        tile - IVec2::Y,
+                        up_right,
+                        down_right,
+                        tile + IVec2::Y,
+                        down_left,
+                        up_left,
+                    ],
+                }
+            }
+        }
+    }
+
+    /// The neighbour of `tile` in a named direction. Isometric and orthogonal maps
+    /// step along the tile axes (`GridDirection::step`); staggered and hexagonal
+    /// maps take the neighbour along that screen diagonal, a step that differs
+    /// between odd and even rows (or columns).
+    pub fn neighbor_in(&self, tile: TilePos, direction: GridDirection) -> TilePos {
+        match self {
+            GridLayout::Isometric | GridLayout::Orthogonal { .. } => tile + direction.step(),
+            GridLayout::Staggered(stagger) | GridLayout::Hexagonal(stagger) => {
+                let [up_right, down_right, down_left, up_left] = stagger.diagonal_neighbors(tile);
+                match direction {
+                    GridDirection::NorthEast => up_right,
+                    GridDirection::SouthEast => down_right,
+                    GridDirection::SouthWest => down_left,
+                    GridDirection::NorthWest => up_left,
+                }
+            }
+        }
+    }
+
+    /// Whether the movement keys follow the diamond's diagonal axes rather than the screen's
+    fn has_diagonal_axes(&self) -> bool {
+        matches!(self, GridLayout::Isometric | GridLayout::Staggered(_))
+    }
+}
+
+/// Tiles of a rectangular area that the outermost points of its shape can lie
+/// on: the corners, and with staggering the tiles next to them
+fn outer_tiles(bounds: IRect) -> impl Iterator<Item = TilePos> {
+    let xs = [bounds.min.x, bounds.min.x + 1, bounds.max.x - 1, bounds.max.x];
+    let ys = [bounds.min.y, bounds.min.y + 1, bounds.max.y - 1, bounds.max.y];
+    xs.into_iter()
+        .flat_map(move |x| ys.into_iter().map(move |y| TilePos::new(x, y)))
+        .filter(move |tile| bounds.contains(tile.as_ivec2()))
+}
+
+/// How closely a step has to line up with the direction asked for (cosine of the angle)
+const MIN_STEP_ALIGNMENT: f32 = 0.75;
+
+/// How much better the best step has to line up than the next best to count as meant
+const MIN_STEP_MARGIN: f32 = 0.05;
+
+/// Conversions between world space and the tile grid of the loaded map, whatever
+/// its layout: isometric, orthogonal, staggered or hexagonal (see `GridLayout`).
+/// Every system that needs to know which tile something is on goes through this.
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct MapGrid {
+    pub tile_width: f32,
+    pub tile_height: f32,
+    /// World position of the centre of tile (0, 0)
+    pub origin: Vec2,
+    pub layout: GridLayout,
+    /// Quarter turns the view is rotated by, clockwise on screen. Only
+    /// isometric maps can be turned; see `rotated`.
+    pub quarter_turns: u8,
+    /// Tile coordinates the view turns around: the middle of the map
+    pub pivot: Vec2,
+}
+
+impl Default for MapGrid {
+    fn default() -> Self {
+        Self::new(64.0, 32.0)
+    }
+}
+
+impl MapGrid {
+    pub fn new(tile_width: f32, tile_height: f32) -> Self {
+        Self {
+            tile_width,
+            tile_height,
+            origin: Vec2::ZERO,
+            layout: GridLayout::Isometric,
+            quarter_turns: 0,
+            pivot: Vec2::ZERO,
+        }
+    }
+
+    pub fn with_layout(mut self, layout: GridLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Grid with a Tiled map's tile size and layout, with tile (0, 0) on the world origin
+    pub fn for_map(map: &tiled::Map) -> Self {
+        Self::new(map.tile_width as f32, map.tile_height as f32).with_layout(GridLayout::from_map(map))
+    }
+
+    /// Grid matching a Tiled map spawned with `TilemapAnchor::Center` under an
+    /// entity with the given transform. The anchor puts the centre of the
+    /// bounding box of all tiles (chunk extents for infinite maps) on the map
+    /// entity's origin. Tiles are as wide on one side of their centre as on the
+    /// other, so that is also the centre of the box around the tile centres.
+    pub fn for_centered_map(map: &tiled::Map, map_transform: &GlobalTransform) -> Self {
+        let mut grid = Self::for_map(map);
+        let bounds = map_tile_bounds(map);
+
+        let centres = outer_tiles(bounds).map(|tile| grid.tile_to_world(tile));
+        let (min, max) = centres.fold((Vec2::MAX, Vec2::MIN), |(min, max), centre| (min.min(centre), max.max(centre)));
+
+        grid.origin = map_transform.translation().truncate() - (min + max) / 2.0;
+        grid.pivot = (bounds.min + bounds.max).as_vec2() / 2.0;
+        grid
+    }
+
+    /// Whether the view of this grid can be turned; only isometric diamonds
+    /// look the same after a quarter turn
+    pub fn can_rotate(&self) -> bool {
+        matches!(self.layout, GridLayout::Isometric)
+    }
+
+    /// The grid with the view turned a further number of quarter turns
+    /// (clockwise for positive turns) around the middle of the map. Tiles keep
+    /// their coordinates and only move on screen.
+    pub fn rotated(&self, quarter_turns: i32) -> Self {
+        let mut grid = self.clone();
+        if self.can_rotate() {
+            grid.quarter_turns = (self.quarter_turns as i32 + quarter_turns).rem_euclid(4) as u8;
+        }
+        grid
+    }
+
+    /// Tile coordinates where a tile is drawn in the turned view
+    fn turn(&self, coords: Vec2) -> Vec2 {
+        let mut offset = coords - self.pivot;
+        for _ in 0..self.quarter_turns % 4 {
+            offset = Vec2::new(-offset.y, offset.x);
+        }
+        self.pivot + offset
+    }
+
+    /// Tile coordinates of what is drawn at `coords` in the turned view
+    fn unturn(&self, coords: Vec2) -> Vec2 {
+        let mut offset = coords - self.pivot;
+        for _ in 0..self.quarter_turns % 4 {
+            offset = Vec2::new(offset.y, -offset.x);
+        }
+        self.pivot + offset
+    }
+
+    /// World-space rectangle covered by the tiles within `bounds`
+    pub fn tiles_world_rect(&self, bounds: IRect) -> Rect {
+        outer_tiles(bounds)
+            .flat_map(|tile| self.tile_outline(tile))
+            .fold(Rect::EMPTY, |rect, corner| rect.union_point(corner))
+    }
+
+    /// World offset of a (possibly fractional) isometric tile coordinate from tile (0, 0)
+    fn project(&self, tile: Vec2) -> Vec2 {
+        Vec2::new(
+            (tile.x - tile.y) * self.tile_width / 2.0,
+            -(tile.x + tile.y) * self.tile_height / 2.0,
+        )
+    }
+
+    /// Distance between neighbouring rows and columns of a staggered or hexagonal
+    /// map: the stagger axis packs its rows (or columns) into each other
+    fn stagger_spacing(&self, stagger: &Stagger) -> Vec2 {
+        match stagger.axis {
+            StaggerAxis::Y => Vec2::new(self.tile_width, (self.tile_height + stagger.side_length) / 2.0),
+            StaggerAxis::X => Vec2::new((self.tile_width + stagger.side_length) / 2.0, self.tile_height),
+        }
+    }
+
+    /// How far a point is from a tile's centre, scaled so the tile's edge is at 1
+    fn tile_gauge(&self, tile: TilePos, world_pos: Vec2) -> f32 {
+        let offset = (world_pos - self.tile_to_world(tile)).abs();
+        let half = Vec2::new(self.tile_width, self.tile_height) / 2.0;
+        match self.layout {
+            GridLayout::Isometric => offset.x / half.x + offset.y / half.y,
+            GridLayout::Orthogonal { .. } => (offset / half).max_element(),
+            GridLayout::Staggered(stagger) | GridLayout::Hexagonal(stagger) => {
+                // A hexagon is a rectangle with its corners cut off along the
+                // stagger axis; with no sides left it's a diamond
+                let (across, along, half_across, half_along) = match stagger.axis {
+                    StaggerAxis::Y => (offset.x, offset.y, half.x, half.y),
+                    StaggerAxis::X => (offset.y, offset.x, half.y, half.x),
+                };
+                let corner_cut = half_along - stagger.side_length / 2.0;
+                let across = across / half_across;
+                across.max((along + corner_cut * across) / half_along)
+            }
+        }
+    }
+
+    /// World position of fractional tile coordinates; the inverse of
+    /// `world_to_tile_coords`
+    pub fn tile_coords_to_world(&self, coords: Vec2) -> Vec2 {
+        match self.layout {
+            GridLayout::Isometric => self.origin + self.project(self.turn(coords)),
+            GridLayout::Orthogonal { .. } => {
+                self.origin + Vec2::new(coords.x * self.tile_width, -coords.y * self.tile_height)
+            }
+            GridLayout::Staggered(_) | GridLayout::Hexagonal(_) => {
+                let coords = coords.round();
+                self.tile_to_world(TilePos::new(coords.x as i32, coords.y as i32))
+            }
+        }
+    }
+
+    /// Centre of a tile in world space
+    pub fn tile_to_world(&self, tile: TilePos) -> Vec2 {
+        let offset = match self.layout {
+            GridLayout::Isometric => self.project(self.turn(tile.as_ivec2().as_vec2())),
+            GridLayout::Orthogonal { .. } => Vec2::new(tile.x as f32 * self.tile_width, -tile.y as f32 * self.tile_height),
+            GridLayout::Staggered(stagger) | GridLayout::Hexagonal(stagger) => {
+                let spacing = self.stagger_spacing(&stagger);
+                let mut offset = Vec2::new(tile.x as f32 * spacing.x, -tile.y as f32 * spacing.y);
+                if stagger.is_shifted(tile) {
+                    match stagger.axis {
+                        StaggerAxis::Y => offset.x += self.tile_width / 2.0,
+                        StaggerAxis::X => offset.y -= self.tile_height / 2.0,
+                    }
+                }
+                offset
+            }
+        };
+        self.origin + offset
+    }
+
+    /// Fractional tile coordinates of a world position; tile centres are whole
+    /// numbers. Shifted rows and columns don't line up, so staggered and
+    /// hexagonal layouts only give the coordinates of the tile's centre.
+    pub fn world_to_tile_coords(&self, world_pos: Vec2) -> Vec2 {
+        let offset = world_pos - self.origin;
+        match self.layout {
+            GridLayout::Isometric => {
+                let a = offset.x / (self.tile_width / 2.0); // tile.x - tile.y
+                let b = -offset.y / (self.tile_height / 2.0); // tile.x + tile.y
+                self.unturn(Vec2::new((a + b) / 2.0, (b - a) / 2.0))
+            }
+            GridLayout::Orthogonal { .. } => Vec2::new(offset.x / self.tile_width, -offset.y / self.tile_height),
+            GridLayout::Staggered(_) | GridLayout::Hexagonal(_) => self.world_to_tile(world_pos).as_ivec2().as_vec2(),
+        }
+    }
+
+    /// Tile whose shape contains a world position
+    pub fn world_to_tile(&self, world_pos: Vec2) -> TilePos {
+        let GridLayout::Staggered(stagger) | GridLayout::Hexagonal(stagger) = self.layout else {
+            let coords = self.world_to_tile_coords(world_pos).round();
+            return TilePos::new(coords.x as i32, coords.y as i32);
+        };
+
+        // Guess from the unshifted rows and columns, then pick whichever tile
+        // around the guess actually contains the point
+        let spacing = self.stagger_spacing(&stagger);
+        let offset = world_pos - self.origin;
+        let guess = TilePos::new((offset.x / spacing.x).round() as i32, (-offset.y / spacing.y).round() as i32);
+        (-1..=1)
+            .flat_map(|y| (-1..=1).map(move |x| guess + IVec2::new(x, y)))
+            .min_by(|a, b| self.tile_gauge(*a, world_pos).total_cmp(&self.tile_gauge(*b, world_pos)))
+            .unwrap_or(guess)
+    }
+
+    pub fn snap_to_tile_center(&self, world_pos: Vec2) -> Vec2 {
+        self.tile_to_world(self.world_to_tile(world_pos))
+    }
+
+    /// World offset covered by a step from tile (0, 0)
+    pub fn step_offset(&self, step: IVec2) -> Vec2 {
+        self.tile_to_world(TilePos::ZERO + step) - self.tile_to_world(TilePos::ZERO)
+    }
+
+    /// Offset from a tile's centre down to where things lying on the ground are
+    /// drawn, such as the cord: half a tile height below it
+    pub fn ground_offset(&self) -> Vec2 {
+        Vec2::new(0.0, -self.tile_height / 2.0)
+    }
+
+    /// Tiles the robot can reach from `tile` in a single step
+    pub fn neighbors(&self, tile: TilePos) -> Vec<TilePos> {
+        self.layout.neighbors(tile)
+    }
+
+    /// Screen direction for the movement keys (W/A/S/D as up/left/down/right).
+    /// On diamond layouts the keys follow the grid's axes, so W heads up and to the right.
+    pub fn input_direction(&self, keys: Vec2) -> Vec2 {
+        if self.layout.has_diagonal_axes() {
+            Vec2::new(keys.x + keys.y, keys.y - keys.x)
+        } else {
+            keys
+        }
+    }
+
+    /// The neighbour of `tile` lying in a screen direction. `None` if no neighbour
+    /// is close enough to it, or two are about as close (such as straight up on a
+    /// map of pointy-topped hexagons).
+    pub fn neighbor_toward(&self, tile: TilePos, direction: Vec2) -> Option<TilePos> {
+        let direction = direction.try_normalize()?;
+        let centre = self.tile_to_world(tile);
+        let mut alignments: Vec<(f32, TilePos)> = self
+            .neighbors(tile)
+            .into_iter()
+            .map(|neighbor| ((self.tile_to_world(neighbor) - centre).normalize_or_zero().dot(direction), neighbor))
+            .collect();
+        alignments.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let (best, neighbor) = *alignments.first()?;
+        let runner_up = alignments.get(1).map_or(-1.0, |(alignment, _)| *alignment);
+        (best >= MIN_STEP_ALIGNMENT && best - runner_up >= MIN_STEP_MARGIN).then_some(neighbor)
+    }
+
+    /// The tile after `to` when carrying on in the direction of the step from
+    /// `from`, e.g. where a pushed crate ends up
+    pub fn continue_line(&self, from: TilePos, to: TilePos) -> TilePos {
+        let direction = (self.tile_to_world(to) - self.tile_to_world(from)).normalize_or_zero();
+        let centre = self.tile_to_world(to);
+        self.neighbors(to)
+            .into_iter()
+            .max_by(|a, b| {
+                let alignment = |tile: &TilePos| (self.tile_to_world(*tile) - centre).normalize_or_zero().dot(direction);
+                alignment(a).total_cmp(&alignment(b))
+            })
+            .unwrap_or(to)
+    }
+
+    /// Corners of the diamond around a tile: top, right, bottom, left. On
+    /// isometric and staggered maps this is the tile itself.
+    pub fn tile_corners(&self, tile: TilePos) -> [Vec2; 4] {
+        let center = self.tile_to_world(tile);
+        let half_width = self.tile_width / 2.0;
+        let half_height = self.tile_height / 2.0;
+        [
+            center + Vec2::new(0.0, half_height),
+            center + Vec2::new(half_width, 0.0),
+            center + Vec2::new(0.0, -half_height),
+            center + Vec2::new(-half_width, 0.0),
+        ]
+    }
+
+    /// Outline of a tile's shape, clockwise
+    pub fn tile_outline(&self, tile: TilePos) -> Vec<Vec2> {
+        let center = self.tile_to_world(tile);
+        let half = Vec2::new(self.tile_width, self.tile_height) / 2.0;
+        let corners = match self.layout {
+            GridLayout::Isometric | GridLayout::Staggered(_) => return self.tile_corners(tile).to_vec(),
+            GridLayout::Orthogonal { .. } => vec![
+                Vec2::new(-half.x, half.y),
+                half,
+                Vec2::new(half.x, -half.y),
+                -half,
+            ],
+            GridLayout::Hexagonal(Stagger { axis: StaggerAxis::Y, side_length, .. }) => vec![
+                Vec2::new(0.0, half.y),
+                Vec2::new(half.x, side_length / 2.0),
+                Vec2::new(half.x, -side_length / 2.0),
+                Vec2::new(0.0, -half.y),
+                Vec2::new(-half.x, -side_length / 2.0),
+                Vec2::new(-half.x, side_length / 2.0),
+            ],
+            GridLayout::Hexagonal(Stagger { axis: StaggerAxis::X, side_length, .. }) => vec![
+                Vec2::new(-side_length / 2.0, half.y),
+                Vec2::new(side_length / 2.0, half.y),
+                Vec2::new(half.x, 0.0),
+                Vec2::new(side_length / 2.0, -half.y),
+                Vec2::new(-side_length / 2.0, -half.y),
+                Vec2::new(-half.x, 0.0),
+            ],
+        };
+        corners.into_iter().map(|corner| center + corner).collect()
+    }
+}
+
+/// Line the grid up with the Tiled map whenever it finishes loading, and snap
+/// the player onto the centre of the aligned tile it stands on
+pub fn align_grid_to_map(
+    mut asset_events: MessageReader<AssetEvent<TiledMapAsset>>,
+    map_assets: Res<Assets<TiledMapAsset>>,
+    map_query: Query<(&TiledMap, &GlobalTransform, &TilemapAnchor)>,
+    mut grid: ResMut<MapGrid>,
+    mut player_query: Query<&mut Transform, With<Player>>,
+) {
+    for event in asset_events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event else {
+            continue;
+        };
+        let Some(map_asset) = map_assets.get(*id) else { continue; };
+        let Some((_, map_transform, anchor)) = map_query.iter().find(|(map, _, _)| map.0.id() == *id) else {
+            continue;
+        };
+        if !matches!(anchor, TilemapAnchor::Center) {
+            warn!("Tiled map is not anchored at its centre; grid alignment may be off");
+        }
+
+        let aligned = MapGrid::for_centered_map(&map_asset.map, map_transform);
+        for mut transform in player_query.iter_mut() {
+            let position = aligned.snap_to_tile_center(transform.translation.truncate());
+            transform.translation.x = position.x;
+            transform.translation.y = position.y;
+        }
+        *grid = aligned;
+    }
+}
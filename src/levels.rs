@@ -0,0 +1,210 @@
+use bevy::prelude::*;
+use bevy_ecs_tiled::prelude::*;
+
+use crate::components::{CordSystem, GridMovement, Player};
+use crate::cord_system::disconnect_cord_from_pole;
+use crate::grid_objects::GridPlaced;
+use crate::hot_reload::MapReload;
+use crate::isometric::MapGrid;
+use crate::loading::{GameState, MapLoadReport};
+use crate::map_objects::{MapObjectIndex, PlayerSpawn};
+use crate::player_movement::GridStepCompleted;
+use crate::tiled_map::spawn_tiled_map;
+use crate::undo::UndoHistory;
+
+/// Spawn point used when a level is entered without naming one
+pub const DEFAULT_SPAWN: &str = "start";
+
+/// Exit zone placed in Tiled as a point or rectangle object with this class
+/// (`balthazar::levels::LevelExit`). Stepping into it moves the robot to the
+/// spawn point named `spawn` on the level called `level`.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct LevelExit {
+    pub level: String,
+    pub spawn: String,
+}
+
+/// A level the robot can travel to
+#[derive(Clone, Debug, PartialEq)]
+pub struct LevelInfo {
+    pub name: String,
+    /// Path of the Tiled map, relative to the assets folder
+    pub map_path: String,
+}
+
+/// Every level in the game, and which one is loaded
+#[derive(Resource, Debug)]
+pub struct LevelRegistry {
+    pub levels: Vec<LevelInfo>,
+    pub current: usize,
+    /// Spawn point to put the robot on once the current level's objects are in
+    pub pending_spawn: Option<String>,
+}
+
+impl Default for LevelRegistry {
+    fn default() -> Self {
+        Self {
+            levels: vec![LevelInfo {
+                name: "colony".to_string(),
+                map_path: "map.tmx".to_string(),
+            }],
+            current: 0,
+            pending_spawn: None,
+        }
+    }
+}
+
+impl LevelRegistry {
+    pub fn with_level(mut self, name: impl Into<String>, map_path: impl Into<String>) -> Self {
+        self.levels.push(LevelInfo {
+            name: name.into(),
+            map_path: map_path.into(),
+        });
+        self
+    }
+
+    pub fn current_level(&self) -> &LevelInfo {
+        &self.levels[self.current]
+    }
+
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.levels.iter().position(|level| level.name == name)
+    }
+}
+
+/// Request to move the robot to a spawn point, possibly on another level
+#[derive(Message, Clone, Debug, PartialEq)]
+pub struct LevelTransition {
+    pub level: String,
+    pub spawn: String,
+}
+
+/// Send the robot through an exit zone when it steps into one. The cord can't
+/// follow it to another map, so the exit is refused while the cord is attached.
+pub fn use_level_exits(
+    mut step_events: MessageReader<GridStepCompleted>,
+    grid: Res<MapGrid>,
+    index: Res<MapObjectIndex>,
+    registry: Res<LevelRegistry>,
+    history: Res<UndoHistory>,
+    cord_system: Option<Res<CordSystem>>,
+    player_query: Query<(), With<Player>>,
+    mut transitions: MessageWriter<LevelTransition>,
+) {
+    for event in step_events.read() {
+        if !player_query.contains(event.entity) || history.pending_restore.is_some() {
+            continue;
+        }
+        let tile = grid.world_to_tile(event.position);
+        let Some(exit) = index
+            .objects
+            .iter()
+            .find(|object| object.short_class() == "LevelExit" && object.covers(tile))
+        else {
+            continue;
+        };
+
+        let level = exit
+            .string_property("level")
+            .unwrap_or(&registry.current_level().name)
+            .to_string();
+        if registry.index_of(&level).is_none() {
+            warn!("Exit '{}' leads to unknown level '{level}'", exit.name);
+            continue;
+        }
+        if cord_system.as_ref().is_some_and(|cord_system| cord_system.attached_pole.is_some()) {
+            println!("The cord can't reach another level: detach it first!");
+            continue;
+        }
+
+        let spawn = exit.string_property("spawn").unwrap_or(DEFAULT_SPAWN).to_string();
+        transitions.write(LevelTransition { level, spawn });
+    }
+}
+
+/// Swap the loaded map for the requested level. The robot entity stays, so its
+/// battery carries over; everything spawned from the old map goes with it.
+#[allow(clippy::too_many_arguments)]
+pub fn change_level(
+    mut commands: Commands,
+    mut transitions: MessageReader<LevelTransition>,
+    asset_server: Res<AssetServer>,
+    mut registry: ResMut<LevelRegistry>,
+    mut report: ResMut<MapLoadReport>,
+    cord_system: Option<ResMut<CordSystem>>,
+    map_query: Query<Entity, With<TiledMap>>,
+    placed_query: Query<Entity, With<GridPlaced>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(transition) = transitions.read().last().cloned() else { return; };
+    let Some(level_index) = registry.index_of(&transition.level) else { return; };
+
+    // Poles belong to the map, so a cord can never stay plugged in across levels
+    if let Some(mut cord_system) = cord_system {
+        disconnect_cord_from_pole(&mut cord_system);
+    }
+    registry.pending_spawn = Some(transition.spawn);
+
+    // Moving within the same level only needs the spawn point
+    if level_index == registry.current {
+        return;
+    }
+
+    for entity in map_query.iter().chain(placed_query.iter()) {
+        commands.entity(entity).despawn();
+    }
+    registry.current = level_index;
+    *report = MapLoadReport::default();
+    let map_path = registry.current_level().map_path.clone();
+    spawn_tiled_map(&mut commands, asset_server.load(map_path));
+    next_state.set(GameState::Loading);
+}
+
+/// Put the robot on a spawn point: the one a transition asked for, or the first
+/// one of a freshly loaded map. The undo history starts over, since earlier
+/// positions belong to another layout. A map reloaded from disk keeps the robot
+/// where it was instead.
+pub fn move_player_to_spawn(
+    grid: Res<MapGrid>,
+    reload: Res<MapReload>,
+    mut registry: ResMut<LevelRegistry>,
+    mut history: ResMut<UndoHistory>,
+    added_query: Query<(), Added<PlayerSpawn>>,
+    spawn_query: Query<(&GlobalTransform, &PlayerSpawn)>,
+    mut player_query: Query<(&mut Transform, &mut GridMovement), With<Player>>,
+) {
+    let map_arrived = !added_query.is_empty() && !reload.is_pending();
+    if !map_arrived && registry.pending_spawn.is_none() {
+        return;
+    }
+    // The next map's objects aren't in yet; keep waiting
+    if spawn_query.is_empty() {
+        return;
+    }
+
+    let requested = registry.pending_spawn.take();
+    let named = requested
+        .as_deref()
+        .and_then(|name| spawn_query.iter().find(|(_, spawn)| spawn.name == name));
+    if let (Some(name), None) = (&requested, named) {
+        warn!("No spawn point named '{name}' on this level");
+    }
+    let chosen = named.or_else(|| {
+        spawn_query
+            .iter()
+            .find(|(_, spawn)| spawn.name == DEFAULT_SPAWN)
+            .or_else(|| spawn_query.iter().next())
+            .filter(|_| map_arrived)
+    });
+    let Some((spawn_transform, _)) = chosen else { return; };
+
+    let position = grid.snap_to_tile_center(spawn_transform.translation().truncate());
+    for (mut transform, mut grid_movement) in player_query.iter_mut() {
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+        grid_movement.target_position = None;
+        grid_movement.is_moving = false;
+    }
+    history.clear();
+}
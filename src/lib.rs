@@ -8,6 +8,26 @@ pub mod ui;
 pub mod power_system;
 pub mod day_night_cycle;
 pub mod tiled_map;
+pub mod terrain;
+pub mod elevation;
+pub mod undo;
+pub mod grid_objects;
+pub mod special_tiles;
+pub mod map_objects;
+pub mod isometric;
+pub mod picking;
+pub mod loading;
+pub mod mapcheck;
+pub mod levels;
+pub mod mapgen;
+pub mod hot_reload;
+pub mod fog_of_war;
+pub mod free_camera;
+pub mod camera_effects;
+pub mod minimap;
+pub mod view_rotation;
+pub mod toggles;
+pub mod reset;
 
 // Re-export all public items for convenience
 pub use components::*;
@@ -19,3 +39,23 @@ pub use ui::*;
 pub use power_system::*;
 pub use day_night_cycle::*;
 pub use tiled_map::*;
+pub use terrain::*;
+pub use elevation::*;
+pub use undo::*;
+pub use grid_objects::*;
+pub use special_tiles::*;
+pub use map_objects::*;
+pub use isometric::*;
+pub use picking::*;
+pub use loading::*;
+pub use mapcheck::*;
+pub use levels::*;
+pub use mapgen::*;
+pub use hot_reload::*;
+pub use fog_of_war::*;
+pub use free_camera::*;
+pub use camera_effects::*;
+pub use minimap::*;
+pub use view_rotation::*;
+pub use toggles::*;
+pub use reset::*;
@@ -0,0 +1,194 @@
+use bevy::asset::{RecursiveDependencyLoadState, RenderAssetUsages, UntypedAssetLoadFailedEvent};
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy_ecs_tiled::prelude::*;
+
+use crate::isometric::{MapGrid, TilePos};
+
+/// Placeholder tiles are laid out this many tiles around tile (0, 0) in every direction
+const PLACEHOLDER_RADIUS: i32 = 10;
+const PLACEHOLDER_CELLS: u32 = 4;
+const CHECKER_DARK: [u8; 4] = [20, 20, 20, 255];
+const CHECKER_LIGHT: [u8; 4] = [255, 0, 255, 255];
+
+/// Top-level flow of the game: nothing is playable until the map has loaded
+#[derive(States, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum GameState {
+    #[default]
+    Loading,
+    Playing,
+}
+
+/// An asset that failed to load, with the path the asset server tried
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssetFailure {
+    pub path: String,
+    pub error: String,
+}
+
+/// Everything that went wrong while loading the map and the files it refers to
+#[derive(Resource, Default, Debug)]
+pub struct MapLoadReport {
+    pub failures: Vec<AssetFailure>,
+}
+
+impl MapLoadReport {
+    /// Record a failure, ignoring repeats for the same path
+    pub fn record(&mut self, path: impl Into<String>, error: impl Into<String>) {
+        let path = path.into();
+        if self.failures.iter().all(|failure| failure.path != path) {
+            self.failures.push(AssetFailure { path, error: error.into() });
+        }
+    }
+
+    pub fn has_failures(&self) -> bool {
+        !self.failures.is_empty()
+    }
+
+    /// Human-readable summary for the loading screen and the log
+    pub fn summary(&self) -> String {
+        if self.failures.is_empty() {
+            return "Loading map...".to_string();
+        }
+        let mut summary = String::from("The map could not be loaded:");
+        for failure in &self.failures {
+            summary.push_str(&format!("\n- {}: {}", failure.path, failure.error));
+        }
+        summary
+    }
+}
+
+/// Panel with the loading message, which stays up with the report if loading fails
+#[derive(Component)]
+pub struct LoadingScreen;
+
+/// Text on the loading screen
+#[derive(Component)]
+pub struct LoadingText;
+
+/// Root of the checkerboard shown in place of a map that failed to load
+#[derive(Component)]
+pub struct MapPlaceholder;
+
+/// A diamond-shaped checkerboard tile, in the shape of an isometric map tile,
+/// that stands in for tileset images that are missing
+pub fn checkerboard_tile_image(width: u32, height: u32, cells: u32) -> Image {
+    let mut data = Vec::with_capacity((width * height * 4) as usize);
+    let half = Vec2::new(width as f32, height as f32) / 2.0;
+    for y in 0..height {
+        for x in 0..width {
+            let offset = (Vec2::new(x as f32, y as f32) + 0.5 - half) / half;
+            let pixel = if offset.x.abs() + offset.y.abs() > 1.0 {
+                [0, 0, 0, 0]
+            } else {
+                // Checker along the diamond's own axes so cells line up with neighbouring tiles
+                let along_x = ((offset.x + offset.y + 1.0) / 2.0 * cells as f32) as u32;
+                let along_y = ((offset.y - offset.x + 1.0) / 2.0 * cells as f32) as u32;
+                if (along_x + along_y) % 2 == 0 { CHECKER_LIGHT } else { CHECKER_DARK }
+            };
+            data.extend_from_slice(&pixel);
+        }
+    }
+
+    Image::new(
+        Extent3d { width, height, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+}
+
+pub fn setup_loading_screen(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(20.0),
+            left: Val::Px(20.0),
+            max_width: Val::Px(700.0),
+            padding: UiRect::all(Val::Px(10.0)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
+        LoadingScreen,
+        children![(
+            Text::new("Loading map..."),
+            TextFont {
+                font_size: 16.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+            LoadingText,
+        )],
+    ));
+}
+
+/// Collect the paths of assets that fail to load: the map itself, tilesets and
+/// images. The loading screen shows the report, even once play has started.
+pub fn track_asset_failures(
+    mut failures: MessageReader<UntypedAssetLoadFailedEvent>,
+    mut report: ResMut<MapLoadReport>,
+    mut text_query: Query<&mut Text, With<LoadingText>>,
+) {
+    for failure in failures.read() {
+        error!("Failed to load '{}': {}", failure.path, failure.error);
+        report.record(failure.path.to_string(), failure.error.to_string());
+    }
+
+    if report.is_changed() {
+        for mut text in text_query.iter_mut() {
+            **text = report.summary();
+        }
+    }
+}
+
+/// Enter play once the map and everything it depends on has loaded. If anything
+/// failed, play on a checkerboard placeholder instead of a blank screen, with the
+/// report left up on the loading screen.
+pub fn check_map_loaded(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+    grid: Res<MapGrid>,
+    map_query: Query<&TiledMap>,
+    loading_screen_query: Query<Entity, With<LoadingScreen>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Ok(map) = map_query.single() else { return; };
+    match asset_server.get_recursive_dependency_load_state(&map.0) {
+        Some(RecursiveDependencyLoadState::Loaded) => {
+            for loading_screen in loading_screen_query.iter() {
+                commands.entity(loading_screen).despawn();
+            }
+            next_state.set(GameState::Playing);
+        }
+        Some(RecursiveDependencyLoadState::Failed(_)) => {
+            spawn_map_placeholder(&mut commands, &mut images, &grid);
+            next_state.set(GameState::Playing);
+        }
+        _ => {}
+    }
+}
+
+fn spawn_map_placeholder(commands: &mut Commands, images: &mut Assets<Image>, grid: &MapGrid) {
+    let image = images.add(checkerboard_tile_image(
+        grid.tile_width as u32,
+        grid.tile_height as u32,
+        PLACEHOLDER_CELLS,
+    ));
+
+    commands
+        .spawn((MapPlaceholder, Transform::default(), Visibility::default()))
+        .with_children(|parent| {
+            for y in -PLACEHOLDER_RADIUS..=PLACEHOLDER_RADIUS {
+                for x in -PLACEHOLDER_RADIUS..=PLACEHOLDER_RADIUS {
+                    let position = grid.tile_to_world(TilePos::new(x, y));
+                    // Tiles further down the screen are drawn on top, like the real map
+                    parent.spawn((
+                        Sprite::from_image(image.clone()),
+                        Transform::from_translation(position.extend(-position.y * 0.001)),
+                    ));
+                }
+            }
+        });
+}
@@ -1,13 +1,16 @@
 use bevy::prelude::*;
 use bevy_ecs_tiled::prelude::*;
 use bevy_light_2d::prelude::*;
-fn handle_reset_button() {
-    // TODO: Implement reset button logic
-}
 // Use types and functions from lib.rs
 use balthazar::*;
 
 fn main() {
+    // `--generate` or `--seed <n>` swaps map.tmx for a generated map
+    let levels = starting_levels(std::env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        std::process::exit(2);
+    });
+
     App::new()
         .add_plugins((
             DefaultPlugins,
@@ -22,29 +25,146 @@ fn main() {
             camera_zoom: true,
             player_rotation: true,
         })
+        // Named toggles for the system controls panel; the built-in ones live in `SystemToggles`
+        .add_plugins(system_toggles_plugin)
+        .register_toggle("day_night", "Day/Night Cycle", true)
+        .register_toggle("power", "Power", true)
+        .register_toggle("lighting", "Lighting", true)
         .insert_resource(DayNightCycle::default()) // Initialize day/night cycle
-        .add_systems(Startup, (setup, setup_ui, load_tiled_map))
+        .init_resource::<TerrainMap>()
+        .init_resource::<HeightMap>()
+        .init_resource::<UndoHistory>()
+        .init_resource::<SpecialTileMap>()
+        .init_resource::<MapObjectIndex>()
+        .init_resource::<HoveredTile>()
+        .init_resource::<MapLoadReport>()
+        .init_resource::<MapReload>()
+        .init_resource::<FogOfWar>()
+        .init_resource::<CameraFollow>()
+        .init_resource::<CameraBounds>()
+        .init_resource::<CameraZoom>()
+        .init_resource::<FreeCamera>()
+        .init_resource::<CameraEffects>()
+        .init_resource::<CameraRails>()
+        .init_resource::<CordReel>()
+        .init_resource::<BatteryFlow>()
+        .insert_resource(levels)
+        .init_state::<GameState>()
+        .add_message::<GridStepCompleted>()
+        .add_message::<LevelTransition>()
+        .add_message::<CameraTrauma>()
+        .add_message::<CameraFocusPan>()
+        .add_message::<PlayCameraRail>()
+        .add_message::<ViewRotated>()
+        .add_message::<ResetWorld>()
+        .add_systems(Startup, (setup, setup_ui, setup_fog_of_war, setup_minimap, load_tiled_map))
+        .add_systems(OnEnter(GameState::Loading), setup_loading_screen)
+        // Hold on the loading screen until the map and its tilesets have loaded
+        .add_systems(Update, (
+            track_asset_failures,
+            check_map_loaded.run_if(in_state(GameState::Loading)),
+        ).chain())
         .add_systems(Update, (
             move_player,
             grid_movement_system,
             cord_retraction_wrapper,
             cord_attachment_wrapper,
             update_player_sprite_direction,
-            update_ui,
-            update_battery_display,
-            transfer_power,
-            solar_charge,
-            handle_reset_button,
-            update_day_night_cycle,
-            apply_day_night_lighting,
-            update_sky_color,
-        ))
-        .add_systems(Update, update_cord_trail.before(render_cord_meshes))
-        .add_systems(Update, render_cord_meshes)
+            update_terrain_display,
+            update_player_elevation,
+            transfer_power.run_if(toggle_enabled("power")),
+            solar_charge.run_if(toggle_enabled("power")),
+            update_day_night_cycle.run_if(toggle_enabled("day_night")),
+            apply_day_night_lighting.run_if(toggle_enabled("lighting")),
+            update_sky_color.run_if(toggle_enabled("lighting")),
+        ).run_if(in_state(GameState::Playing)))
+        // HUD: clock, battery with its charge rates, and the cord
+        .add_systems(Update, (
+            update_charge_rates.after(transfer_power).after(solar_charge).after(move_player),
+            update_battery_display.after(update_charge_rates),
+            update_charge_rate_display.after(update_charge_rates),
+            update_clock_display.after(update_day_night_cycle),
+            update_cord_display.after(update_cord_trail),
+        ).run_if(in_state(GameState::Playing)))
+        // Line the grid up with the Tiled map, then rebuild per-tile map data whenever it (re)loads
+        .add_systems(Update, align_grid_to_map)
+        // Save the game state in map terms before a map edited on disk realigns the grid
+        .add_systems(Update, save_state_for_reload.before(align_grid_to_map))
+        .add_systems(Update, (
+            build_terrain_map,
+            build_height_map,
+            build_special_tile_map,
+            index_map_objects,
+            update_camera_bounds,
+            load_camera_rails,
+        ).after(align_grid_to_map))
+        // Fog of war: explored tiles are kept per level, and the robot's light reveals the rest
+        .add_systems(Update, (
+            persist_fog_of_war,
+            spawn_fog_tiles.after(align_grid_to_map).after(build_height_map),
+            (update_fog_of_war, update_fog_tiles).chain().run_if(in_state(GameState::Playing)),
+        ).chain())
+        .add_systems(Last, save_fog_on_exit)
+        // Minimap: drawn on the CPU from the map's tiles, redrawn as the robot explores
+        .add_systems(Update, (
+            build_minimap_tiles.after(align_grid_to_map).after(build_terrain_map).after(build_height_map),
+            update_minimap.after(update_fog_tiles).after(update_cord_trail),
+        ).chain())
+        // The toggle panel works on the loading screen too
+        .add_systems(Update, update_ui)
+        .add_systems(Update, (minimap_click.before(free_camera), sync_toggle_buttons.after(update_ui)).chain())
+        .add_systems(Update, update_cord_trail.after(grid_movement_system).before(render_cord_meshes).run_if(in_state(GameState::Playing)))
+        .add_systems(Update, render_cord_meshes.run_if(in_state(GameState::Playing)))
+        .add_systems(Update, (
+            handle_undo_input,
+            record_move_snapshots.after(update_cord_trail),
+        ).run_if(in_state(GameState::Playing)))
+        // Tiles that move the robot on their own
+        .add_systems(Update, (
+            apply_tile_behaviors.before(record_move_snapshots),
+            run_conveyors,
+        ).chain().after(grid_movement_system).run_if(in_state(GameState::Playing)))
+        .add_systems(Update, (
+            activate_switches,
+            update_door_visuals,
+            // Exits are checked alongside the other tile reactions, while undo steps can still be told apart
+            use_level_exits.after(apply_tile_behaviors).before(record_move_snapshots),
+            change_level.after(use_level_exits),
+        ).run_if(in_state(GameState::Playing)))
+        // Map objects need their world position from the map, so place them after propagation
+        .add_systems(PostUpdate, (
+            place_map_objects,
+            move_player_to_spawn,
+            restore_state_after_reload,
+        ).chain().after(bevy::transform::TransformSystems::Propagate))
         .add_systems(Update, (
             camera_follow_player,
+            free_camera,
+            // Zooming towards the cursor moves the camera after following has placed it
             camera_zoom,
-        ))
+            // Shake and camera shots go on top of wherever the camera ended up
+            queue_camera_effects,
+            apply_camera_effects,
+        ).chain().after(update_camera_bounds).after(load_camera_rails))
+        .add_systems(Update, (
+            shake_on_battery_empty.after(transfer_power),
+            shake_on_cord_short.after(update_cord_trail),
+            pan_to_powered_poles.after(activate_switches),
+        ).before(queue_camera_effects).run_if(in_state(GameState::Playing)))
+        // R or the Reset button starts the level over; a turned view is turned back first
+        .add_systems(Update, (request_reset, reset_world).chain().before(rotate_view)
+            .run_if(in_state(GameState::Playing)))
+        // Q/E turn the view; everything drawn at a world position follows its tiles
+        .add_systems(Update, (
+            rotate_view,
+            (reproject_world, reproject_camera, rotate_tilemaps),
+        ).chain().before(move_player).before(grid_movement_system).before(update_cord_trail)
+            .before(camera_follow_player).run_if(in_state(GameState::Playing)))
+        // Cursor picking runs after the camera has moved for this frame
+        .add_systems(Update, (
+            update_hovered_tile,
+            draw_hover_outline,
+        ).chain().after(apply_camera_effects).before(update_terrain_display))
         .run();
 }
 
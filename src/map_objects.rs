@@ -0,0 +1,244 @@
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy_ecs_tiled::prelude::*;
+use bevy_light_2d::prelude::*;
+
+use crate::components::{Player, Pole, PowerSource, SolarPanel};
+use crate::grid_objects::{prepare_grid_object, Door, GridPlaced, PushableCrate, Switch};
+use crate::isometric::{MapGrid, TilePos};
+use crate::setup::Z_GAME_LAYER;
+use crate::tiled_map::{for_each_map_object, object_tile};
+
+// Map objects are placed in Tiled on an object layer, using the full type path of
+// a reflected component as the object's class (e.g. `balthazar::components::Pole`).
+// bevy_ecs_tiled's `user_properties` attaches that component to the object's
+// entity; the pipeline below then snaps it onto the grid and dresses it up.
+// Extra components can be given as class-typed custom properties named after the
+// component, such as a `PowerSource` property with a `max_output` field on a pole.
+
+const POLE_COLOR: Color = Color::srgb(0.25, 0.22, 0.2);
+const SOLAR_PANEL_COLOR: Color = Color::srgb(0.15, 0.25, 0.6);
+
+/// Stable Tiled object id of an entity spawned from the map
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MapObjectId(pub u32);
+
+/// Where the robot starts on a map. Spawn points are named so other maps can send
+/// the robot to a particular one.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct PlayerSpawn {
+    pub name: String,
+}
+
+/// A Tiled object as read from the map file
+#[derive(Clone, Debug)]
+pub struct MapObjectInfo {
+    pub id: u32,
+    pub name: String,
+    pub class: String,
+    pub tile: TilePos,
+    /// Tiles covered by the object: its own tile for points, every tile under a rectangle
+    pub area: IRect,
+    pub properties: tiled::Properties,
+}
+
+impl MapObjectInfo {
+    /// Class name without its module path (`balthazar::components::Pole` -> `Pole`)
+    pub fn short_class(&self) -> &str {
+        short_type_name(&self.class)
+    }
+
+    pub fn covers(&self, tile: TilePos) -> bool {
+        self.area.contains(tile.as_ivec2())
+    }
+
+    /// Read a string property, e.g. the target level of an exit
+    pub fn string_property(&self, name: &str) -> Option<&str> {
+        match self.properties.get(name) {
+            Some(tiled::PropertyValue::StringValue(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Fields of a class-typed custom property, looked up by the component's short name
+    pub fn class_property(&self, component: &str) -> Option<&tiled::Properties> {
+        self.properties.iter().find_map(|(name, value)| match value {
+            tiled::PropertyValue::ClassValue { properties, .. } if short_type_name(name) == component => {
+                Some(properties)
+            }
+            _ => None,
+        })
+    }
+}
+
+pub fn short_type_name(type_path: &str) -> &str {
+    type_path.rsplit("::").next().unwrap_or(type_path)
+}
+
+/// Read a float field from a set of Tiled properties, accepting integers too
+pub fn float_property(properties: &tiled::Properties, name: &str) -> Option<f32> {
+    match properties.get(name) {
+        Some(tiled::PropertyValue::FloatValue(value)) => Some(*value),
+        Some(tiled::PropertyValue::IntValue(value)) => Some(*value as f32),
+        _ => None,
+    }
+}
+
+/// Every classed object in the loaded map
+#[derive(Resource, Default)]
+pub struct MapObjectIndex {
+    pub objects: Vec<MapObjectInfo>,
+}
+
+impl MapObjectIndex {
+    pub fn from_tiled_map(map: &tiled::Map) -> Self {
+        let mut objects = Vec::new();
+        for_each_map_object(map, |object| {
+            if object.user_type.is_empty() {
+                return;
+            }
+            let tile = object_tile(map, object.x, object.y);
+            // Stop just short of the far edges so a rectangle that ends on a tile border doesn't spill over
+            let far_corner = match object.shape {
+                tiled::ObjectShape::Rect { width, height } if width > 0.0 && height > 0.0 => {
+                    object_tile(map, object.x + width - 0.01, object.y + height - 0.01)
+                }
+                _ => tile,
+            };
+            objects.push(MapObjectInfo {
+                id: object.id(),
+                name: object.name.clone(),
+                class: object.user_type.clone(),
+                tile,
+                area: IRect::from_corners(tile.as_ivec2(), far_corner.as_ivec2()),
+                properties: object.properties.clone(),
+            });
+        });
+        Self { objects }
+    }
+
+    /// The object of a given class (by short name) standing on a tile
+    pub fn find(&self, short_class: &str, tile: TilePos) -> Option<&MapObjectInfo> {
+        self.objects
+            .iter()
+            .find(|object| object.tile == tile && object.short_class() == short_class)
+    }
+
+    pub fn get(&self, id: u32) -> Option<&MapObjectInfo> {
+        self.objects.iter().find(|object| object.id == id)
+    }
+}
+
+/// Rebuild the `MapObjectIndex` whenever a Tiled map finishes loading or is modified
+pub fn index_map_objects(
+    mut asset_events: MessageReader<AssetEvent<TiledMapAsset>>,
+    map_assets: Res<Assets<TiledMapAsset>>,
+    mut index: ResMut<MapObjectIndex>,
+) {
+    for event in asset_events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event else {
+            continue;
+        };
+        if let Some(map_asset) = map_assets.get(*id) {
+            *index = MapObjectIndex::from_tiled_map(&map_asset.map);
+        }
+    }
+}
+
+/// Take newly spawned map objects out of the map hierarchy, snap them onto the
+/// centre of their tile and attach their Tiled id, extra components, sprites and
+/// lights. Runs after transform propagation so the objects' world positions are known.
+#[allow(clippy::type_complexity)]
+pub fn place_map_objects(
+    mut commands: Commands,
+    grid: Res<MapGrid>,
+    index: Res<MapObjectIndex>,
+    query: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            (Has<Pole>, Has<PowerSource>, Has<SolarPanel>),
+            (Has<PushableCrate>, Has<Switch>, Option<&Door>),
+        ),
+        (
+            Or<(
+                With<Pole>,
+                With<PowerSource>,
+                With<SolarPanel>,
+                With<PushableCrate>,
+                With<Switch>,
+                With<Door>,
+            )>,
+            Without<GridPlaced>,
+            Without<Player>,
+        ),
+    >,
+) {
+    for (entity, global_transform, (is_pole, has_power, is_solar), (is_crate, is_switch, door)) in query.iter() {
+        let tile = grid.world_to_tile(global_transform.translation().truncate());
+        let position = grid.tile_to_world(tile);
+
+        let mut entity_commands = commands.entity(entity);
+        entity_commands
+            .remove::<ChildOf>()
+            .insert((
+                Transform::from_translation(position.extend(Z_GAME_LAYER - 1.0)),
+                GridPlaced,
+            ));
+
+        // Match the entity back to its Tiled object to pick up its id and class properties
+        let class = if is_pole {
+            "Pole"
+        } else if is_solar {
+            "SolarPanel"
+        } else if has_power {
+            "PowerSource"
+        } else if is_crate {
+            "PushableCrate"
+        } else if is_switch {
+            "Switch"
+        } else {
+            "Door"
+        };
+        let info = index.find(class, tile);
+        if let Some(info) = info {
+            entity_commands.insert((MapObjectId(info.id), Name::new(info.name.clone())));
+        }
+
+        let power_output = info
+            .and_then(|info| info.class_property("PowerSource"))
+            .and_then(|properties| float_property(properties, "max_output"));
+        if let Some(max_output) = power_output {
+            entity_commands.insert_if_new(PowerSource { max_output });
+        }
+        let solar_output = info
+            .and_then(|info| info.class_property("SolarPanel"))
+            .and_then(|properties| float_property(properties, "max_output"));
+        if let Some(max_output) = solar_output {
+            entity_commands.insert_if_new(SolarPanel { max_output });
+        }
+
+        if is_pole {
+            entity_commands.insert_if_new((
+                Sprite::from_color(POLE_COLOR, Vec2::new(6.0, 40.0)),
+                Anchor::BOTTOM_CENTER,
+            ));
+        } else if is_solar || solar_output.is_some() {
+            entity_commands.insert_if_new(Sprite::from_color(SOLAR_PANEL_COLOR, Vec2::new(24.0, 12.0)));
+        }
+
+        // Powered poles light up their surroundings
+        if has_power || power_output.is_some() {
+            entity_commands.insert_if_new(PointLight2d {
+                radius: 120.0,
+                intensity: 1.0,
+                color: Color::srgb(0.6, 0.8, 1.0),
+                cast_shadows: false,
+                ..default()
+            });
+        }
+
+        prepare_grid_object(&mut entity_commands, is_crate, is_switch, door);
+    }
+}
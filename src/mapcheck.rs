@@ -0,0 +1,340 @@
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use bevy::reflect::{TypeInfo, TypeRegistry};
+use bevy_ecs_tiled::prelude::tiled;
+
+use crate::components::{Pole, PowerRoute, PowerSource, SolarPanel};
+use crate::elevation::HeightMap;
+use crate::grid_objects::{Door, PushableCrate, Switch};
+use crate::isometric::{GridLayout, TilePos};
+use crate::levels::LevelExit;
+use crate::map_objects::{float_property, short_type_name, PlayerSpawn};
+use crate::tiled_map::{for_each_map_object, for_each_map_tile, object_tile};
+
+// Offline checks for a Tiled map, run by the `balthazar-mapcheck` binary. They
+// catch the mistakes that otherwise only show up once the game is running.
+
+/// Result of checking a map: errors make the map unusable, warnings are worth a look
+#[derive(Debug, Default)]
+pub struct MapCheckReport {
+    pub map_path: PathBuf,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+    /// Summary lines about what was checked
+    pub notes: Vec<String>,
+}
+
+impl MapCheckReport {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    fn error(&mut self, message: impl Into<String>) {
+        self.errors.push(message.into());
+    }
+
+    fn warning(&mut self, message: impl Into<String>) {
+        self.warnings.push(message.into());
+    }
+}
+
+impl fmt::Display for MapCheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Checking {}", self.map_path.display())?;
+        for note in &self.notes {
+            writeln!(f, "  {note}")?;
+        }
+        for warning in &self.warnings {
+            writeln!(f, "warning: {warning}")?;
+        }
+        for error in &self.errors {
+            writeln!(f, "error: {error}")?;
+        }
+        write!(
+            f,
+            "{}: {} error(s), {} warning(s)",
+            if self.is_ok() { "OK" } else { "FAILED" },
+            self.errors.len(),
+            self.warnings.len()
+        )
+    }
+}
+
+/// Components that map objects may use as their class or as class-typed properties
+pub fn map_component_registry() -> TypeRegistry {
+    let mut registry = TypeRegistry::default();
+    registry.register::<Pole>();
+    registry.register::<PowerSource>();
+    registry.register::<SolarPanel>();
+    registry.register::<PowerRoute>();
+    registry.register::<PushableCrate>();
+    registry.register::<Switch>();
+    registry.register::<Door>();
+    registry.register::<PlayerSpawn>();
+    registry.register::<LevelExit>();
+    registry
+}
+
+/// Paths of external tilesets (`<tileset source="...">`) referenced by a TMX file
+pub fn referenced_tilesets(tmx: &str) -> Vec<String> {
+    tmx.split("<tileset")
+        .skip(1)
+        .filter_map(|tag| {
+            let tag = &tag[..tag.find('>').unwrap_or(tag.len())];
+            let start = tag.find("source=\"")? + "source=\"".len();
+            let end = start + tag[start..].find('"')?;
+            Some(tag[start..end].to_string())
+        })
+        .collect()
+}
+
+/// Run every check against the map at `path`
+pub fn check_map_file(path: &Path) -> MapCheckReport {
+    let mut report = MapCheckReport {
+        map_path: path.to_path_buf(),
+        ..Default::default()
+    };
+
+    let tmx = match std::fs::read_to_string(path) {
+        Ok(tmx) => tmx,
+        Err(err) => {
+            report.error(format!("cannot read {}: {err}", path.display()));
+            return report;
+        }
+    };
+
+    // Tilesets are checked up front so every missing one is listed, rather than
+    // just the first one the parser trips over
+    let map_dir = path.parent().unwrap_or(Path::new("."));
+    let tilesets = referenced_tilesets(&tmx);
+    for tileset in &tilesets {
+        let tileset_path = map_dir.join(tileset);
+        if !tileset_path.exists() {
+            report.error(format!("tileset not found: {}", tileset_path.display()));
+        }
+    }
+    report.notes.push(format!("{} external tileset(s) referenced", tilesets.len()));
+    if !report.is_ok() {
+        return report;
+    }
+
+    match tiled::Loader::new().load_tmx_map(path) {
+        Ok(map) => check_map(&map, &mut report),
+        Err(err) => report.error(format!("cannot parse map: {err}")),
+    }
+    report
+}
+
+/// Checks that need the parsed map: images, object classes, spawn and reachability
+pub fn check_map(map: &tiled::Map, report: &mut MapCheckReport) {
+    check_images(map, report);
+
+    let registry = map_component_registry();
+    let mut spawn = None;
+    let mut poles = Vec::new();
+    for_each_map_object(map, |object| {
+        if object.user_type.is_empty() {
+            return;
+        }
+        let label = object_label(&object);
+        let tile = object_tile(map, object.x, object.y);
+
+        // Camera rails are read straight from the map rather than spawned as components
+        if short_type_name(&object.user_type) == "CameraRail" {
+            check_camera_rail(&registry, &label, &object, report);
+            return;
+        }
+
+        let Some(fields) = component_fields(&registry, &object.user_type) else {
+            let hint = known_type_path(&registry, short_type_name(&object.user_type))
+                .map(|path| format!(" (did you mean `{path}`?)"))
+                .unwrap_or_default();
+            report.error(format!("{label}: class `{}` is not a map component{hint}", object.user_type));
+            return;
+        };
+        check_properties(&registry, &label, &fields, &object.properties, report);
+
+        match short_type_name(&object.user_type) {
+            "Pole" => poles.push((label, tile)),
+            "PlayerSpawn" => {
+                if spawn.is_some() {
+                    report.warning(format!("{label}: more than one player spawn, the first one is used"));
+                } else {
+                    spawn = Some(tile);
+                }
+            }
+            _ => {}
+        }
+    });
+
+    let Some(spawn) = spawn else {
+        report.error("no player spawn: add an object with class `balthazar::map_objects::PlayerSpawn`");
+        return;
+    };
+    report.notes.push(format!("player spawn at tile ({}, {})", spawn.x, spawn.y));
+
+    let reachable = reachable_tiles(map, spawn);
+    if reachable.is_empty() {
+        report.error(format!("player spawn at ({}, {}) is not on a map tile", spawn.x, spawn.y));
+        return;
+    }
+    for (label, tile) in &poles {
+        if !reachable.contains(tile) {
+            report.error(format!("{label} at tile ({}, {}) cannot be reached on foot from the spawn", tile.x, tile.y));
+        }
+    }
+    report.notes.push(format!(
+        "{} pole(s), {} tile(s) reachable from the spawn",
+        poles.len(),
+        reachable.len()
+    ));
+}
+
+/// A camera rail has to be a polyline, with a positive `speed` and a bool `intro`
+fn check_camera_rail(registry: &TypeRegistry, label: &str, object: &tiled::Object, report: &mut MapCheckReport) {
+    match &object.shape {
+        tiled::ObjectShape::Polyline { points } if points.len() >= 2 => {}
+        tiled::ObjectShape::Polyline { .. } => report.error(format!("{label}: camera rail needs at least two points")),
+        _ => report.error(format!("{label}: camera rail should be a polyline")),
+    }
+
+    let fields = [("speed".to_string(), FieldKind::Float), ("intro".to_string(), FieldKind::Bool)];
+    check_properties(registry, label, &fields, &object.properties, report);
+    if float_property(&object.properties, "speed").is_some_and(|speed| speed <= 0.0) {
+        report.error(format!("{label}: camera rail `speed` should be above zero"));
+    }
+}
+
+fn object_label(object: &tiled::Object) -> String {
+    if object.name.is_empty() {
+        format!("object {}", object.id())
+    } else {
+        format!("object {} \"{}\"", object.id(), object.name)
+    }
+}
+
+fn check_images(map: &tiled::Map, report: &mut MapCheckReport) {
+    for tileset in map.tilesets() {
+        let tile_images = tileset.tiles().filter_map(|(_, tile)| tile.image.clone());
+        for image in tileset.image.clone().into_iter().chain(tile_images) {
+            if !image.source.exists() {
+                report.error(format!(
+                    "image not found: {} (tileset `{}`)",
+                    image.source.display(),
+                    tileset.name
+                ));
+            }
+        }
+    }
+}
+
+/// Value types a component field can take in Tiled
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldKind {
+    Float,
+    Int,
+    Bool,
+    String,
+    Other,
+}
+
+impl FieldKind {
+    fn from_type_path(type_path: &str) -> Self {
+        match type_path {
+            "f32" | "f64" => FieldKind::Float,
+            "i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32" | "u64" | "isize" | "usize" => FieldKind::Int,
+            "bool" => FieldKind::Bool,
+            "alloc::string::String" => FieldKind::String,
+            _ => FieldKind::Other,
+        }
+    }
+
+    /// Whether a Tiled property value can fill a field of this kind
+    pub fn accepts(self, value: &tiled::PropertyValue) -> bool {
+        matches!(
+            (self, value),
+            (FieldKind::Float, tiled::PropertyValue::FloatValue(_) | tiled::PropertyValue::IntValue(_))
+                | (FieldKind::Int, tiled::PropertyValue::IntValue(_))
+                | (FieldKind::Bool, tiled::PropertyValue::BoolValue(_))
+                | (FieldKind::String, tiled::PropertyValue::StringValue(_))
+                | (FieldKind::Other, _)
+        )
+    }
+}
+
+/// Named fields of a registered component, looked up by its full type path
+pub fn component_fields(registry: &TypeRegistry, type_path: &str) -> Option<Vec<(String, FieldKind)>> {
+    let registration = registry.get_with_type_path(type_path)?;
+    match registration.type_info() {
+        TypeInfo::Struct(info) => Some(
+            info.iter()
+                .map(|field| (field.name().to_string(), FieldKind::from_type_path(field.type_path())))
+                .collect(),
+        ),
+        _ => Some(Vec::new()),
+    }
+}
+
+fn known_type_path(registry: &TypeRegistry, short_name: &str) -> Option<String> {
+    registry
+        .iter()
+        .map(|registration| registration.type_info().type_path())
+        .find(|path| short_type_name(path) == short_name)
+        .map(str::to_string)
+}
+
+fn check_properties(
+    registry: &TypeRegistry,
+    label: &str,
+    fields: &[(String, FieldKind)],
+    properties: &tiled::Properties,
+    report: &mut MapCheckReport,
+) {
+    for (name, value) in properties {
+        // Class-typed properties add another component to the object
+        if let tiled::PropertyValue::ClassValue { property_type, properties } = value {
+            let Some(class_fields) = component_fields(registry, property_type) else {
+                report.error(format!("{label}: property `{name}` has class `{property_type}`, which is not a map component"));
+                continue;
+            };
+            check_properties(registry, &format!("{label} {}", short_type_name(property_type)), &class_fields, properties, report);
+            continue;
+        }
+
+        match fields.iter().find(|(field, _)| field == name) {
+            Some((_, kind)) if !kind.accepts(value) => {
+                report.error(format!("{label}: property `{name}` should be {kind:?}, found {value:?}"));
+            }
+            Some(_) => {}
+            None => report.warning(format!("{label}: property `{name}` doesn't match any component field")),
+        }
+    }
+}
+
+/// Tiles that can be walked to from `start`, following the same height rules as the robot
+pub fn reachable_tiles(map: &tiled::Map, start: TilePos) -> HashSet<TilePos> {
+    let mut ground = HashSet::new();
+    for_each_map_tile(map, |_layer, tile, _| {
+        ground.insert(tile);
+    });
+    let height_map = HeightMap::from_tiled_map(map);
+    let layout = GridLayout::from_map(map);
+
+    let mut reachable = HashSet::new();
+    if !ground.contains(&start) {
+        return reachable;
+    }
+    let mut queue = VecDeque::from([start]);
+    reachable.insert(start);
+    while let Some(tile) = queue.pop_front() {
+        for neighbor in layout.neighbors(tile) {
+            if ground.contains(&neighbor) && !reachable.contains(&neighbor) && height_map.can_step(&layout, tile, neighbor) {
+                reachable.insert(neighbor);
+                queue.push_back(neighbor);
+            }
+        }
+    }
+    reachable
+}
@@ -0,0 +1,373 @@
+use std::collections::{HashSet, VecDeque};
+use std::fmt::Write as _;
+use std::path::Path;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::isometric::TilePos;
+use crate::levels::{LevelInfo, LevelRegistry};
+
+// Seeded generator for fresh isometric maps. It writes ordinary Tiled maps that
+// use the same tilesets as `map.tmx`, so everything else loads them as usual.
+
+/// Tilesets shared with `map.tmx`: (first gid, file name, tiles used)
+const TILESETS: [(u32, &str, u32); 3] = [
+    (1, "RocksAndMountainsSet_02_8Tiles_256x256.tsx", 8),
+    (9, "ForestSet_01_8Tiles_256x256.tsx", 8),
+    (41, "ColonyBuildings_Tier1_Set_01_8Tiles_256x256.tsx", 8),
+];
+/// Where generated maps are written, and the tileset folder as seen from there
+pub const GENERATED_MAP_FILE: &str = "assets/generated/run.tmx";
+pub const GENERATED_MAP_ASSET: &str = "generated/run.tmx";
+const GENERATED_TILESET_DIR: &str = "../tilesets";
+const TILE_WIDTH: u32 = 64;
+const TILE_HEIGHT: u32 = 32;
+const SMOOTHING_PASSES: usize = 3;
+/// Noise below this leaves a hole in the ground
+const GAP_THRESHOLD: f32 = 0.35;
+
+/// Settings for one generated map
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapGenConfig {
+    pub seed: u64,
+    pub width: u32,
+    pub height: u32,
+    pub pole_count: usize,
+    /// How many of the poles also supply power
+    pub power_source_count: usize,
+}
+
+impl Default for MapGenConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            width: 24,
+            height: 24,
+            pole_count: 5,
+            power_source_count: 2,
+        }
+    }
+}
+
+impl MapGenConfig {
+    pub fn with_seed(seed: u64) -> Self {
+        Self { seed, ..Default::default() }
+    }
+
+    /// Read `--generate` (random seed) or `--seed <n>` from the command line.
+    /// Returns `None` when the hand-made map should be used, and an error naming
+    /// the bad value when the seed isn't a number.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut args = args.into_iter();
+        let mut config = None;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--generate" => {
+                    config.get_or_insert_with(|| Self::with_seed(rand::random()));
+                }
+                "--seed" => {
+                    let Some(seed) = args.next() else {
+                        return Err("--seed needs a number after it".to_string());
+                    };
+                    let seed = seed
+                        .parse()
+                        .map_err(|_| format!("--seed expects a whole number, got '{seed}'"))?;
+                    config = Some(Self::with_seed(seed));
+                }
+                _ => {}
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// A pole placed by the generator; powered poles carry their output
+#[derive(Clone, Debug, PartialEq)]
+pub struct GeneratedPole {
+    pub tile: TilePos,
+    pub power: Option<f32>,
+}
+
+/// A generated map in memory, before it is written out as TMX
+#[derive(Clone, Debug, PartialEq)]
+pub struct GeneratedMap {
+    pub config: MapGenConfig,
+    /// Global tile ids row by row; 0 means no ground
+    pub tiles: Vec<u32>,
+    pub spawn: TilePos,
+    pub poles: Vec<GeneratedPole>,
+}
+
+impl GeneratedMap {
+    pub fn gid(&self, tile: TilePos) -> u32 {
+        if !self.contains(tile) {
+            return 0;
+        }
+        self.tiles[(tile.y as u32 * self.config.width + tile.x as u32) as usize]
+    }
+
+    pub fn is_ground(&self, tile: TilePos) -> bool {
+        self.gid(tile) != 0
+    }
+
+    fn contains(&self, tile: TilePos) -> bool {
+        tile.x >= 0 && tile.y >= 0 && (tile.x as u32) < self.config.width && (tile.y as u32) < self.config.height
+    }
+
+    /// Ground tiles that can be walked to from `start`
+    pub fn reachable_from(&self, start: TilePos) -> HashSet<TilePos> {
+        let mut reachable = HashSet::new();
+        if !self.is_ground(start) {
+            return reachable;
+        }
+        let mut queue = VecDeque::from([start]);
+        reachable.insert(start);
+        while let Some(tile) = queue.pop_front() {
+            for neighbor in tile.neighbors() {
+                if self.is_ground(neighbor) && reachable.insert(neighbor) {
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        reachable
+    }
+
+    /// The map as a Tiled TMX document. `tileset_dir` is where the tilesets live,
+    /// relative to the file the map is written to.
+    pub fn to_tmx(&self, tileset_dir: &str) -> String {
+        let MapGenConfig { width, height, .. } = self.config;
+        let mut tmx = String::new();
+        let _ = writeln!(tmx, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+        let _ = writeln!(
+            tmx,
+            r#"<map version="1.10" orientation="isometric" renderorder="right-down" width="{width}" height="{height}" tilewidth="{TILE_WIDTH}" tileheight="{TILE_HEIGHT}" infinite="0" nextlayerid="3" nextobjectid="{}">"#,
+            self.poles.len() + 2
+        );
+        let _ = writeln!(tmx, r#" <properties>"#);
+        let _ = writeln!(tmx, r#"  <property name="seed" value="{}"/>"#, self.config.seed);
+        let _ = writeln!(tmx, r#" </properties>"#);
+        for (first_gid, file, _) in TILESETS {
+            let _ = writeln!(tmx, r#" <tileset firstgid="{first_gid}" source="{tileset_dir}/{file}"/>"#);
+        }
+
+        let _ = writeln!(tmx, r#" <layer id="1" name="Ground" width="{width}" height="{height}">"#);
+        let _ = writeln!(tmx, r#"  <data encoding="csv">"#);
+        let rows: Vec<String> = self
+            .tiles
+            .chunks(width as usize)
+            .map(|row| row.iter().map(u32::to_string).collect::<Vec<_>>().join(","))
+            .collect();
+        let _ = writeln!(tmx, "{}", rows.join(",\n"));
+        let _ = writeln!(tmx, "</data>");
+        let _ = writeln!(tmx, " </layer>");
+
+        // Isometric object positions are measured in tile heights along both tile axes
+        let object_position = |tile: TilePos| {
+            let half = TILE_HEIGHT as f32 / 2.0;
+            (tile.x as f32 * TILE_HEIGHT as f32 + half, tile.y as f32 * TILE_HEIGHT as f32 + half)
+        };
+        let _ = writeln!(tmx, r#" <objectgroup id="2" name="Objects">"#);
+        let (x, y) = object_position(self.spawn);
+        let _ = writeln!(
+            tmx,
+            r#"  <object id="1" name="Start" type="balthazar::map_objects::PlayerSpawn" x="{x}" y="{y}">
+   <properties>
+    <property name="name" value="start"/>
+   </properties>
+   <point/>
+  </object>"#
+        );
+        for (index, pole) in self.poles.iter().enumerate() {
+            let (x, y) = object_position(pole.tile);
+            let _ = writeln!(
+                tmx,
+                r#"  <object id="{}" name="Pole {}" type="balthazar::components::Pole" x="{x}" y="{y}">"#,
+                index + 2,
+                index + 1
+            );
+            if let Some(power) = pole.power {
+                let _ = writeln!(
+                    tmx,
+                    r#"   <properties>
+    <property name="balthazar::components::PowerSource" type="class" propertytype="balthazar::components::PowerSource">
+     <properties>
+      <property name="max_output" type="float" value="{power}"/>
+     </properties>
+    </property>
+   </properties>"#
+                );
+            }
+            let _ = writeln!(tmx, "   <point/>\n  </object>");
+        }
+        let _ = writeln!(tmx, " </objectgroup>");
+        let _ = writeln!(tmx, "</map>");
+        tmx
+    }
+
+    /// Write the map as TMX, pointing at the tilesets in `tileset_dir`
+    pub fn write_tmx(&self, path: &Path, tileset_dir: &str) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, self.to_tmx(tileset_dir))
+    }
+}
+
+/// Generate a map. The same config always gives the same map. The spawn sits in the
+/// largest connected patch of ground, any ground cut off from it is removed, and
+/// poles only go on the remaining tiles, so every pole can be walked to.
+pub fn generate_map(config: &MapGenConfig) -> GeneratedMap {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let (width, height) = (config.width as i32, config.height as i32);
+    let index = |tile: TilePos| (tile.y * width + tile.x) as usize;
+
+    // Smoothed value noise gives terrain patches rather than speckles
+    let mut noise: Vec<f32> = (0..width * height).map(|_| rng.gen::<f32>()).collect();
+    for _ in 0..SMOOTHING_PASSES {
+        let previous = noise.clone();
+        for y in 0..height {
+            for x in 0..width {
+                let tile = TilePos::new(x, y);
+                let (mut sum, mut count) = (previous[index(tile)], 1.0);
+                for neighbor in tile.neighbors() {
+                    if (0..width).contains(&neighbor.x) && (0..height).contains(&neighbor.y) {
+                        sum += previous[index(neighbor)];
+                        count += 1.0;
+                    }
+                }
+                noise[index(tile)] = sum / count;
+            }
+        }
+    }
+
+    // Spread the smoothed values back out so the thresholds below hit every terrain
+    let (low, high) = noise
+        .iter()
+        .fold((f32::MAX, f32::MIN), |(low, high), value| (low.min(*value), high.max(*value)));
+    let tiles: Vec<u32> = noise
+        .iter()
+        .map(|value| {
+            let value = (value - low) / (high - low).max(f32::EPSILON);
+            let (first_gid, count) = match value {
+                v if v < GAP_THRESHOLD => return 0,
+                v if v < 0.5 => (TILESETS[0].0, TILESETS[0].2),
+                v if v < 0.8 => (TILESETS[1].0, TILESETS[1].2),
+                _ => (TILESETS[2].0, TILESETS[2].2),
+            };
+            first_gid + rng.gen_range(0..count)
+        })
+        .collect();
+
+    let mut map = GeneratedMap {
+        config: config.clone(),
+        tiles,
+        spawn: TilePos::ZERO,
+        poles: Vec::new(),
+    };
+
+    // Keep only the largest connected patch of ground
+    let mut best: HashSet<TilePos> = HashSet::new();
+    let mut seen: HashSet<TilePos> = HashSet::new();
+    for y in 0..height {
+        for x in 0..width {
+            let tile = TilePos::new(x, y);
+            if map.is_ground(tile) && !seen.contains(&tile) {
+                let patch = map.reachable_from(tile);
+                seen.extend(patch.iter().copied());
+                if patch.len() > best.len() {
+                    best = patch;
+                }
+            }
+        }
+    }
+    if best.is_empty() {
+        // Nothing but holes: fall back to a single road tile
+        map.tiles[0] = TILESETS[2].0;
+        best.insert(TilePos::ZERO);
+    }
+    for y in 0..height {
+        for x in 0..width {
+            let tile = TilePos::new(x, y);
+            if !best.contains(&tile) {
+                map.tiles[index(tile)] = 0;
+            }
+        }
+    }
+
+    // Spawn in the middle of the patch, poles spread out across the rest of it
+    let mut ground: Vec<TilePos> = best.into_iter().collect();
+    ground.sort();
+    let centre = ground.iter().fold((0, 0), |(x, y), tile| (x + tile.x, y + tile.y));
+    let centre = TilePos::new(centre.0 / ground.len() as i32, centre.1 / ground.len() as i32);
+    map.spawn = *ground
+        .iter()
+        .min_by_key(|tile| tile.manhattan_distance(centre))
+        .expect("the patch has at least one tile");
+
+    let mut taken = vec![map.spawn];
+    let distance_to_taken =
+        |taken: &[TilePos], tile: &TilePos| taken.iter().map(|other| tile.manhattan_distance(*other)).min().unwrap_or(0);
+    for pole_index in 0..config.pole_count {
+        // Pick the farthest of a few random candidates to keep poles apart
+        let candidate = (0..8)
+            .map(|_| ground[rng.gen_range(0..ground.len())])
+            .filter(|tile| !taken.contains(tile))
+            .max_by_key(|tile| distance_to_taken(&taken, tile));
+        // When they all land on taken tiles, take the farthest free one instead
+        let candidate = candidate.or_else(|| {
+            ground
+                .iter()
+                .copied()
+                .filter(|tile| !taken.contains(tile))
+                .max_by_key(|tile| distance_to_taken(&taken, tile))
+        });
+        // A patch with no room left grows a road tile next to it
+        let candidate = candidate.or_else(|| {
+            let tile = ground
+                .iter()
+                .flat_map(|tile| tile.neighbors())
+                .find(|neighbor| map.contains(*neighbor) && !map.is_ground(*neighbor))?;
+            map.tiles[index(tile)] = TILESETS[2].0;
+            ground.push(tile);
+            Some(tile)
+        });
+        // Only a map without a single free tile left ends up short of poles
+        let Some(tile) = candidate else { break; };
+        taken.push(tile);
+        let power = (pole_index < config.power_source_count).then(|| rng.gen_range(4..=10) as f32);
+        map.poles.push(GeneratedPole { tile, power });
+    }
+
+    map
+}
+
+/// Level entry for a generated map. Every generated map is written to the same
+/// file, so the seed goes in the name: that keeps the explored tiles saved for one
+/// seed from showing up on another.
+pub fn generated_level(config: &MapGenConfig) -> LevelInfo {
+    LevelInfo {
+        name: format!("generated-{}", config.seed),
+        map_path: GENERATED_MAP_ASSET.to_string(),
+    }
+}
+
+/// Levels to start the game with: the hand-made map, or a freshly generated one
+/// when asked for on the command line (`--generate` or `--seed <n>`). A seed that
+/// can't be read is an error rather than a quiet fall back to the hand-made map.
+pub fn starting_levels(args: impl IntoIterator<Item = String>) -> Result<LevelRegistry, String> {
+    let mut registry = LevelRegistry::default();
+    let Some(config) = MapGenConfig::from_args(args)? else {
+        return Ok(registry);
+    };
+
+    let map = generate_map(&config);
+    match map.write_tmx(Path::new(GENERATED_MAP_FILE), GENERATED_TILESET_DIR) {
+        Ok(()) => {
+            println!("Generated map with seed {} ({GENERATED_MAP_FILE})", config.seed);
+            registry.levels[0] = generated_level(&config);
+        }
+        Err(err) => eprintln!("Could not write generated map, using map.tmx instead: {err}"),
+    }
+    Ok(registry)
+}
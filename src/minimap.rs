@@ -0,0 +1,280 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::asset::RenderAssetUsages;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::ui::RelativeCursorPosition;
+use bevy_ecs_tiled::prelude::*;
+
+use crate::components::{CordSystem, Player, Pole, PowerSource, SystemToggles};
+use crate::elevation::HeightMap;
+use crate::fog_of_war::FogOfWar;
+use crate::free_camera::FreeCamera;
+use crate::isometric::{MapGrid, TilePos};
+use crate::terrain::{TerrainMap, TerrainType};
+use crate::tiled_map::{for_each_map_tile, map_tile_bounds};
+
+/// Longest side of the minimap, in pixels
+pub const MINIMAP_MAX_SIZE: u32 = 160;
+
+pub const OUTSIDE_COLOR: [u8; 4] = [0, 0, 0, 0];
+pub const UNEXPLORED_COLOR: [u8; 4] = [12, 12, 16, 255];
+pub const TRAIL_COLOR: [u8; 4] = [255, 200, 40, 255];
+pub const POLE_COLOR: [u8; 4] = [200, 200, 200, 255];
+pub const POWER_SOURCE_COLOR: [u8; 4] = [80, 200, 255, 255];
+pub const ROBOT_COLOR: [u8; 4] = [255, 60, 60, 255];
+
+/// Minimap colour of a tile: its terrain, lighter the higher it is
+pub fn tile_color(terrain: TerrainType, level: i32) -> [u8; 4] {
+    let base: [u8; 3] = match terrain {
+        TerrainType::Plain => [96, 128, 72],
+        TerrainType::Road => [150, 140, 120],
+        TerrainType::Forest => [40, 90, 48],
+        TerrainType::Rock => [110, 104, 100],
+        TerrainType::Mud => [100, 76, 52],
+    };
+    let lift = 1.0 + 0.15 * level.max(0) as f32;
+    let [r, g, b] = base.map(|channel| (channel as f32 * lift).min(255.0) as u8);
+    [r, g, b, 255]
+}
+
+/// The part of the world a minimap shows and its size in pixels. Pixel
+/// coordinates start at the top left, like the image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MinimapView {
+    pub world_rect: Rect,
+    pub size: UVec2,
+}
+
+impl MinimapView {
+    /// View of `world_rect` with its longest side `max_size` pixels long
+    pub fn fit(world_rect: Rect, max_size: u32) -> Self {
+        let world_size = world_rect.size().max(Vec2::splat(1.0));
+        let pixels = world_size * (max_size as f32 / world_size.max_element());
+        Self {
+            world_rect,
+            size: pixels.round().as_uvec2().max(UVec2::ONE),
+        }
+    }
+
+    /// World position at a (fractional) pixel position
+    pub fn pixel_to_world(&self, pixel: Vec2) -> Vec2 {
+        let fraction = pixel / self.size.as_vec2();
+        Vec2::new(
+            self.world_rect.min.x + fraction.x * self.world_rect.width(),
+            self.world_rect.max.y - fraction.y * self.world_rect.height(),
+        )
+    }
+
+    /// Pixel a world position falls on, if it's on the minimap
+    pub fn world_to_pixel(&self, world: Vec2) -> Option<UVec2> {
+        let fraction = Vec2::new(
+            (world.x - self.world_rect.min.x) / self.world_rect.width(),
+            (self.world_rect.max.y - world.y) / self.world_rect.height(),
+        );
+        let pixel = (fraction * self.size.as_vec2()).floor();
+        let inside = pixel.cmpge(Vec2::ZERO).all() && pixel.cmplt(self.size.as_vec2()).all();
+        inside.then(|| pixel.as_uvec2())
+    }
+}
+
+/// Everything drawn on the minimap
+pub struct MinimapScene<'a> {
+    /// Colour of every tile of the map
+    pub tiles: &'a HashMap<TilePos, [u8; 4]>,
+    /// Tiles the robot has seen. The rest of the map is drawn dark, without markers.
+    pub explored: &'a HashSet<TilePos>,
+    pub trail: &'a [Vec2],
+    pub poles: &'a [Vec2],
+    pub power_sources: &'a [Vec2],
+    pub robot: Option<Vec2>,
+}
+
+/// RGBA pixels of a minimap, row by row from the top
+pub fn render_minimap(view: &MinimapView, grid: &MapGrid, scene: &MinimapScene) -> Vec<u8> {
+    let width = view.size.x as usize;
+    let mut pixels = vec![OUTSIDE_COLOR; width * view.size.y as usize];
+
+    for y in 0..view.size.y {
+        for x in 0..view.size.x {
+            let world = view.pixel_to_world(Vec2::new(x as f32, y as f32) + 0.5);
+            let tile = grid.world_to_tile(world);
+            if let Some(color) = scene.tiles.get(&tile) {
+                let explored = scene.explored.contains(&tile);
+                pixels[y as usize * width + x as usize] = if explored { *color } else { UNEXPLORED_COLOR };
+            }
+        }
+    }
+
+    let mut plot = |world: Vec2, color: [u8; 4]| {
+        if let Some(pixel) = view.world_to_pixel(world) {
+            pixels[pixel.y as usize * width + pixel.x as usize] = color;
+        }
+    };
+    // Step along each cord segment at least once per pixel
+    let world_per_pixel = view.world_rect.size() / view.size.as_vec2();
+    for segment in scene.trail.windows(2) {
+        let pixel_length = ((segment[1] - segment[0]) / world_per_pixel).abs().max_element();
+        let steps = pixel_length.ceil().max(1.0) as i32;
+        for step in 0..=steps {
+            plot(segment[0].lerp(segment[1], step as f32 / steps as f32), TRAIL_COLOR);
+        }
+    }
+
+    let seen = |world: &&Vec2| scene.explored.contains(&grid.world_to_tile(**world));
+    let markers = scene
+        .poles
+        .iter()
+        .filter(seen)
+        .map(|pole| (*pole, POLE_COLOR))
+        .chain(scene.power_sources.iter().filter(seen).map(|source| (*source, POWER_SOURCE_COLOR)))
+        .chain(scene.robot.map(|robot| (robot, ROBOT_COLOR)));
+    for (world, color) in markers {
+        // Markers are three pixels across
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                plot(world + Vec2::new(dx as f32, dy as f32) * world_per_pixel, color);
+            }
+        }
+    }
+
+    pixels.concat()
+}
+
+fn minimap_image(size: UVec2, data: Vec<u8>) -> Image {
+    Image::new(
+        Extent3d { width: size.x, height: size.y, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+}
+
+/// The minimap of the loaded map
+#[derive(Resource, Default)]
+pub struct Minimap {
+    /// `None` until a map has loaded
+    pub view: Option<MinimapView>,
+    pub tiles: HashMap<TilePos, [u8; 4]>,
+    pub image: Handle<Image>,
+}
+
+/// UI node showing the minimap
+#[derive(Component)]
+pub struct MinimapNode;
+
+pub fn setup_minimap(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let image = images.add(minimap_image(UVec2::ONE, OUTSIDE_COLOR.to_vec()));
+    commands.insert_resource(Minimap { image: image.clone(), ..default() });
+
+    // Bottom-right corner, sized once the map is known
+    commands.spawn((
+        MinimapNode,
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            right: Val::Px(10.0),
+            ..default()
+        },
+        ImageNode::new(image),
+        Interaction::default(),
+        RelativeCursorPosition::default(),
+        Visibility::Hidden,
+    ));
+}
+
+/// Work out the minimap's tiles and view whenever a map finishes loading or is modified
+pub fn build_minimap_tiles(
+    mut asset_events: MessageReader<AssetEvent<TiledMapAsset>>,
+    map_assets: Res<Assets<TiledMapAsset>>,
+    grid: Res<MapGrid>,
+    terrain: Res<TerrainMap>,
+    height_map: Res<HeightMap>,
+    mut minimap: ResMut<Minimap>,
+    mut node_query: Query<(&mut Node, &mut Visibility), With<MinimapNode>>,
+) {
+    for event in asset_events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event else {
+            continue;
+        };
+        let Some(map_asset) = map_assets.get(*id) else { continue; };
+
+        let mut tiles = HashMap::new();
+        for_each_map_tile(&map_asset.map, |_layer, tile, _| {
+            tiles.insert(tile, tile_color(terrain.get(tile), height_map.level(tile)));
+        });
+        let view = MinimapView::fit(grid.tiles_world_rect(map_tile_bounds(&map_asset.map)), MINIMAP_MAX_SIZE);
+        minimap.tiles = tiles;
+        minimap.view = Some(view);
+
+        for (mut node, mut visibility) in node_query.iter_mut() {
+            node.width = Val::Px(view.size.x as f32);
+            node.height = Val::Px(view.size.y as f32);
+            *visibility = Visibility::Inherited;
+        }
+    }
+}
+
+/// Redraw the minimap when what it shows changes
+#[allow(clippy::too_many_arguments)]
+pub fn update_minimap(
+    minimap: Res<Minimap>,
+    grid: Res<MapGrid>,
+    fog: Res<FogOfWar>,
+    cord_system: Option<Res<CordSystem>>,
+    player_query: Query<Ref<Transform>, With<Player>>,
+    pole_query: Query<(&Transform, Has<PowerSource>), With<Pole>>,
+    source_query: Query<&Transform, (With<PowerSource>, Without<Pole>)>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let Some(view) = minimap.view else { return; };
+    let robot = player_query.single().ok();
+    let cord_changed = cord_system.as_ref().is_some_and(|cord_system| cord_system.is_changed());
+    let robot_moved = robot.as_ref().is_some_and(|transform| transform.is_changed());
+    if !minimap.is_changed() && !fog.is_changed() && !cord_changed && !robot_moved {
+        return;
+    }
+
+    let (powered, unpowered): (Vec<_>, Vec<_>) = pole_query.iter().partition(|(_, has_power)| *has_power);
+    let position = |transform: &Transform| transform.translation.truncate();
+    let poles: Vec<Vec2> = unpowered.into_iter().map(|(transform, _)| position(transform)).collect();
+    let power_sources: Vec<Vec2> = powered
+        .into_iter()
+        .map(|(transform, _)| position(transform))
+        .chain(source_query.iter().map(position))
+        .collect();
+    let scene = MinimapScene {
+        tiles: &minimap.tiles,
+        explored: &fog.explored,
+        trail: cord_system.as_ref().map_or(&[][..], |cord_system| cord_system.trail_path.as_slice()),
+        poles: &poles,
+        power_sources: &power_sources,
+        robot: robot.map(|transform| transform.translation.truncate()),
+    };
+
+    let data = render_minimap(&view, &grid, &scene);
+    if let Some(image) = images.get_mut(&minimap.image) {
+        *image = minimap_image(view.size, data);
+    }
+}
+
+/// Clicking the minimap sets the camera free and glides it to the spot clicked
+pub fn minimap_click(
+    minimap: Res<Minimap>,
+    node_query: Query<(&Interaction, &RelativeCursorPosition), (Changed<Interaction>, With<MinimapNode>)>,
+    mut toggles: ResMut<SystemToggles>,
+    mut free: ResMut<FreeCamera>,
+) {
+    let Some(view) = minimap.view else { return; };
+    for (interaction, cursor) in node_query.iter() {
+        let (Interaction::Pressed, Some(normalized)) = (interaction, cursor.normalized) else {
+            continue;
+        };
+        // The relative cursor position runs from -0.5 at the top left to 0.5 at the bottom right
+        let pixel = (normalized + 0.5) * view.size.as_vec2();
+        toggles.camera_follow = false;
+        free.returning = false;
+        free.pan_to = Some(view.pixel_to_world(pixel));
+    }
+}
@@ -0,0 +1,89 @@
+use bevy::camera::CameraProjection;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::elevation::{HeightMap, LEVEL_HEIGHT};
+use crate::isometric::{MapGrid, TilePos};
+
+const HOVER_OUTLINE_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.8);
+
+/// Tile under the mouse cursor, updated every frame
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq)]
+pub struct HoveredTile {
+    /// `None` while the cursor is outside the window
+    pub tile: Option<TilePos>,
+    /// Cursor position on the ground plane (before any elevation is accounted for)
+    pub world_position: Option<Vec2>,
+}
+
+/// Project a cursor position (in logical window pixels) through an orthographic
+/// projection and camera transform onto the world plane. The projection's area is
+/// recomputed from its current scale, so zooming earlier in the frame is picked up
+/// straight away.
+pub fn viewport_to_world(
+    cursor: Vec2,
+    viewport: Rect,
+    projection: &OrthographicProjection,
+    camera_transform: &GlobalTransform,
+) -> Vec2 {
+    let mut projection = projection.clone();
+    projection.update(viewport.width(), viewport.height());
+
+    // Window coordinates start at the top left, projection space at the bottom left
+    let mut relative = (cursor - viewport.min) / viewport.size();
+    relative.y = 1.0 - relative.y;
+
+    let local = projection.area.min + relative * projection.area.size();
+    camera_transform.transform_point(local.extend(0.0)).truncate()
+}
+
+/// Tile drawn under a point on the ground plane. Raised tiles are drawn higher up
+/// the screen and cover the tiles behind them, so they are checked from the top level down.
+pub fn pick_tile(grid: &MapGrid, height_map: &HeightMap, world_pos: Vec2) -> TilePos {
+    for level in (1..=height_map.max_level()).rev() {
+        let tile = grid.world_to_tile(world_pos - Vec2::Y * level as f32 * LEVEL_HEIGHT);
+        if height_map.level(tile) == level {
+            return tile;
+        }
+    }
+    grid.world_to_tile(world_pos)
+}
+
+/// Work out which tile the cursor is over. The camera is a top-level entity, so its
+/// local `Transform` is used directly and camera movement earlier in the frame counts.
+pub fn update_hovered_tile(
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &Transform, &Projection), With<Camera2d>>,
+    grid: Res<MapGrid>,
+    height_map: Res<HeightMap>,
+    mut hovered: ResMut<HoveredTile>,
+) {
+    let (Ok(window), Ok((camera, camera_transform, projection))) = (window_query.single(), camera_query.single()) else {
+        return;
+    };
+
+    let camera_transform = GlobalTransform::from(*camera_transform);
+    let world_position = window.cursor_position().and_then(|cursor| match projection {
+        Projection::Orthographic(ortho) => {
+            let viewport = camera.logical_viewport_rect()?;
+            Some(viewport_to_world(cursor, viewport, ortho, &camera_transform))
+        }
+        _ => camera.viewport_to_world_2d(&camera_transform, cursor).ok(),
+    });
+
+    let tile = world_position.map(|world_pos| pick_tile(&grid, &height_map, world_pos));
+    hovered.set_if_neq(HoveredTile { tile, world_position });
+}
+
+/// Outline the hovered tile at the height it is drawn at
+pub fn draw_hover_outline(
+    mut gizmos: Gizmos,
+    hovered: Res<HoveredTile>,
+    grid: Res<MapGrid>,
+    height_map: Res<HeightMap>,
+) {
+    let Some(tile) = hovered.tile else { return; };
+    let lift = Vec2::Y * height_map.height_offset(tile);
+    let outline: Vec<Vec2> = grid.tile_outline(tile).into_iter().map(|corner| corner + lift).collect();
+    gizmos.linestrip_2d(outline.iter().chain(outline.first()).copied(), HOVER_OUTLINE_COLOR);
+}
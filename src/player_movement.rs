@@ -1,13 +1,52 @@
 use bevy::prelude::*;
-use crate::components::{Battery, Player, SystemToggles, PlayerSprite, PlayerDirection, GridMovement, IsometricGrid};
+use crate::components::{Battery, Player, SystemToggles, PlayerSprite, PlayerDirection, GridMovement};
+use crate::isometric::MapGrid;
+use crate::terrain::TerrainMap;
+use crate::elevation::HeightMap;
+use crate::grid_objects::GridObstacles;
+use crate::power_system::BatteryFlow;
 
+// Sent by `grid_movement_system` whenever an entity arrives on its target tile
+#[derive(Message)]
+pub struct GridStepCompleted {
+    pub entity: Entity,
+    pub position: Vec2,
+}
+
+/// Movement keys and the screen direction each one stands for
+const MOVEMENT_KEYS: [(KeyCode, Vec2); 4] = [
+    (KeyCode::KeyW, Vec2::Y),
+    (KeyCode::KeyS, Vec2::NEG_Y),
+    (KeyCode::KeyA, Vec2::NEG_X),
+    (KeyCode::KeyD, Vec2::X),
+];
+
+/// Combined screen direction of the movement keys that pass a check
+pub fn movement_keys_direction(
+    keys: &ButtonInput<KeyCode>,
+    is_down: fn(&ButtonInput<KeyCode>, KeyCode) -> bool,
+) -> Vec2 {
+    MOVEMENT_KEYS
+        .iter()
+        .filter(|(key, _)| is_down(keys, *key))
+        .map(|(_, direction)| *direction)
+        .sum()
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn move_player(
     keys: Res<ButtonInput<KeyCode>>,
-    grid: Res<IsometricGrid>,
+    grid: Res<MapGrid>,
+    terrain: Res<TerrainMap>,
+    height_map: Res<HeightMap>,
     mut player_query: Query<(&Transform, &mut GridMovement, &mut Battery), With<Player>>,
+    mut obstacles: GridObstacles,
+    mut flow: Option<ResMut<BatteryFlow>>,
     toggles: Res<SystemToggles>,
 ) {
     if !toggles.player_movement { return; }
+    // With the camera set free, the movement keys pan the camera instead
+    if !toggles.camera_follow { return; }
     
     for (transform, mut grid_movement, mut battery) in player_query.iter_mut() {
         // Check if we're currently moving to a target
@@ -15,35 +54,45 @@ pub fn move_player(
             continue; // Let the grid_movement_system handle it
         }
         
-        // Check for new movement input (only one key press at a time for grid movement)
-        // Isometric movement: keys move along diagonal axes
-        // W: up-right (NE), S: down-left (SW), A: up-left (NW), D: down-right (SE)
-        let mut target_offset = None;
-        
-        if keys.just_pressed(KeyCode::KeyW) {
-            // Move up-right in isometric space (NE)
-            target_offset = Some(Vec2::new(grid.tile_size / 2.0, grid.tile_size / 4.0));
-        } else if keys.just_pressed(KeyCode::KeyS) {
-            // Move down-left in isometric space (SW)
-            target_offset = Some(Vec2::new(-grid.tile_size / 2.0, -grid.tile_size / 4.0));
-        } else if keys.just_pressed(KeyCode::KeyA) {
-            // Move up-left in isometric space (NW)
-            target_offset = Some(Vec2::new(-grid.tile_size / 2.0, grid.tile_size / 4.0));
-        } else if keys.just_pressed(KeyCode::KeyD) {
-            // Move down-right in isometric space (SE)
-            target_offset = Some(Vec2::new(grid.tile_size / 2.0, -grid.tile_size / 4.0));
+        // Movement starts when a key goes down. Keys held together ask for a
+        // diagonal where the grid has one (8-way orthogonal maps, hexagons);
+        // otherwise the key just pressed decides.
+        // `input_direction` turns the keys into a screen direction for the map's
+        // layout (along the diagonals on diamond maps), and `neighbor_toward` picks
+        // the neighbour lying that way on screen rather than by its tile step, so
+        // the keys hold on every layout and however the view is turned
+        let pressed = movement_keys_direction(&keys, ButtonInput::just_pressed);
+        if pressed == Vec2::ZERO {
+            continue;
         }
+        let held = movement_keys_direction(&keys, ButtonInput::pressed);
+        
+        let current_tile = grid.world_to_tile(transform.translation.truncate());
+        let target_tile = grid
+            .neighbor_toward(current_tile, grid.input_direction(held))
+            .or_else(|| grid.neighbor_toward(current_tile, grid.input_direction(pressed)));
         
         // Start moving to target if input detected and battery has charge
-        if let Some(offset) = target_offset {
-            if battery.current_charge > 0.0 {
-                let current_pos = transform.translation.truncate();
-                grid_movement.target_position = Some(current_pos + offset);
+        if let Some(target_tile) = target_tile {
+            let target = grid.tile_to_world(target_tile);
+            
+            // Cliffs can't be crossed; changing level needs a ramp
+            if !height_map.can_step(&grid.layout, current_tile, target_tile) {
+                continue;
+            }
+
+            // Closed doors block the way, crates get pushed if there's room behind them
+            if battery.current_charge > 0.0 && obstacles.try_enter(&grid, &height_map, current_tile, target_tile) {
+                grid_movement.target_position = Some(target);
                 grid_movement.is_moving = true;
                 
-                // Drain some battery for initiating movement
-                let drain_per_move = 2.0;
+                // Drain battery based on the terrain being stepped onto
+                let drain_per_move = terrain.at_world(&grid, target).step_cost();
+                let before = battery.current_charge;
                 battery.current_charge = (battery.current_charge - drain_per_move).max(0.0);
+                if let Some(flow) = flow.as_mut() {
+                    flow.drained += before - battery.current_charge;
+                }
             }
         }
     }
@@ -51,12 +100,18 @@ pub fn move_player(
 
 pub fn grid_movement_system(
     time: Res<Time>,
-    mut player_query: Query<(&mut Transform, &mut GridMovement), With<Player>>,
+    grid: Res<MapGrid>,
+    terrain: Res<TerrainMap>,
+    mut mover_query: Query<(Entity, &mut Transform, &mut GridMovement, Has<Player>)>,
+    mut step_events: MessageWriter<GridStepCompleted>,
     toggles: Res<SystemToggles>,
 ) {
-    if !toggles.player_movement { return; }
-    
-    for (mut transform, mut grid_movement) in player_query.iter_mut() {
+    // Moves the player and anything else on the grid, such as pushed crates.
+    // Turning player movement off only holds the robot; crates finish their slide.
+    for (entity, mut transform, mut grid_movement, is_player) in mover_query.iter_mut() {
+        if is_player && !toggles.player_movement {
+            continue;
+        }
         if let Some(target) = grid_movement.target_position {
             let current_pos = transform.translation.truncate();
             let direction = target - current_pos;
@@ -69,10 +124,13 @@ pub fn grid_movement_system(
                 transform.translation.y = target.y;
                 grid_movement.target_position = None;
                 grid_movement.is_moving = false;
+                step_events.write(GridStepCompleted { entity, position: target });
             } else {
-                // Move towards target using direct transform manipulation
+                // Move towards target using direct transform manipulation,
+                // at the speed of the terrain being entered
                 let direction_normalized = direction.normalize();
-                let move_delta = direction_normalized * grid_movement.move_speed * time.delta_secs();
+                let speed = grid_movement.move_speed * terrain.at_world(&grid, target).speed_multiplier();
+                let move_delta = direction_normalized * speed * time.delta_secs();
                 
                 // Make sure we don't overshoot
                 if move_delta.length() >= distance {
@@ -80,6 +138,7 @@ pub fn grid_movement_system(
                     transform.translation.y = target.y;
                     grid_movement.target_position = None;
                     grid_movement.is_moving = false;
+                    step_events.write(GridStepCompleted { entity, position: target });
                 } else {
                     transform.translation.x += move_delta.x;
                     transform.translation.y += move_delta.y;
@@ -97,6 +156,8 @@ pub fn update_player_sprite_direction(
     toggles: Res<SystemToggles>,
 ) {
     if !toggles.player_rotation { return; }
+    // With the camera set free, the movement keys pan the camera instead
+    if !toggles.camera_follow { return; }
     
     for (mut player_sprite, mut sprite, _grid_movement) in player_query.iter_mut() {
         // Check for key just pressed to update direction immediately
@@ -1,24 +1,93 @@
 use bevy::prelude::*;
-use crate::components::{Battery, Player, PowerSource, Pole, CordSystem, SolarPanel};
+use crate::components::{Battery, Player, PowerSource, Pole, CordSystem, SolarPanel, PowerRoute};
 use crate::day_night_cycle::DayNightCycle;
 
+/// How quickly the charge rates shown on the HUD follow changes, in seconds.
+/// Steps drain the battery in lumps, so the drain is averaged over a few of them.
+pub const CHARGE_RATE_SMOOTHING_SECS: f32 = 1.5;
+
+/// Charge flowing in and out of the robot's battery. The systems that charge or
+/// drain it add what they moved, and `update_charge_rates` turns that into rates.
+#[derive(Resource, Default, Debug)]
+pub struct BatteryFlow {
+    /// Charge gained from the solar panel since the rates were last updated
+    pub solar_in: f32,
+    /// Charge gained through the cord since the rates were last updated
+    pub pole_in: f32,
+    /// Charge used since the rates were last updated
+    pub drained: f32,
+    /// Smoothed rates, in charge per second
+    pub solar_rate: f32,
+    pub pole_rate: f32,
+    pub drain_rate: f32,
+}
+
+impl BatteryFlow {
+    /// Charge gained per second, less what is used
+    pub fn net_rate(&self) -> f32 {
+        self.solar_rate + self.pole_rate - self.drain_rate
+    }
+
+    /// Fold the charge moved over the last `delta_secs` into the rates
+    pub fn update_rates(&mut self, delta_secs: f32) {
+        if delta_secs <= 0.0 {
+            return;
+        }
+        let blend = 1.0 - (-delta_secs / CHARGE_RATE_SMOOTHING_SECS).exp();
+        let follow = |rate: &mut f32, amount: f32| *rate += (amount / delta_secs - *rate) * blend;
+        follow(&mut self.solar_rate, self.solar_in);
+        follow(&mut self.pole_rate, self.pole_in);
+        follow(&mut self.drain_rate, self.drained);
+        self.solar_in = 0.0;
+        self.pole_in = 0.0;
+        self.drained = 0.0;
+    }
+}
+
+/// Seconds until the battery is full (`true`) or empty (`false`) at a net charge
+/// rate; `None` when it's holding steady or already there
+pub fn battery_time_left(battery: &Battery, net_rate: f32) -> Option<(f32, bool)> {
+    const STEADY: f32 = 0.01;
+    if net_rate > STEADY && battery.current_charge < battery.max_charge {
+        Some(((battery.max_charge - battery.current_charge) / net_rate, true))
+    } else if net_rate < -STEADY && battery.current_charge > 0.0 {
+        Some((battery.current_charge / -net_rate, false))
+    } else {
+        None
+    }
+}
+
+pub fn update_charge_rates(time: Res<Time<Virtual>>, mut flow: ResMut<BatteryFlow>) {
+    flow.update_rates(time.delta_secs());
+}
+
 pub fn transfer_power(
     time: Res<Time<Virtual>>,
     cord_system: Option<Res<CordSystem>>,
     mut player_query: Query<&mut Battery, With<Player>>,
-    pole_query: Query<&PowerSource, With<Pole>>,
+    pole_query: Query<(&PowerSource, Option<&PowerRoute>), With<Pole>>,
+    flow: Option<ResMut<BatteryFlow>>,
 ) {
     let Some(cord_system) = cord_system else { return; };
     
     // Only transfer power if player is attached to a pole
     if let Some(attached_pole) = cord_system.attached_pole {
         // Check if the pole has a power source
-        if let Ok(power_source) = pole_query.get(attached_pole) {
+        if let Ok((power_source, route)) = pole_query.get(attached_pole) {
+            // Poles on a switched-off route deliver nothing
+            if route.is_some_and(|route| !route.enabled) {
+                return;
+            }
+            
             // Transfer power to player
             if let Ok(mut battery) = player_query.single_mut() {
                 if battery.current_charge < battery.max_charge {
                     let charge_amount = power_source.max_output * time.delta_secs();
+                    let before = battery.current_charge;
                     battery.current_charge = (battery.current_charge + charge_amount).min(battery.max_charge);
+                    if let Some(mut flow) = flow {
+                        flow.pole_in += battery.current_charge - before;
+                    }
                 }
             }
         }
@@ -29,6 +98,7 @@ pub fn solar_charge(
     time: Res<Time<Virtual>>,
     cycle: Res<DayNightCycle>,
     mut query: Query<(&SolarPanel, &mut Battery), With<Player>>,
+    mut flow: Option<ResMut<BatteryFlow>>,
 ) {
     // Charge amount scales with brightness (0.2 at night, 1.0 at noon)
     let brightness = cycle.get_brightness();
@@ -36,7 +106,11 @@ pub fn solar_charge(
     for (solar_panel, mut battery) in query.iter_mut() {
         if battery.current_charge < battery.max_charge {
             let charge_amount = solar_panel.max_output * brightness * time.delta_secs();
+            let before = battery.current_charge;
             battery.current_charge = (battery.current_charge + charge_amount).min(battery.max_charge);
+            if let Some(flow) = flow.as_mut() {
+                flow.solar_in += battery.current_charge - before;
+            }
         }
     }
 }
@@ -0,0 +1,102 @@
+use bevy::prelude::*;
+use bevy_ecs_tiled::prelude::*;
+
+use crate::components::{Battery, CordSystem, GridMovement, Player};
+use crate::cord_system::{disconnect_cord_from_pole, CordMeshSegment};
+use crate::day_night_cycle::DayNightCycle;
+use crate::grid_objects::GridPlaced;
+use crate::isometric::MapGrid;
+use crate::power_system::BatteryFlow;
+use crate::special_tiles::TileMotion;
+use crate::tiled_map::spawn_tiled_map;
+use crate::undo::UndoHistory;
+use crate::view_rotation::ViewRotated;
+
+// A reset starts the current level over without reloading anything from disk:
+// the map is respawned from the asset already in memory, which brings back every
+// crate, door and switch as Tiled placed it. The robot keeps its entity and goes
+// back to the spawn point once the map's objects are in, as on a level change.
+
+/// Start the level over
+pub const RESET_KEY: KeyCode = KeyCode::KeyR;
+
+/// The Reset button in the system controls panel
+#[derive(Component)]
+pub struct ResetButton;
+
+/// Request to put the world back the way it was when the level started
+#[derive(Message, Clone, Debug, Default)]
+pub struct ResetWorld;
+
+/// Ask for a reset with R or the Reset button
+pub fn request_reset(
+    keys: Res<ButtonInput<KeyCode>>,
+    button_query: Query<&Interaction, (Changed<Interaction>, With<ResetButton>)>,
+    mut reset_events: MessageWriter<ResetWorld>,
+) {
+    let pressed = button_query.iter().any(|interaction| *interaction == Interaction::Pressed);
+    if keys.just_pressed(RESET_KEY) || pressed {
+        reset_events.write(ResetWorld);
+    }
+}
+
+/// Unplug and clear the cord, fill the battery, turn the clock back, forget the
+/// undo history and respawn the map. A turned view is turned back too, since the
+/// respawned map comes in unturned.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn reset_world(
+    mut commands: Commands,
+    mut reset_events: MessageReader<ResetWorld>,
+    mut grid: ResMut<MapGrid>,
+    mut rotated_events: MessageWriter<ViewRotated>,
+    cord_system: Option<ResMut<CordSystem>>,
+    mut cycle: ResMut<DayNightCycle>,
+    mut history: ResMut<UndoHistory>,
+    flow: Option<ResMut<BatteryFlow>>,
+    mut player_query: Query<(&mut Battery, &mut GridMovement, Option<&mut TileMotion>), With<Player>>,
+    mesh_query: Query<Entity, With<CordMeshSegment>>,
+    map_query: Query<(Entity, &TiledMap)>,
+    placed_query: Query<Entity, With<GridPlaced>>,
+) {
+    if reset_events.read().count() == 0 {
+        return;
+    }
+
+    if let Some(mut cord_system) = cord_system {
+        disconnect_cord_from_pole(&mut cord_system);
+        cord_system.visual_meshes.clear();
+    }
+    for entity in mesh_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    for (mut battery, mut grid_movement, tile_motion) in player_query.iter_mut() {
+        battery.current_charge = battery.max_charge;
+        grid_movement.target_position = None;
+        grid_movement.is_moving = false;
+        if let Some(mut tile_motion) = tile_motion {
+            *tile_motion = TileMotion::default();
+        }
+    }
+    *cycle = DayNightCycle::default();
+    if let Some(mut flow) = flow {
+        *flow = BatteryFlow::default();
+    }
+    history.clear();
+
+    if grid.quarter_turns != 0 {
+        let previous = grid.clone();
+        *grid = previous.rotated(-(previous.quarter_turns as i32));
+        rotated_events.write(ViewRotated { previous });
+    }
+
+    // Everything spawned from the map goes, and a fresh copy comes in from the
+    // loaded asset; `move_player_to_spawn` puts the robot on its spawn point
+    for entity in placed_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    for (entity, map) in map_query.iter() {
+        commands.entity(entity).despawn();
+        spawn_tiled_map(&mut commands, map.0.clone());
+    }
+}
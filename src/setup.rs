@@ -1,11 +1,18 @@
 use bevy::prelude::*;
 use bevy_light_2d::prelude::*;
-use crate::components::{Battery, Player, CordSystem, CordMaterial, SolarPanel, PlayerSprite, PlayerDirection, GridMovement, IsometricGrid};
+use crate::components::{Battery, Player, CordSystem, CordMaterial, SolarPanel, PlayerSprite, PlayerDirection, GridMovement};
+use crate::isometric::{MapGrid, TilePos};
+use crate::elevation::Elevation;
+use crate::special_tiles::TileMotion;
 
 // Z-layer constants for rendering depth
 // Tilemap is typically at Z = 0-100 range
 pub const Z_CORD_LAYER: f32 = 150.0;  // Above tilemap, below player
 pub const Z_GAME_LAYER: f32 = 200.0;  // Player and game entities
+pub const Z_FOG_LAYER: f32 = 250.0;   // Fog of war, hiding whatever stands on unexplored tiles
+
+// Size of one frame of the robot spritesheet
+pub const PLAYER_SPRITE_SIZE: f32 = 64.0;
 
 pub fn setup(
     mut commands: Commands,
@@ -59,10 +66,10 @@ pub fn setup(
         material: cord_material,
     });
     
-    // Initialize isometric grid (64 pixel tiles to match sprite size)
-    commands.insert_resource(IsometricGrid {
-        tile_size: 64.0,
-    });
+    // Initialize the grid (isometric 64x32 tiles to match the map); it is lined up
+    // with the Tiled map, and takes on its layout, once that has loaded
+    let grid = MapGrid::new(64.0, 32.0);
+    commands.insert_resource(grid.clone());
     
     // Spawn 2D camera with orthographic projection for isometric view
     let mut projection = OrthographicProjection::default_2d();
@@ -78,20 +85,12 @@ pub fn setup(
         },
     ));
 
-    // Convert isometric grid coordinates to world positions
-    let tile_size = 64.0;
-    let grid_to_world = |grid_x: f32, grid_y: f32| -> Vec2 {
-        let x = (grid_x - grid_y) * tile_size / 2.0;
-        let y = (grid_x + grid_y) * tile_size / 4.0;
-        Vec2::new(x, y)
-    };
-    
     // Spawn player at grid (0, 0)
-    let player_pos = grid_to_world(0.0, 0.0);
+    let player_pos = grid.tile_to_world(TilePos::ZERO);
     
     // Load robot spritesheet
     let texture_handle = asset_server.load("robo.png");
-    let layout = TextureAtlasLayout::from_grid(UVec2::splat(64), 1, 4, None, None);
+    let layout = TextureAtlasLayout::from_grid(UVec2::splat(PLAYER_SPRITE_SIZE as u32), 1, 4, None, None);
     let texture_atlas_layout = texture_atlas_layouts.add(layout);
     
     // Spawn player
@@ -113,6 +112,8 @@ pub fn setup(
             move_speed: 400.0,
             is_moving: false,
         },
+        Elevation::default(),
+        TileMotion::default(),
         Battery {
             max_charge: 100.0,
             current_charge: 100.0,
@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_ecs_tiled::prelude::*;
+
+use crate::components::{CordSystem, GridMovement, Player, SystemToggles};
+use crate::elevation::HeightMap;
+use crate::grid_objects::GridObstacles;
+use crate::isometric::{GridDirection, MapGrid, TilePos};
+use crate::player_movement::GridStepCompleted;
+use crate::tiled_map::for_each_map_tile;
+use crate::undo::UndoHistory;
+
+/// Default time the robot waits on a conveyor before being carried a tile
+pub const DEFAULT_CONVEYOR_INTERVAL: f32 = 0.5;
+
+/// Tiles that move the robot without input, configured with tile properties in Tiled:
+/// - `conveyor` (direction such as `ne`) and optional `conveyor_interval` (seconds)
+/// - `ice` (bool)
+/// - `teleport_pad` (name of this pad) and `teleport_to` (name of the linked pad)
+#[derive(Clone, Debug, PartialEq)]
+pub enum TileBehavior {
+    Conveyor { direction: GridDirection, interval: f32 },
+    Ice,
+    Teleport { target: String },
+}
+
+/// Special behaviour of every tile in the loaded map, keyed by Tiled tile coordinates
+#[derive(Resource, Default)]
+pub struct SpecialTileMap {
+    pub tiles: HashMap<TilePos, TileBehavior>,
+    /// Tile of each named teleport pad
+    pub pads: HashMap<String, TilePos>,
+}
+
+impl SpecialTileMap {
+    pub fn get(&self, tile: TilePos) -> Option<&TileBehavior> {
+        self.tiles.get(&tile)
+    }
+
+    /// Where a teleport pad on `tile` sends the robot
+    pub fn teleport_destination(&self, tile: TilePos) -> Option<TilePos> {
+        match self.tiles.get(&tile) {
+            Some(TileBehavior::Teleport { target }) => self.pads.get(target).copied(),
+            _ => None,
+        }
+    }
+
+    pub fn from_tiled_map(map: &tiled::Map) -> Self {
+        let mut special_tiles = Self::default();
+        for_each_map_tile(map, |_layer, pos, layer_tile| {
+            let Some(tile) = layer_tile.get_tile() else { return; };
+            let properties = &tile.properties;
+
+            if let Some(tiled::PropertyValue::StringValue(name)) = properties.get("teleport_pad") {
+                special_tiles.pads.insert(name.clone(), pos);
+            }
+
+            let behavior = if let Some(tiled::PropertyValue::StringValue(name)) = properties.get("conveyor") {
+                GridDirection::from_name(name).map(|direction| {
+                    let interval = match properties.get("conveyor_interval") {
+                        Some(tiled::PropertyValue::FloatValue(interval)) => *interval,
+                        _ => DEFAULT_CONVEYOR_INTERVAL,
+                    };
+                    TileBehavior::Conveyor { direction, interval }
+                })
+            } else if let Some(tiled::PropertyValue::BoolValue(true)) = properties.get("ice") {
+                Some(TileBehavior::Ice)
+            } else if let Some(tiled::PropertyValue::StringValue(target)) = properties.get("teleport_to") {
+                Some(TileBehavior::Teleport { target: target.clone() })
+            } else {
+                None
+            };
+
+            if let Some(behavior) = behavior {
+                special_tiles.tiles.insert(pos, behavior);
+            }
+        });
+        special_tiles
+    }
+}
+
+/// Per-entity bookkeeping for forced movement
+#[derive(Component, Default)]
+pub struct TileMotion {
+    /// Tile the entity stood on before its last completed step
+    pub previous_tile: Option<TilePos>,
+    /// Time spent standing on the current conveyor
+    pub conveyor_timer: f32,
+    /// Set while arriving through a teleport so the destination pad doesn't send it straight back
+    pub teleported: bool,
+}
+
+/// Rebuild the `SpecialTileMap` whenever a Tiled map finishes loading or is modified
+pub fn build_special_tile_map(
+    mut asset_events: MessageReader<AssetEvent<TiledMapAsset>>,
+    map_assets: Res<Assets<TiledMapAsset>>,
+    mut special_tiles: ResMut<SpecialTileMap>,
+) {
+    for event in asset_events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event else {
+            continue;
+        };
+        if let Some(map_asset) = map_assets.get(*id) {
+            *special_tiles = SpecialTileMap::from_tiled_map(&map_asset.map);
+        }
+    }
+}
+
+/// Whether forced movement may carry the robot from `from` onto `to`.
+/// Unlike walking, sliding into a crate stops the robot instead of pushing it.
+fn can_slide(
+    grid: &MapGrid,
+    height_map: &HeightMap,
+    obstacles: &GridObstacles,
+    from: TilePos,
+    to: TilePos,
+) -> bool {
+    height_map.can_step(&grid.layout, from, to) && !obstacles.blocked_tiles(grid).contains_key(&to)
+}
+
+/// React to the robot arriving on ice or a teleport pad
+pub fn apply_tile_behaviors(
+    mut step_events: MessageReader<GridStepCompleted>,
+    grid: Res<MapGrid>,
+    height_map: Res<HeightMap>,
+    special_tiles: Res<SpecialTileMap>,
+    history: Res<UndoHistory>,
+    cord_system: Option<Res<CordSystem>>,
+    obstacles: GridObstacles,
+    mut player_query: Query<(&mut Transform, &mut GridMovement, &mut TileMotion), With<Player>>,
+    toggles: Res<SystemToggles>,
+) {
+    if !toggles.player_movement { return; }
+
+    for event in step_events.read() {
+        let Ok((mut transform, mut grid_movement, mut tile_motion)) = player_query.get_mut(event.entity) else {
+            continue;
+        };
+        let tile = grid.world_to_tile(event.position);
+        let previous = tile_motion.previous_tile.replace(tile).filter(|previous| *previous != tile);
+
+        // Walking back through the undo history shouldn't set anything off
+        if history.pending_restore.is_some() {
+            continue;
+        }
+        if std::mem::take(&mut tile_motion.teleported) {
+            continue;
+        }
+
+        match special_tiles.get(tile) {
+            Some(TileBehavior::Ice) => {
+                // Keep sliding in the same direction until something is in the way
+                let Some(previous) = previous else { continue; };
+                let next = grid.continue_line(previous, tile);
+                if can_slide(&grid, &height_map, &obstacles, tile, next) {
+                    grid_movement.target_position = Some(grid.tile_to_world(next));
+                    grid_movement.is_moving = true;
+                }
+            }
+            Some(TileBehavior::Teleport { .. }) => {
+                let Some(destination) = special_tiles.teleport_destination(tile) else { continue; };
+                // A cord can't follow the robot through a teleport
+                if cord_system.as_ref().is_some_and(|cord_system| cord_system.attached_pole.is_some()) {
+                    println!("Teleport refused: detach the cord first!");
+                    continue;
+                }
+                if obstacles.blocked_tiles(&grid).contains_key(&destination) {
+                    continue;
+                }
+                // Jump onto the destination and let grid movement complete the step there
+                let destination_pos = grid.tile_to_world(destination);
+                transform.translation.x = destination_pos.x;
+                transform.translation.y = destination_pos.y;
+                grid_movement.target_position = Some(destination_pos);
+                grid_movement.is_moving = true;
+                tile_motion.teleported = true;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Carry the robot one tile along a conveyor each time its interval elapses.
+/// Also remembers the tile the robot rests on, so the direction of its next step
+/// is known when it completes; run it after `apply_tile_behaviors`.
+pub fn run_conveyors(
+    time: Res<Time>,
+    grid: Res<MapGrid>,
+    height_map: Res<HeightMap>,
+    special_tiles: Res<SpecialTileMap>,
+    obstacles: GridObstacles,
+    mut player_query: Query<(&Transform, &mut GridMovement, &mut TileMotion), With<Player>>,
+    toggles: Res<SystemToggles>,
+) {
+    if !toggles.player_movement { return; }
+
+    for (transform, mut grid_movement, mut tile_motion) in player_query.iter_mut() {
+        if grid_movement.is_moving {
+            continue;
+        }
+        let tile = grid.world_to_tile(transform.translation.truncate());
+        tile_motion.previous_tile = Some(tile);
+        
+        let Some(TileBehavior::Conveyor { direction, interval }) = special_tiles.get(tile) else {
+            tile_motion.conveyor_timer = 0.0;
+            continue;
+        };
+
+        tile_motion.conveyor_timer += time.delta_secs();
+        if tile_motion.conveyor_timer < *interval {
+            continue;
+        }
+        tile_motion.conveyor_timer = 0.0;
+        
+        // The tile in that direction can depend on the row on staggered and hex maps
+        let next = grid.layout.neighbor_in(tile, *direction);
+        if can_slide(&grid, &height_map, &obstacles, tile, next) {
+            grid_movement.target_position = Some(grid.tile_to_world(next));
+            grid_movement.is_moving = true;
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_ecs_tiled::prelude::*;
+
+use crate::isometric::{MapGrid, TilePos};
+use crate::tiled_map::for_each_map_tile;
+
+/// Base battery drain for a single grid step on plain ground
+pub const BASE_STEP_COST: f32 = 2.0;
+
+/// Kind of ground a tile is made of, read from the Tiled map
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Reflect)]
+pub enum TerrainType {
+    #[default]
+    Plain,
+    Road,
+    Forest,
+    Rock,
+    Mud,
+}
+
+impl TerrainType {
+    /// Parse the value of a tile's `terrain` property (case-insensitive)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "plain" | "ground" => Some(TerrainType::Plain),
+            "road" => Some(TerrainType::Road),
+            "forest" => Some(TerrainType::Forest),
+            "rock" | "rocks" => Some(TerrainType::Rock),
+            "mud" => Some(TerrainType::Mud),
+            _ => None,
+        }
+    }
+
+    /// Guess the terrain from a tileset name when a tile has no `terrain` property
+    pub fn from_tileset_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        if name.contains("rock") || name.contains("mountain") {
+            Some(TerrainType::Rock)
+        } else if name.contains("forest") {
+            Some(TerrainType::Forest)
+        } else if name.contains("colony") || name.contains("road") {
+            Some(TerrainType::Road)
+        } else if name.contains("mud") {
+            Some(TerrainType::Mud)
+        } else {
+            None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TerrainType::Plain => "Plain",
+            TerrainType::Road => "Road",
+            TerrainType::Forest => "Forest",
+            TerrainType::Rock => "Rock",
+            TerrainType::Mud => "Mud",
+        }
+    }
+
+    /// Multiplier applied to `GridMovement::move_speed` while entering a tile of this terrain
+    pub fn speed_multiplier(&self) -> f32 {
+        match self {
+            TerrainType::Plain => 1.0,
+            TerrainType::Road => 1.5,
+            TerrainType::Forest => 0.7,
+            TerrainType::Rock => 0.5,
+            TerrainType::Mud => 0.35,
+        }
+    }
+
+    /// Battery charge spent to step onto a tile of this terrain
+    pub fn step_cost(&self) -> f32 {
+        match self {
+            TerrainType::Plain => BASE_STEP_COST,
+            TerrainType::Road => BASE_STEP_COST * 0.5,
+            TerrainType::Forest => BASE_STEP_COST * 1.5,
+            TerrainType::Rock => BASE_STEP_COST * 2.0,
+            TerrainType::Mud => BASE_STEP_COST * 3.0,
+        }
+    }
+}
+
+/// Terrain of every tile in the loaded map, keyed by Tiled tile coordinates
+#[derive(Resource, Default)]
+pub struct TerrainMap {
+    pub tiles: HashMap<TilePos, TerrainType>,
+}
+
+impl TerrainMap {
+    /// Terrain at a tile, `Plain` for tiles the map doesn't describe
+    pub fn get(&self, tile: TilePos) -> TerrainType {
+        self.tiles.get(&tile).copied().unwrap_or_default()
+    }
+
+    /// Terrain under a world position
+    pub fn at_world(&self, grid: &MapGrid, world_pos: Vec2) -> TerrainType {
+        self.get(grid.world_to_tile(world_pos))
+    }
+
+    /// Build the terrain lookup from a Tiled map. Later layers override earlier ones,
+    /// and a tile's `terrain` property wins over the tileset-name guess.
+    pub fn from_tiled_map(map: &tiled::Map) -> Self {
+        let mut tiles = HashMap::new();
+        for_each_map_tile(map, |_layer, pos, layer_tile| {
+            let from_property = layer_tile.get_tile().and_then(|tile| {
+                match tile.properties.get("terrain") {
+                    Some(tiled::PropertyValue::StringValue(name)) => TerrainType::from_name(name),
+                    _ => None,
+                }
+            });
+            let terrain = from_property
+                .or_else(|| TerrainType::from_tileset_name(&layer_tile.get_tileset().name));
+            if let Some(terrain) = terrain {
+                tiles.insert(pos, terrain);
+            }
+        });
+        Self { tiles }
+    }
+}
+
+/// Rebuild the `TerrainMap` whenever a Tiled map finishes loading or is modified
+pub fn build_terrain_map(
+    mut asset_events: MessageReader<AssetEvent<TiledMapAsset>>,
+    map_assets: Res<Assets<TiledMapAsset>>,
+    mut terrain_map: ResMut<TerrainMap>,
+) {
+    for event in asset_events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event else {
+            continue;
+        };
+        if let Some(map_asset) = map_assets.get(*id) {
+            *terrain_map = TerrainMap::from_tiled_map(&map_asset.map);
+        }
+    }
+}
@@ -1,9 +1,18 @@
 use bevy::prelude::*;
 use bevy_ecs_tiled::prelude::*;
 
-pub fn load_tiled_map(mut commands: Commands, asset_server: Res<AssetServer>) {
+use crate::isometric::{MapGrid, TilePos};
+use crate::levels::LevelRegistry;
+
+pub fn load_tiled_map(mut commands: Commands, asset_server: Res<AssetServer>, registry: Res<LevelRegistry>) {
+    let map_path = registry.current_level().map_path.clone();
+    spawn_tiled_map(&mut commands, asset_server.load(map_path));
+}
+
+/// Spawn a Tiled map entity, centred on the world origin
+pub fn spawn_tiled_map(commands: &mut Commands, map: Handle<TiledMapAsset>) {
     commands.spawn((
-        TiledMap(asset_server.load("map.tmx")),
+        TiledMap(map),
         TilemapAnchor::Center,
         // For isometric maps, it can be useful to tweak `bevy_ecs_tilemap` render settings.
         // [`TilemapRenderSettings`] provides the `y_sort`` parameter to sort chunks using their y-axis
@@ -16,3 +25,138 @@ pub fn load_tiled_map(mut commands: Commands, asset_server: Res<AssetServer>) {
         },
     ));
 }
+
+/// Visit every non-empty tile of every tile layer (including layers nested in groups),
+/// in layer order. Positions are Tiled tile coordinates, which for infinite maps
+/// can be negative.
+pub fn for_each_map_tile<'map>(
+    map: &'map tiled::Map,
+    mut visit: impl FnMut(&tiled::Layer<'map>, TilePos, tiled::LayerTile<'map>),
+) {
+    for layer in map.layers() {
+        visit_layer_tiles(layer, &mut visit);
+    }
+}
+
+fn visit_layer_tiles<'map>(
+    layer: tiled::Layer<'map>,
+    visit: &mut impl FnMut(&tiled::Layer<'map>, TilePos, tiled::LayerTile<'map>),
+) {
+    match layer.layer_type() {
+        tiled::LayerType::Tiles(tiled::TileLayer::Finite(finite)) => {
+            for y in 0..finite.height() as i32 {
+                for x in 0..finite.width() as i32 {
+                    if let Some(tile) = finite.get_tile(x, y) {
+                        visit(&layer, TilePos::new(x, y), tile);
+                    }
+                }
+            }
+        }
+        tiled::LayerType::Tiles(tiled::TileLayer::Infinite(infinite)) => {
+            let chunk_size = IVec2::new(
+                tiled::ChunkData::WIDTH as i32,
+                tiled::ChunkData::HEIGHT as i32,
+            );
+            for ((chunk_x, chunk_y), chunk) in infinite.chunks() {
+                let chunk_origin = IVec2::new(chunk_x, chunk_y) * chunk_size;
+                for y in 0..chunk_size.y {
+                    for x in 0..chunk_size.x {
+                        if let Some(tile) = chunk.get_tile(x, y) {
+                            visit(&layer, TilePos::from(chunk_origin + IVec2::new(x, y)), tile);
+                        }
+                    }
+                }
+            }
+        }
+        tiled::LayerType::Group(group) => {
+            for child in group.layers() {
+                visit_layer_tiles(child, visit);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Visit every object of every object layer (including layers nested in groups)
+pub fn for_each_map_object<'map>(map: &'map tiled::Map, mut visit: impl FnMut(tiled::Object<'map>)) {
+    for layer in map.layers() {
+        visit_layer_objects(layer, &mut visit);
+    }
+}
+
+fn visit_layer_objects<'map>(layer: tiled::Layer<'map>, visit: &mut impl FnMut(tiled::Object<'map>)) {
+    match layer.layer_type() {
+        tiled::LayerType::Objects(object_layer) => {
+            for object in object_layer.objects() {
+                visit(object);
+            }
+        }
+        tiled::LayerType::Group(group) => {
+            for child in group.layers() {
+                visit_layer_objects(child, visit);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Tile under an object's position. On isometric maps Tiled measures object
+/// positions along both tile axes in units of the tile height; on the other
+/// layouts they are pixels from the map's top left corner.
+pub fn object_tile(map: &tiled::Map, x: f32, y: f32) -> TilePos {
+    let tile_width = map.tile_width as f32;
+    let tile_height = map.tile_height as f32;
+    if matches!(map.orientation, tiled::Orientation::Isometric) {
+        return TilePos::new((x / tile_height).floor() as i32, (y / tile_height).floor() as i32);
+    }
+    // Tile (0, 0) fills the top left corner, and map pixels count downwards
+    let mut grid = MapGrid::for_map(map);
+    grid.origin = Vec2::new(tile_width / 2.0, -tile_height / 2.0);
+    grid.world_to_tile(Vec2::new(x, -y))
+}
+
+/// World position of a point given in Tiled object coordinates, on a grid lined
+/// up with the map. Unlike `object_tile` this keeps where in the tile it lies.
+pub fn object_world_position(map: &tiled::Map, grid: &MapGrid, x: f32, y: f32) -> Vec2 {
+    let first_centre = grid.tile_to_world(TilePos::ZERO);
+    let tile_width = map.tile_width as f32;
+    let tile_height = map.tile_height as f32;
+    if matches!(map.orientation, tiled::Orientation::Isometric) {
+        // Fractional tile coordinates, measured from the centre of tile (0, 0)
+        let along = Vec2::new(x, y) / tile_height - Vec2::splat(0.5);
+        return first_centre + along.x * grid.step_offset(IVec2::X) + along.y * grid.step_offset(IVec2::Y);
+    }
+    // Map pixels count downwards from the top left corner of tile (0, 0)
+    first_centre + Vec2::new(-tile_width / 2.0, tile_height / 2.0) + Vec2::new(x, -y)
+}
+
+/// Smallest and largest tile coordinates covered by the map's tile layers. Finite
+/// maps cover their whole declared size; infinite maps cover every chunk that
+/// exists in any layer, which is what bevy_ecs_tiled sizes (and anchors) the map by.
+pub fn map_tile_bounds(map: &tiled::Map) -> IRect {
+    let mut bounds: Option<IRect> = None;
+    let mut cover = |min: IVec2, max: IVec2| {
+        let rect = IRect::from_corners(min, max);
+        bounds = Some(bounds.map_or(rect, |bounds| bounds.union(rect)));
+    };
+
+    let chunk_size = IVec2::new(tiled::ChunkData::WIDTH as i32, tiled::ChunkData::HEIGHT as i32);
+    let mut layers: Vec<tiled::Layer> = map.layers().collect();
+    while let Some(layer) = layers.pop() {
+        match layer.layer_type() {
+            tiled::LayerType::Tiles(tiled::TileLayer::Finite(finite)) => {
+                cover(IVec2::ZERO, IVec2::new(finite.width() as i32 - 1, finite.height() as i32 - 1));
+            }
+            tiled::LayerType::Tiles(tiled::TileLayer::Infinite(infinite)) => {
+                for ((chunk_x, chunk_y), _) in infinite.chunks() {
+                    let chunk_origin = IVec2::new(chunk_x, chunk_y) * chunk_size;
+                    cover(chunk_origin, chunk_origin + chunk_size - IVec2::ONE);
+                }
+            }
+            tiled::LayerType::Group(group) => layers.extend(group.layers()),
+            _ => {}
+        }
+    }
+
+    bounds.unwrap_or_else(|| IRect::new(0, 0, map.width as i32 - 1, map.height as i32 - 1))
+}
@@ -0,0 +1,172 @@
+use bevy::log::warn_once;
+use bevy::prelude::*;
+
+use crate::components::SystemToggles;
+
+// Toggles are registered by name, each with a label for the system controls
+// panel. The panel builds a button for every registered toggle, and systems can
+// be switched off with the `toggle_enabled` run condition.
+
+/// Where a toggle keeps its value
+#[derive(Clone, Copy, Debug)]
+pub enum ToggleValue {
+    /// Kept by the registry itself
+    Stored(bool),
+    /// A field of `SystemToggles`, for systems that check their toggle themselves
+    Field {
+        get: fn(&SystemToggles) -> bool,
+        set: fn(&mut SystemToggles, bool),
+    },
+}
+
+/// A named switch shown in the system controls panel
+#[derive(Clone, Debug)]
+pub struct RegisteredToggle {
+    pub name: String,
+    pub label: String,
+    pub value: ToggleValue,
+}
+
+impl RegisteredToggle {
+    pub fn is_enabled(&self, toggles: &SystemToggles) -> bool {
+        match self.value {
+            ToggleValue::Stored(enabled) => enabled,
+            ToggleValue::Field { get, .. } => get(toggles),
+        }
+    }
+
+    pub fn set_enabled(&mut self, toggles: &mut SystemToggles, enabled: bool) {
+        match &mut self.value {
+            ToggleValue::Stored(value) => *value = enabled,
+            ToggleValue::Field { set, .. } => set(toggles, enabled),
+        }
+    }
+}
+
+/// Every toggle, in the order the panel lists them
+#[derive(Resource, Default, Debug)]
+pub struct ToggleRegistry {
+    toggles: Vec<RegisteredToggle>,
+}
+
+impl ToggleRegistry {
+    /// Add a toggle, replacing any registered under the same name
+    pub fn register(&mut self, toggle: RegisteredToggle) {
+        match self.toggles.iter_mut().find(|existing| existing.name == toggle.name) {
+            Some(existing) => *existing = toggle,
+            None => self.toggles.push(toggle),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&RegisteredToggle> {
+        self.toggles.iter().find(|toggle| toggle.name == name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut RegisteredToggle> {
+        self.toggles.iter_mut().find(|toggle| toggle.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &RegisteredToggle> {
+        self.toggles.iter()
+    }
+
+    /// Whether a toggle is on; `None` if nothing is registered under `name`
+    pub fn is_enabled(&self, name: &str, toggles: &SystemToggles) -> Option<bool> {
+        self.get(name).map(|toggle| toggle.is_enabled(toggles))
+    }
+
+    /// Switch a toggle on or off. Returns `false` if nothing is registered under `name`.
+    pub fn set_enabled(&mut self, name: &str, toggles: &mut SystemToggles, enabled: bool) -> bool {
+        let Some(toggle) = self.get_mut(name) else { return false; };
+        toggle.set_enabled(toggles, enabled);
+        true
+    }
+}
+
+/// Registering toggles while building the app
+pub trait RegisterToggle {
+    /// A toggle the registry keeps the value of, for use with `toggle_enabled`
+    fn register_toggle(&mut self, name: &str, label: &str, enabled: bool) -> &mut Self;
+
+    /// A toggle backed by a field of `SystemToggles`
+    fn register_field_toggle(
+        &mut self,
+        name: &str,
+        label: &str,
+        get: fn(&SystemToggles) -> bool,
+        set: fn(&mut SystemToggles, bool),
+    ) -> &mut Self;
+}
+
+fn register(app: &mut App, name: &str, label: &str, value: ToggleValue) {
+    app.init_resource::<ToggleRegistry>();
+    app.world_mut().resource_mut::<ToggleRegistry>().register(RegisteredToggle {
+        name: name.to_string(),
+        label: label.to_string(),
+        value,
+    });
+}
+
+impl RegisterToggle for App {
+    fn register_toggle(&mut self, name: &str, label: &str, enabled: bool) -> &mut Self {
+        register(self, name, label, ToggleValue::Stored(enabled));
+        self
+    }
+
+    fn register_field_toggle(
+        &mut self,
+        name: &str,
+        label: &str,
+        get: fn(&SystemToggles) -> bool,
+        set: fn(&mut SystemToggles, bool),
+    ) -> &mut Self {
+        register(self, name, label, ToggleValue::Field { get, set });
+        self
+    }
+}
+
+/// Run condition: the named toggle is on. A name that was never registered
+/// keeps the system from running, with a warning, rather than passing silently.
+pub fn toggle_enabled(name: &'static str) -> impl FnMut(Res<ToggleRegistry>, Res<SystemToggles>) -> bool + Clone {
+    move |registry: Res<ToggleRegistry>, toggles: Res<SystemToggles>| match registry.is_enabled(name, &toggles) {
+        Some(enabled) => enabled,
+        None => {
+            warn_once!("No toggle is registered as \"{name}\"");
+            false
+        }
+    }
+}
+
+/// Plugin registering the toggles for the built-in `SystemToggles` fields
+pub fn system_toggles_plugin(app: &mut App) {
+    app.register_field_toggle(
+        "player_movement",
+        "Player Movement",
+        |toggles| toggles.player_movement,
+        |toggles, enabled| toggles.player_movement = enabled,
+    )
+    .register_field_toggle(
+        "cord_systems",
+        "Cord Systems",
+        |toggles| toggles.cord_systems,
+        |toggles, enabled| toggles.cord_systems = enabled,
+    )
+    .register_field_toggle(
+        "camera_follow",
+        "Camera Follow",
+        |toggles| toggles.camera_follow,
+        |toggles, enabled| toggles.camera_follow = enabled,
+    )
+    .register_field_toggle(
+        "camera_zoom",
+        "Camera Zoom",
+        |toggles| toggles.camera_zoom,
+        |toggles, enabled| toggles.camera_zoom = enabled,
+    )
+    .register_field_toggle(
+        "player_rotation",
+        "Player Rotation",
+        |toggles| toggles.player_rotation,
+        |toggles, enabled| toggles.player_rotation = enabled,
+    );
+}
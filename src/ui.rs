@@ -1,25 +1,133 @@
 use bevy::prelude::*;
 use crate::components::*;
+use crate::cord_system::{cord_tiles_used, CordReel};
+use crate::day_night_cycle::DayNightCycle;
+use crate::isometric::MapGrid;
+use crate::map_objects::MapObjectId;
+use crate::picking::HoveredTile;
+use crate::power_system::{battery_time_left, BatteryFlow};
+use crate::reset::ResetButton;
+use crate::terrain::TerrainMap;
+use crate::toggles::ToggleRegistry;
+
+/// Width of the battery bar, in pixels
+const BATTERY_BAR_WIDTH: f32 = 240.0;
+const SUN_COLOR: Color = Color::srgb(1.0, 0.85, 0.2);
+const MOON_COLOR: Color = Color::srgb(0.75, 0.8, 0.95);
 
 #[derive(Component)]
 pub struct BatteryDisplay;
 
-pub fn setup_ui(mut commands: Commands) {
+/// The filled part of the battery bar
+#[derive(Component)]
+pub struct BatteryBar;
+
+#[derive(Component)]
+pub struct ClockDisplay;
+
+/// Round icon next to the clock: the sun by day, the moon by night
+#[derive(Component)]
+pub struct SunMoonIcon;
+
+#[derive(Component)]
+pub struct ChargeRateDisplay;
+
+#[derive(Component)]
+pub struct CordDisplay;
+
+#[derive(Component)]
+pub struct TerrainDisplay;
+
+/// Battery text colour for a charge level in percent
+fn battery_color(percentage: f32) -> Color {
+    if percentage > 50.0 {
+        Color::srgb(0.2, 1.0, 0.2) // Green
+    } else if percentage > 20.0 {
+        Color::srgb(1.0, 1.0, 0.2) // Yellow
+    } else {
+        Color::srgb(1.0, 0.2, 0.2) // Red
+    }
+}
+
+/// A duration as minutes and seconds (`m:ss`)
+pub fn format_duration(secs: f32) -> String {
+    let secs = secs.max(0.0).round() as u32;
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+/// Battery readout, with the time until it's full or empty at the current rate
+pub fn battery_text(battery: &Battery, net_rate: f32) -> String {
+    let percentage = (battery.current_charge / battery.max_charge * 100.0).round();
+    match battery_time_left(battery, net_rate) {
+        Some((secs, true)) => format!("Battery: {:.0}% (full in {})", percentage, format_duration(secs)),
+        Some((secs, false)) => format!("Battery: {:.0}% (empty in {})", percentage, format_duration(secs)),
+        None => format!("Battery: {:.0}%", percentage),
+    }
+}
+
+/// Net charge rate with what goes into it
+pub fn charge_rate_text(flow: &BatteryFlow) -> String {
+    format!(
+        "Charge: {:+.1}/s (solar {:+.1}, pole {:+.1}, drain {:+.1})",
+        flow.net_rate(),
+        flow.solar_rate,
+        flow.pole_rate,
+        -flow.drain_rate,
+    )
+}
+
+/// Cord readout: how much of the reel is laid out, and the pole it runs to
+/// along with how far away that is in tiles
+pub fn cord_text(used: usize, max: usize, pole: Option<(&str, f32)>) -> String {
+    match pole {
+        Some((name, distance)) => format!("Cord: {}/{} tiles to {} ({:.1} tiles away)", used, max, name, distance),
+        None => format!("Cord: 0/{} tiles, not plugged in", max),
+    }
+}
+
+pub fn setup_ui(mut commands: Commands, registry: Res<ToggleRegistry>, toggles: Res<SystemToggles>) {
     // Battery display (top-left corner)
     commands.spawn((
         Node {
             position_type: PositionType::Absolute,
             top: Val::Px(10.0),
             left: Val::Px(10.0),
-            width: Val::Px(150.0),
-            height: Val::Px(40.0),
+            width: Val::Px(BATTERY_BAR_WIDTH + 20.0),
             padding: UiRect::all(Val::Px(10.0)),
+            row_gap: Val::Px(4.0),
             flex_direction: FlexDirection::Column,
             ..default()
         },
         BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
     ))
     .with_children(|parent| {
+        // Clock with the sun or moon in front of it
+        parent.spawn(Node {
+            column_gap: Val::Px(6.0),
+            align_items: AlignItems::Center,
+            ..default()
+        })
+        .with_children(|row| {
+            row.spawn((
+                Node {
+                    width: Val::Px(14.0),
+                    height: Val::Px(14.0),
+                    ..default()
+                },
+                BorderRadius::MAX,
+                BackgroundColor(SUN_COLOR),
+                SunMoonIcon,
+            ));
+            row.spawn((
+                Text::new("12:00"),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                ClockDisplay,
+            ));
+        });
         parent.spawn((
             Text::new("Battery: 100%"),
             TextFont {
@@ -29,6 +137,52 @@ pub fn setup_ui(mut commands: Commands) {
             TextColor(Color::srgb(0.2, 1.0, 0.2)),
             BatteryDisplay,
         ));
+        parent.spawn((
+            Node {
+                width: Val::Px(BATTERY_BAR_WIDTH),
+                height: Val::Px(8.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.15, 0.15, 0.15)),
+        ))
+        .with_children(|bar| {
+            bar.spawn((
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(0.2, 1.0, 0.2)),
+                BatteryBar,
+            ));
+        });
+        parent.spawn((
+            Text::new("Charge: +0.0/s"),
+            TextFont {
+                font_size: 14.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+            ChargeRateDisplay,
+        ));
+        parent.spawn((
+            Text::new("Cord: -"),
+            TextFont {
+                font_size: 14.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+            CordDisplay,
+        ));
+        parent.spawn((
+            Text::new("Terrain: -"),
+            TextFont {
+                font_size: 14.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+            TerrainDisplay,
+        ));
     });
 
     // Create UI root node
@@ -38,7 +192,6 @@ pub fn setup_ui(mut commands: Commands) {
             top: Val::Px(10.0),
             right: Val::Px(10.0),
             width: Val::Px(200.0),
-            height: Val::Px(300.0),
             padding: UiRect::all(Val::Px(10.0)),
             flex_direction: FlexDirection::Column,
             ..default()
@@ -56,16 +209,9 @@ pub fn setup_ui(mut commands: Commands) {
             TextColor(Color::WHITE),
         ));
         
-        // Toggle buttons
-        let buttons = [
-            ("Player Movement", "player_movement"),
-            ("Cord Systems", "cord_systems"),
-            ("Camera Follow", "camera_follow"),
-            ("Camera Zoom", "camera_zoom"),
-            ("Player Rotation", "player_rotation"),
-        ];
-        
-        for (label, system_name) in buttons {
+        // A toggle button for every registered toggle, showing its current state
+        for toggle in registry.iter() {
+            let (background, state) = toggle_look(toggle.is_enabled(&toggles));
             parent.spawn((
                 Node {
                     width: Val::Percent(100.0),
@@ -79,7 +225,7 @@ pub fn setup_ui(mut commands: Commands) {
             .with_children(|row| {
                 // Label
                 row.spawn((
-                    Text::new(label),
+                    Text::new(toggle.label.clone()),
                     TextFont {
                         font_size: 14.0,
                         ..default()
@@ -97,12 +243,12 @@ pub fn setup_ui(mut commands: Commands) {
                         align_items: AlignItems::Center,
                         ..default()
                     },
-                    BackgroundColor(Color::srgb(0.2, 0.6, 0.2)), // Green for enabled
-                    ToggleButton(system_name.to_string()),
+                    BackgroundColor(background),
+                    ToggleButton(toggle.name.clone()),
                 ))
                 .with_children(|button| {
                     button.spawn((
-                        Text::new("ON"),
+                        Text::new(state),
                         TextFont {
                             font_size: 12.0,
                             ..default()
@@ -112,73 +258,177 @@ pub fn setup_ui(mut commands: Commands) {
                 });
             });
         }
+
+        // Starts the level over, same as the R key
+        parent.spawn((
+            Button,
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Px(30.0),
+                margin: UiRect::top(Val::Px(10.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.35, 0.35, 0.45)),
+            ResetButton,
+        ))
+        .with_children(|button| {
+            button.spawn((
+                Text::new("Reset"),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+        });
     });
 }
 
 pub fn update_battery_display(
     player_query: Query<&Battery, With<Player>>,
+    flow: Option<Res<BatteryFlow>>,
     mut text_query: Query<(&mut Text, &mut TextColor), With<BatteryDisplay>>,
+    mut bar_query: Query<(&mut Node, &mut BackgroundColor), With<BatteryBar>>,
+) {
+    let Ok(battery) = player_query.single() else { return; };
+    let percentage = (battery.current_charge / battery.max_charge * 100.0).round();
+    let net_rate = flow.map_or(0.0, |flow| flow.net_rate());
+
+    if let Ok((mut text, mut color)) = text_query.single_mut() {
+        **text = battery_text(battery, net_rate);
+        *color = TextColor(battery_color(percentage));
+    }
+    if let Ok((mut node, mut color)) = bar_query.single_mut() {
+        node.width = Val::Percent((battery.current_charge / battery.max_charge * 100.0).clamp(0.0, 100.0));
+        *color = BackgroundColor(battery_color(percentage));
+    }
+}
+
+pub fn update_clock_display(
+    cycle: Res<DayNightCycle>,
+    mut text_query: Query<&mut Text, With<ClockDisplay>>,
+    mut icon_query: Query<&mut BackgroundColor, With<SunMoonIcon>>,
+) {
+    if let Ok(mut text) = text_query.single_mut() {
+        let (hours, minutes) = cycle.clock();
+        **text = format!("{:02}:{:02}", hours, minutes);
+    }
+    if let Ok(mut icon) = icon_query.single_mut() {
+        *icon = BackgroundColor(if cycle.is_day() { SUN_COLOR } else { MOON_COLOR });
+    }
+}
+
+pub fn update_charge_rate_display(
+    flow: Res<BatteryFlow>,
+    mut text_query: Query<&mut Text, With<ChargeRateDisplay>>,
+) {
+    if let Ok(mut text) = text_query.single_mut() {
+        **text = charge_rate_text(&flow);
+    }
+}
+
+pub fn update_cord_display(
+    cord_system: Option<Res<CordSystem>>,
+    reel: Res<CordReel>,
+    grid: Res<MapGrid>,
+    player_query: Query<&Transform, With<Player>>,
+    pole_query: Query<(&Transform, Option<&Name>, Option<&MapObjectId>), With<Pole>>,
+    mut text_query: Query<&mut Text, With<CordDisplay>>,
 ) {
-    if let Ok(battery) = player_query.single() {
-        if let Ok((mut text, mut color)) = text_query.single_mut() {
-            let percentage = (battery.current_charge / battery.max_charge * 100.0).round();
-            **text = format!("Battery: {:.0}%", percentage);
-            
-            // Change color based on charge level
-            if percentage > 50.0 {
-                *color = TextColor(Color::srgb(0.2, 1.0, 0.2)); // Green
-            } else if percentage > 20.0 {
-                *color = TextColor(Color::srgb(1.0, 1.0, 0.2)); // Yellow
-            } else {
-                *color = TextColor(Color::srgb(1.0, 0.2, 0.2)); // Red
-            }
+    let Ok(mut text) = text_query.single_mut() else { return; };
+    let Some(cord_system) = cord_system else { return; };
+
+    let attached = cord_system.attached_pole.and_then(|pole| pole_query.get(pole).ok());
+    let pole = attached.zip(player_query.single().ok()).map(|((pole_transform, name, object_id), player_transform)| {
+        // Poles are called by their Tiled name, or their object id if they have none
+        let name = match (name, object_id) {
+            (Some(name), _) if !name.as_str().is_empty() => name.as_str().to_string(),
+            (_, Some(object_id)) => format!("pole #{}", object_id.0),
+            _ => "a pole".to_string(),
+        };
+        let tiles_between = grid.world_to_tile_coords(player_transform.translation.truncate())
+            - grid.world_to_tile_coords(pole_transform.translation.truncate());
+        (name, tiles_between.length())
+    });
+    **text = cord_text(
+        cord_tiles_used(&cord_system),
+        reel.max_tiles,
+        pole.as_ref().map(|(name, distance)| (name.as_str(), *distance)),
+    );
+}
+
+pub fn update_terrain_display(
+    hovered: Res<HoveredTile>,
+    terrain: Res<TerrainMap>,
+    mut text_query: Query<&mut Text, With<TerrainDisplay>>,
+) {
+    let Ok(mut text) = text_query.single_mut() else { return; };
+    
+    **text = match hovered.tile {
+        Some(tile) => {
+            let terrain_type = terrain.get(tile);
+            format!(
+                "Terrain: {} (cost {:.1}, speed {:.0}%)",
+                terrain_type.name(),
+                terrain_type.step_cost(),
+                terrain_type.speed_multiplier() * 100.0,
+            )
         }
+        None => "Terrain: -".to_string(),
+    };
+}
+
+/// Button colour and label for a toggle's state
+fn toggle_look(enabled: bool) -> (Color, &'static str) {
+    if enabled {
+        (Color::srgb(0.2, 0.6, 0.2), "ON") // Green for enabled
+    } else {
+        (Color::srgb(0.6, 0.2, 0.2), "OFF") // Red for disabled
+    }
+}
+
+/// Colour a toggle button and label it ON or OFF
+fn show_toggle_state(enabled: bool, color: &mut BackgroundColor, children: &Children, text_query: &mut Query<&mut Text>) {
+    let (background, label) = toggle_look(enabled);
+    *color = BackgroundColor(background);
+    if let Ok(mut text) = text_query.get_mut(children[0]) {
+        **text = label.to_string();
     }
 }
 
 pub fn update_ui(
-    mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor, &ToggleButton, &Children),
-        (Changed<Interaction>, With<Button>)
-    >,
-    mut text_query: Query<&mut Text>,
+    interaction_query: Query<(&Interaction, &ToggleButton), (Changed<Interaction>, With<Button>)>,
+    mut registry: ResMut<ToggleRegistry>,
     mut toggles: ResMut<SystemToggles>,
 ) {
-    for (interaction, mut color, toggle_button, children) in &mut interaction_query {
+    for (interaction, toggle_button) in &interaction_query {
         if *interaction == Interaction::Pressed {
-            // Get current state
-            let current_state = match toggle_button.0.as_str() {
-                "player_movement" => toggles.player_movement,
-                "cord_systems" => toggles.cord_systems,
-                "camera_follow" => toggles.camera_follow,
-                "camera_zoom" => toggles.camera_zoom,
-                "player_rotation" => toggles.player_rotation,
-                _ => true,
+            // Flip the toggle; `sync_toggle_buttons` updates the button's appearance
+            let Some(enabled) = registry.is_enabled(&toggle_button.0, &toggles) else {
+                warn!("Toggle button for unregistered toggle \"{}\"", toggle_button.0);
+                continue;
             };
-            
-            // Toggle the state
-            let new_state = !current_state;
-            match toggle_button.0.as_str() {
-                "player_movement" => toggles.player_movement = new_state,
-                "cord_systems" => toggles.cord_systems = new_state,
-                "camera_follow" => toggles.camera_follow = new_state,
-                "camera_zoom" => toggles.camera_zoom = new_state,
-                "player_rotation" => toggles.player_rotation = new_state,
-                _ => {},
-            }
-            
-            // Update button appearance
-            if new_state {
-                *color = BackgroundColor(Color::srgb(0.2, 0.6, 0.2)); // Green for enabled
-                if let Ok(mut text) = text_query.get_mut(children[0]) {
-                    **text = "ON".to_string();
-                }
-            } else {
-                *color = BackgroundColor(Color::srgb(0.6, 0.2, 0.2)); // Red for disabled
-                if let Ok(mut text) = text_query.get_mut(children[0]) {
-                    **text = "OFF".to_string();
-                }
-            }
+            registry.set_enabled(&toggle_button.0, &mut toggles, !enabled);
+        }
+    }
+}
+
+/// Keep the buttons showing the real state of their toggles, including toggles
+/// changed from elsewhere, such as a click on the minimap setting the camera free
+pub fn sync_toggle_buttons(
+    registry: Res<ToggleRegistry>,
+    toggles: Res<SystemToggles>,
+    mut button_query: Query<(&ToggleButton, &mut BackgroundColor, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    if !registry.is_changed() && !toggles.is_changed() {
+        return;
+    }
+    for (toggle_button, mut color, children) in button_query.iter_mut() {
+        if let Some(enabled) = registry.is_enabled(&toggle_button.0, &toggles) {
+            show_toggle_state(enabled, &mut color, children, &mut text_query);
         }
     }
 }
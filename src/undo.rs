@@ -0,0 +1,216 @@
+use bevy::prelude::*;
+
+use crate::components::{Battery, CordSystem, GridMovement, Player, SystemToggles};
+use crate::grid_objects::PushableCrate;
+use crate::isometric::{MapGrid, TilePos};
+use crate::player_movement::GridStepCompleted;
+
+/// State of the robot and its cord after a committed grid step
+#[derive(Clone, Debug, PartialEq)]
+pub struct MoveSnapshot {
+    pub tile: TilePos,
+    pub trail_path: Vec<Vec2>,
+    pub attached_pole: Option<Entity>,
+    pub battery_charge: f32,
+    /// Tile of every crate, so undoing a push slides the crate back too
+    pub crates: Vec<(Entity, TilePos)>,
+}
+
+/// History of committed grid steps that the player can walk back and forth through
+#[derive(Resource)]
+pub struct UndoHistory {
+    pub snapshots: Vec<MoveSnapshot>,
+    /// Index of the snapshot matching the current state
+    pub cursor: usize,
+    /// How many undos the player may use in total (`None` for unlimited)
+    pub max_undos: Option<usize>,
+    pub undos_used: usize,
+    /// Oldest snapshots are dropped once the history grows past this
+    pub max_history: usize,
+    /// Snapshot to apply once the robot has animated back onto its tile
+    pub pending_restore: Option<usize>,
+}
+
+impl Default for UndoHistory {
+    fn default() -> Self {
+        Self {
+            snapshots: Vec::new(),
+            cursor: 0,
+            max_undos: None,
+            undos_used: 0,
+            max_history: 256,
+            pending_restore: None,
+        }
+    }
+}
+
+impl UndoHistory {
+    pub fn can_undo(&self) -> bool {
+        let within_limit = self.max_undos.is_none_or(|max| self.undos_used < max);
+        self.cursor > 0 && within_limit
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.cursor + 1 < self.snapshots.len()
+    }
+
+    /// Record a new committed step. Anything that was undone is discarded.
+    pub fn record(&mut self, snapshot: MoveSnapshot) {
+        if !self.snapshots.is_empty() {
+            self.snapshots.truncate(self.cursor + 1);
+        }
+        self.snapshots.push(snapshot);
+
+        if self.snapshots.len() > self.max_history {
+            let excess = self.snapshots.len() - self.max_history;
+            self.snapshots.drain(..excess);
+        }
+        self.cursor = self.snapshots.len() - 1;
+    }
+
+    /// Step the cursor back, returning the snapshot to return to
+    pub fn undo(&mut self) -> Option<&MoveSnapshot> {
+        if !self.can_undo() {
+            return None;
+        }
+        self.cursor -= 1;
+        self.undos_used += 1;
+        self.snapshots.get(self.cursor)
+    }
+
+    /// Step the cursor forward again, returning the snapshot to return to
+    pub fn redo(&mut self) -> Option<&MoveSnapshot> {
+        if !self.can_redo() {
+            return None;
+        }
+        self.cursor += 1;
+        self.snapshots.get(self.cursor)
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+        self.cursor = 0;
+        self.undos_used = 0;
+        self.pending_restore = None;
+    }
+}
+
+fn take_snapshot(
+    tile: TilePos,
+    cord_system: &CordSystem,
+    battery: &Battery,
+    crates: Vec<(Entity, TilePos)>,
+) -> MoveSnapshot {
+    MoveSnapshot {
+        tile,
+        trail_path: cord_system.trail_path.clone(),
+        attached_pole: cord_system.attached_pole,
+        battery_charge: battery.current_charge,
+        crates,
+    }
+}
+
+/// Tile a crate is on, or heading to while it's being pushed
+fn crate_tile(grid: &MapGrid, transform: &Transform, grid_movement: &GridMovement) -> TilePos {
+    grid.world_to_tile(grid_movement.target_position.unwrap_or(transform.translation.truncate()))
+}
+
+/// Record a snapshot for every committed player step, and the starting state
+/// before the first one. Steps that finish an undo/redo apply the pending
+/// snapshot instead of recording a new one.
+pub fn record_move_snapshots(
+    mut step_events: MessageReader<GridStepCompleted>,
+    mut history: ResMut<UndoHistory>,
+    grid: Res<MapGrid>,
+    cord_system: Option<ResMut<CordSystem>>,
+    mut player_query: Query<(Entity, &Transform, &mut Battery), With<Player>>,
+    mut crate_query: Query<(Entity, &mut Transform, &mut GridMovement), (With<PushableCrate>, Without<Player>)>,
+) {
+    let Some(mut cord_system) = cord_system else { return; };
+    let Ok((player_entity, transform, mut battery)) = player_query.single_mut() else { return; };
+    let tile = grid.world_to_tile(transform.translation.truncate());
+    let crates: Vec<(Entity, TilePos)> = crate_query
+        .iter()
+        .map(|(entity, transform, grid_movement)| (entity, crate_tile(&grid, transform, grid_movement)))
+        .collect();
+
+    if history.snapshots.is_empty() {
+        history.record(take_snapshot(tile, &cord_system, &battery, crates.clone()));
+    }
+
+    for event in step_events.read() {
+        if event.entity != player_entity {
+            continue;
+        }
+
+        match history.pending_restore.take() {
+            Some(index) => {
+                if let Some(snapshot) = history.snapshots.get(index) {
+                    cord_system.trail_path = snapshot.trail_path.clone();
+                    cord_system.attached_pole = snapshot.attached_pole;
+                    battery.current_charge = snapshot.battery_charge.min(battery.max_charge);
+                    for (entity, saved_tile) in snapshot.crates.iter() {
+                        let Ok((_, mut transform, mut grid_movement)) = crate_query.get_mut(*entity) else {
+                            continue;
+                        };
+                        let position = grid.tile_to_world(*saved_tile);
+                        transform.translation.x = position.x;
+                        transform.translation.y = position.y;
+                        grid_movement.target_position = None;
+                        grid_movement.is_moving = false;
+                    }
+                }
+            }
+            None => history.record(take_snapshot(tile, &cord_system, &battery, crates.clone())),
+        }
+    }
+}
+
+/// Z undoes the last step and Y redoes it. The robot walks back onto the
+/// snapshot's tile through `GridMovement`, and crates it pushed slide back
+/// alongside it; cord and battery are restored once it arrives. A tile that
+/// isn't next to the robot, such as the far side of a teleport, can't be walked
+/// to without going through whatever lies between, so the robot is put straight
+/// onto it instead.
+pub fn handle_undo_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut history: ResMut<UndoHistory>,
+    grid: Res<MapGrid>,
+    mut player_query: Query<(&mut Transform, &mut GridMovement), With<Player>>,
+    mut crate_query: Query<(&Transform, &mut GridMovement), (With<PushableCrate>, Without<Player>)>,
+    toggles: Res<SystemToggles>,
+) {
+    if !toggles.player_movement { return; }
+    let Ok((mut transform, mut grid_movement)) = player_query.single_mut() else { return; };
+    if grid_movement.is_moving || history.pending_restore.is_some() {
+        return;
+    }
+
+    let snapshot = if keys.just_pressed(KeyCode::KeyZ) {
+        history.undo().cloned()
+    } else if keys.just_pressed(KeyCode::KeyY) {
+        history.redo().cloned()
+    } else {
+        None
+    };
+
+    if let Some(snapshot) = snapshot {
+        let target = grid.tile_to_world(snapshot.tile);
+        let tile = grid.world_to_tile(transform.translation.truncate());
+        if !grid.neighbors(tile).contains(&snapshot.tile) {
+            transform.translation.x = target.x;
+            transform.translation.y = target.y;
+        }
+        // Grid movement completes the step, so the snapshot is applied either way
+        grid_movement.target_position = Some(target);
+        grid_movement.is_moving = true;
+        for (entity, tile) in snapshot.crates.iter() {
+            let Ok((transform, mut crate_movement)) = crate_query.get_mut(*entity) else { continue; };
+            if crate_tile(&grid, transform, &crate_movement) != *tile {
+                crate_movement.target_position = Some(grid.tile_to_world(*tile));
+                crate_movement.is_moving = true;
+            }
+        }
+        history.pending_restore = Some(history.cursor);
+    }
+}
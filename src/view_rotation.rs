@@ -0,0 +1,196 @@
+use bevy::prelude::*;
+use bevy_ecs_tiled::prelude::*;
+
+use crate::camera::{CameraBounds, CameraFollow};
+use crate::camera_effects::{CameraEffects, CameraRails, ShotPath};
+use crate::components::{CordSystem, GridMovement, Player};
+use crate::elevation::HeightMap;
+use crate::fog_of_war::FogTile;
+use crate::free_camera::FreeCamera;
+use crate::grid_objects::GridPlaced;
+use crate::isometric::MapGrid;
+use crate::minimap::{Minimap, MinimapNode, MinimapView, MINIMAP_MAX_SIZE};
+use crate::tiled_map::map_tile_bounds;
+use crate::undo::UndoHistory;
+
+// Turning the view doesn't change any tile coordinates: the grid draws every tile
+// somewhere else, and everything that remembers a world position is moved to
+// where its tile coordinates now are. A freshly loaded map starts unturned.
+
+/// Turn the view a quarter anticlockwise
+pub const ROTATE_LEFT_KEY: KeyCode = KeyCode::KeyQ;
+/// Turn the view a quarter clockwise
+pub const ROTATE_RIGHT_KEY: KeyCode = KeyCode::KeyE;
+
+/// Sent when the view has turned, with the grid from before the turn
+#[derive(Message, Clone, Debug)]
+pub struct ViewRotated {
+    pub previous: MapGrid,
+}
+
+/// Where a world position on the `previous` grid is on `grid`
+pub fn reproject(previous: &MapGrid, grid: &MapGrid, point: Vec2) -> Vec2 {
+    grid.tile_coords_to_world(previous.world_to_tile_coords(point))
+}
+
+/// Turn the view with Q and E. Only isometric maps can be turned.
+pub fn rotate_view(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut grid: ResMut<MapGrid>,
+    mut rotated_events: MessageWriter<ViewRotated>,
+) {
+    let turns = keys.just_pressed(ROTATE_RIGHT_KEY) as i32 - keys.just_pressed(ROTATE_LEFT_KEY) as i32;
+    if turns == 0 || !grid.can_rotate() {
+        return;
+    }
+    let previous = grid.clone();
+    *grid = previous.rotated(turns);
+    rotated_events.write(ViewRotated { previous });
+}
+
+/// Move the robot, the objects on the grid, the fog and the cord (along with
+/// every undo step) to where their tiles are drawn after a turn
+#[allow(clippy::type_complexity)]
+pub fn reproject_world(
+    mut rotated_events: MessageReader<ViewRotated>,
+    grid: Res<MapGrid>,
+    height_map: Res<HeightMap>,
+    mut cord_system: Option<ResMut<CordSystem>>,
+    mut history: ResMut<UndoHistory>,
+    mut mover_query: Query<(&mut Transform, Option<&mut GridMovement>), Or<(With<Player>, With<GridPlaced>)>>,
+    mut fog_query: Query<(&FogTile, &mut Transform), (Without<Player>, Without<GridPlaced>)>,
+) {
+    for event in rotated_events.read() {
+        let move_point = |point: &mut Vec2| *point = reproject(&event.previous, &grid, *point);
+
+        for (mut transform, grid_movement) in mover_query.iter_mut() {
+            let mut position = transform.translation.truncate();
+            move_point(&mut position);
+            transform.translation.x = position.x;
+            transform.translation.y = position.y;
+            if let Some(target) = grid_movement.and_then(|movement| movement.into_inner().target_position.as_mut()) {
+                move_point(target);
+            }
+        }
+        for (fog_tile, mut transform) in fog_query.iter_mut() {
+            let position = grid.tile_to_world(fog_tile.tile) + Vec2::Y * height_map.height_offset(fog_tile.tile);
+            transform.translation.x = position.x;
+            transform.translation.y = position.y;
+        }
+
+        if let Some(cord_system) = cord_system.as_mut() {
+            cord_system.trail_path.iter_mut().for_each(move_point);
+        }
+        for step in history.snapshots.iter_mut() {
+            step.trail_path.iter_mut().for_each(move_point);
+        }
+    }
+}
+
+/// Keep the camera on the same spot of the map through a turn, and turn its
+/// bounds, the camera rails and the minimap along with the map
+#[allow(clippy::too_many_arguments)]
+pub fn reproject_camera(
+    mut rotated_events: MessageReader<ViewRotated>,
+    grid: Res<MapGrid>,
+    map_assets: Res<Assets<TiledMapAsset>>,
+    map_query: Query<&TiledMap>,
+    mut camera_query: Query<&mut Transform, With<Camera2d>>,
+    mut follow: ResMut<CameraFollow>,
+    mut free: ResMut<FreeCamera>,
+    mut effects: ResMut<CameraEffects>,
+    mut rails: ResMut<CameraRails>,
+    mut bounds: ResMut<CameraBounds>,
+    mut minimap: ResMut<Minimap>,
+    mut node_query: Query<&mut Node, With<MinimapNode>>,
+) {
+    for event in rotated_events.read() {
+        let move_point = |point: &mut Vec2| *point = reproject(&event.previous, &grid, *point);
+
+        for mut transform in camera_query.iter_mut() {
+            let mut position = transform.translation.truncate();
+            move_point(&mut position);
+            transform.translation.x = position.x;
+            transform.translation.y = position.y;
+        }
+        if let Some(focus) = follow.focus.as_mut() {
+            move_point(focus);
+        }
+        if let Some(target) = free.pan_to.as_mut() {
+            move_point(target);
+        }
+
+        // Shots look at world positions too; the camera has jumped, so there is no shake to take back off
+        let effects = &mut *effects;
+        effects.applied = None;
+        for shot in effects.shot.iter_mut().chain(effects.queue.iter_mut()) {
+            match &mut shot.path {
+                ShotPath::Point(target) => move_point(target),
+                ShotPath::Rail { points, .. } => points.iter_mut().for_each(move_point),
+            }
+        }
+        for rail in rails.rails.iter_mut() {
+            rail.points.iter_mut().for_each(move_point);
+        }
+
+        let Some(map_asset) = map_query.iter().find_map(|map| map_assets.get(&map.0)) else { continue; };
+        let rect = grid.tiles_world_rect(map_tile_bounds(&map_asset.map));
+        bounds.rect = Some(rect);
+        if minimap.view.is_some() {
+            let view = MinimapView::fit(rect, MINIMAP_MAX_SIZE);
+            minimap.view = Some(view);
+            for mut node in node_query.iter_mut() {
+                node.width = Val::Px(view.size.x as f32);
+                node.height = Val::Px(view.size.y as f32);
+            }
+        }
+    }
+}
+
+/// Move the tiles of every tile layer to where the turned grid draws them. A
+/// layer's tiles are found by position, so this doesn't depend on how the layer
+/// numbers its tiles; a quarter turn swaps the layer's width and height.
+#[allow(clippy::type_complexity)]
+pub fn rotate_tilemaps(
+    mut rotated_events: MessageReader<ViewRotated>,
+    grid: Res<MapGrid>,
+    mut tilemap_query: Query<(
+        &mut TileStorage,
+        &mut TilemapSize,
+        &TilemapGridSize,
+        &TilemapTileSize,
+        &TilemapType,
+        &TilemapAnchor,
+        &GlobalTransform,
+    )>,
+    mut tile_query: Query<&mut TilePos>,
+) {
+    for event in rotated_events.read() {
+        let odd_turn = (grid.quarter_turns + 4 - event.previous.quarter_turns) % 2 == 1;
+
+        for (mut storage, mut size, grid_size, tile_size, map_type, anchor, transform) in tilemap_query.iter_mut() {
+            let turned_size = if odd_turn { TilemapSize { x: size.y, y: size.x } } else { *size };
+            let to_local = transform.affine().inverse();
+            let mut turned_storage = TileStorage::empty(turned_size);
+
+            for tile in storage.iter().flatten() {
+                let Ok(mut tile_pos) = tile_query.get_mut(*tile) else { continue; };
+                let local = tile_pos.center_in_world(&size, grid_size, tile_size, map_type, anchor);
+                let world = transform.transform_point(local.extend(0.0)).truncate();
+                let turned = to_local
+                    .transform_point3(reproject(&event.previous, &grid, world).extend(0.0))
+                    .truncate();
+                let Some(turned_pos) =
+                    TilePos::from_world_pos(&turned, &turned_size, grid_size, tile_size, map_type, anchor)
+                else {
+                    continue;
+                };
+                *tile_pos = turned_pos;
+                turned_storage.set(&turned_pos, *tile);
+            }
+
+            *storage = turned_storage;
+            *size = turned_size;
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use bevy::camera::RenderTargetInfo;
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+use balthazar::camera::{camera_zoom, clamp_to_bounds, max_scale_for_bounds, CameraBounds, CameraFollow, CameraZoom};
+use balthazar::isometric::MapGrid;
+
+mod common;
+use common::all_toggles_on;
+
+#[test]
+fn test_map_rect_covers_the_outer_tile_corners() {
+    let grid = MapGrid::new(64.0, 32.0);
+    let rect = grid.tiles_world_rect(IRect::new(0, 0, 1, 1));
+    assert_eq!(rect, Rect::new(-64.0, -48.0, 64.0, 16.0));
+
+    // Chunks of an infinite map can start at negative tiles; halfway between
+    // tiles -16 and 15 is tile (-0.5, -0.5)
+    let rect = grid.tiles_world_rect(IRect::new(-16, -16, 15, 15));
+    assert_eq!(rect.center(), Vec2::new(0.0, 16.0));
+}
+
+#[test]
+fn test_view_is_pushed_back_inside_the_map() {
+    let bounds = Rect::new(0.0, 0.0, 1000.0, 500.0);
+    let half_view = Vec2::new(200.0, 100.0);
+
+    assert_eq!(clamp_to_bounds(Vec2::new(500.0, 250.0), half_view, bounds), Vec2::new(500.0, 250.0));
+    assert_eq!(clamp_to_bounds(Vec2::new(-50.0, 480.0), half_view, bounds), Vec2::new(200.0, 400.0));
+    // Wider than the map: centred on that axis
+    assert_eq!(
+        clamp_to_bounds(Vec2::new(900.0, 0.0), Vec2::new(600.0, 100.0), bounds),
+        Vec2::new(500.0, 100.0)
+    );
+
+    assert!((max_scale_for_bounds(Vec2::new(400.0, 300.0), bounds) - 250.0 / 300.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_zooming_out_stops_at_the_map_edges() {
+    let mut app = App::new();
+    app.insert_resource(all_toggles_on());
+    app.add_message::<MouseWheel>();
+    app.init_resource::<Time>();
+    app.init_resource::<ButtonInput<KeyCode>>();
+    app.init_resource::<CameraZoom>();
+    app.init_resource::<CameraFollow>();
+    app.insert_resource(CameraBounds { rect: Some(Rect::new(0.0, 0.0, 400.0, 300.0)) });
+    app.add_systems(Update, camera_zoom);
+
+    let mut camera = Camera::default();
+    camera.computed.target_info = Some(RenderTargetInfo { physical_size: UVec2::new(800, 600), scale_factor: 1.0 });
+    let camera = app
+        .world_mut()
+        .spawn((
+            Camera2d,
+            camera,
+            Projection::Orthographic(OrthographicProjection::default_2d()),
+            Transform::from_xyz(0.0, 0.0, 1000.0),
+        ))
+        .id();
+
+    app.world_mut().write_message(MouseWheel {
+        unit: MouseScrollUnit::Line,
+        x: 0.0,
+        y: -1.0,
+        window: Entity::PLACEHOLDER,
+    });
+    app.update();
+
+    // An 800x600 window fits a 400x300 map at half scale, looking at its centre
+    let Projection::Orthographic(ortho) = app.world().get::<Projection>(camera).unwrap() else {
+        panic!("not orthographic");
+    };
+    assert_eq!(ortho.scale, 0.5);
+    let translation = app.world().get::<Transform>(camera).unwrap().translation;
+    assert_eq!(translation, Vec3::new(200.0, 150.0, 1000.0));
+}
@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+use balthazar::camera_effects::{
+    point_along, polyline_length, shake_offset, CameraEffects, CameraRail, CameraRails, CameraShot, SHOT_BLEND_SECS,
+};
+use balthazar::isometric::MapGrid;
+use bevy_ecs_tiled::prelude::tiled;
+
+const FRAME: f32 = 1.0 / 60.0;
+
+fn run(effects: &mut CameraEffects, base: Vec2, secs: f32) -> Vec2 {
+    let mut position = base;
+    for _ in 0..(secs / FRAME).round() as usize {
+        position = effects.advance(base, FRAME);
+    }
+    position
+}
+
+#[test]
+fn test_shake_grows_with_trauma_squared() {
+    let max = Vec2::new(12.0, 8.0);
+    assert_eq!(shake_offset(0.0, max, 20.0, 1.3), Vec2::ZERO);
+    for step in 0..200 {
+        let time = step as f32 * 0.01;
+        let full = shake_offset(1.0, max, 20.0, time);
+        assert!(full.x.abs() <= 12.0 && full.y.abs() <= 8.0, "{full:?}");
+        assert!((shake_offset(0.5, max, 20.0, time) - full * 0.25).length() < 1e-4);
+    }
+}
+
+#[test]
+fn test_trauma_wears_off() {
+    let mut effects = CameraEffects::default();
+    effects.add_trauma(0.7);
+    effects.add_trauma(0.7);
+    assert_eq!(effects.trauma, 1.0);
+
+    let shaken = run(&mut effects, Vec2::ZERO, 0.5);
+    assert!(effects.trauma > 0.0 && effects.trauma < 1.0);
+    assert_ne!(shaken, Vec2::ZERO);
+
+    assert_eq!(run(&mut effects, Vec2::ZERO, 1.0), Vec2::ZERO);
+    assert_eq!(effects.trauma, 0.0);
+}
+
+#[test]
+fn test_focus_pan_goes_there_and_back() {
+    let mut effects = CameraEffects::default();
+    let base = Vec2::new(-40.0, 10.0);
+    effects.queue.push_back(CameraShot::focus_pan(Vec2::new(300.0, 200.0), 1.0));
+
+    let halfway_in = run(&mut effects, base, SHOT_BLEND_SECS / 2.0);
+    assert!(halfway_in.x > base.x && halfway_in.x < 300.0);
+    assert!((run(&mut effects, base, SHOT_BLEND_SECS) - Vec2::new(300.0, 200.0)).length() < 1e-3);
+
+    // Back at the base once the pan is over
+    let back = run(&mut effects, base, 1.0 + SHOT_BLEND_SECS);
+    assert_eq!(back, base);
+    assert!(effects.shot.is_none());
+
+    // Queued shots play one after the other
+    effects.queue.push_back(CameraShot::focus_pan(Vec2::new(-300.0, 0.0), 0.5));
+    effects.queue.push_back(CameraShot::focus_pan(Vec2::new(0.0, 300.0), 2.0));
+    run(&mut effects, base, FRAME);
+    assert_eq!(effects.shot.as_ref().map(|shot| shot.hold), Some(0.5));
+    assert_eq!(effects.queue.len(), 1);
+}
+
+#[test]
+fn test_rails_follow_their_polyline() {
+    let points = [Vec2::ZERO, Vec2::new(100.0, 0.0), Vec2::new(100.0, 50.0)];
+    assert_eq!(polyline_length(&points), 150.0);
+    assert_eq!(point_along(&points, 50.0), Vec2::new(50.0, 0.0));
+    assert_eq!(point_along(&points, 125.0), Vec2::new(100.0, 25.0));
+    assert_eq!(point_along(&points, 400.0), Vec2::new(100.0, 50.0));
+
+    let rail = CameraRail { name: "intro".to_string(), points: points.to_vec(), speed: 100.0, intro: true };
+    let mut shot = CameraShot::rail(&rail);
+    assert_eq!(shot.duration(), SHOT_BLEND_SECS * 2.0 + 1.5);
+    shot.elapsed = SHOT_BLEND_SECS + 1.0;
+    assert_eq!(shot.weight(), 1.0);
+    assert_eq!(shot.target(), Vec2::new(100.0, 0.0));
+}
+
+#[test]
+fn test_rails_are_read_from_polylines_in_the_map() {
+    let dir = std::env::temp_dir().join(format!("balthazar-rails-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("rails.tmx");
+    std::fs::write(
+        &path,
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" renderorder="right-down" width="4" height="4" tilewidth="32" tileheight="16" infinite="0" nextlayerid="3" nextobjectid="2">
+ <layer id="1" name="Ground" width="4" height="4">
+  <data encoding="csv">
+0,0,0,0,
+0,0,0,0,
+0,0,0,0,
+0,0,0,0
+</data>
+ </layer>
+ <objectgroup id="2" name="Camera">
+  <object id="1" name="intro" type="balthazar::camera_effects::CameraRail" x="32" y="16">
+   <properties>
+    <property name="intro" type="bool" value="true"/>
+    <property name="speed" type="float" value="80"/>
+   </properties>
+   <polyline points="0,0 64,0 64,32"/>
+  </object>
+ </objectgroup>
+</map>
+"#,
+    )
+    .unwrap();
+    let map = tiled::Loader::new().load_tmx_map(&path).unwrap();
+    let rails = CameraRails::from_tiled_map(&map, &MapGrid::for_map(&map));
+
+    // Tile (0, 0) is centred on the origin, so the map's top left corner is at (-16, 8)
+    let rail = rails.get("intro").unwrap();
+    assert_eq!(rail.points, vec![Vec2::new(16.0, -8.0), Vec2::new(80.0, -8.0), Vec2::new(80.0, -40.0)]);
+    assert_eq!(rail.speed, 80.0);
+    assert!(rail.intro);
+}
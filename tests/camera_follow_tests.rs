@@ -0,0 +1,74 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use balthazar::camera::{camera_follow_player, damp_towards, deadzone_target, snap_to_pixels, CameraBounds, CameraFollow};
+use balthazar::components::{GridMovement, Player};
+
+mod common;
+use common::all_toggles_on;
+
+#[test]
+fn test_damping_does_not_depend_on_frame_rate() {
+    let start = Vec2::ZERO;
+    let target = Vec2::new(300.0, -120.0);
+
+    let one_frame = damp_towards(start, target, 8.0, 0.1);
+    let ten_frames = (0..10).fold(start, |position, _| damp_towards(position, target, 8.0, 0.01));
+    assert!(one_frame.distance(ten_frames) < 1e-3, "{one_frame:?} {ten_frames:?}");
+
+    // Never overshoots, however long the frame
+    assert!(damp_towards(start, target, 8.0, 10.0).distance(target) < 1e-3);
+}
+
+#[test]
+fn test_deadzone_only_moves_the_camera_when_left() {
+    let deadzone = Vec2::new(24.0, 16.0);
+    let focus = Vec2::new(100.0, 50.0);
+
+    assert_eq!(deadzone_target(focus, Vec2::new(110.0, 40.0), deadzone), focus);
+    // Leaving to the right pulls the camera just far enough to keep the robot on the edge
+    assert_eq!(deadzone_target(focus, Vec2::new(140.0, 50.0), deadzone), Vec2::new(116.0, 50.0));
+    assert_eq!(deadzone_target(focus, Vec2::new(100.0, 0.0), deadzone), Vec2::new(100.0, 16.0));
+}
+
+#[test]
+fn test_snapping_rounds_to_screen_pixels() {
+    assert_eq!(snap_to_pixels(Vec2::new(10.3, -4.6), 0.25), Vec2::new(10.25, -4.5));
+    assert_eq!(snap_to_pixels(Vec2::new(10.3, -4.6), 1.0), Vec2::new(10.0, -5.0));
+}
+
+#[test]
+fn test_camera_eases_ahead_of_a_moving_robot() {
+    let mut app = App::new();
+    app.insert_resource(all_toggles_on());
+    app.init_resource::<Time>();
+    app.init_resource::<CameraBounds>();
+    app.insert_resource(CameraFollow {
+        pixel_snap: false,
+        focus: Some(Vec2::ZERO),
+        ..default()
+    });
+    app.add_systems(Update, camera_follow_player);
+
+    app.world_mut().spawn((
+        Player,
+        Transform::from_xyz(100.0, 0.0, 200.0),
+        GridMovement {
+            target_position: Some(Vec2::new(164.0, 0.0)),
+            move_speed: 400.0,
+            is_moving: true,
+        },
+    ));
+    let camera = app.world_mut().spawn((Camera2d, Transform::from_xyz(0.0, 0.0, 1000.0))).id();
+
+    app.world_mut().resource_mut::<Time>().advance_by(Duration::from_millis(100));
+    app.update();
+
+    // Looking 48 units ahead of the robot, minus the deadzone, is 124; a tenth
+    // of a second closes 1 - e^-0.8 of the gap
+    let expected = 124.0 * (1.0 - (-0.8f32).exp());
+    let translation = app.world().get::<Transform>(camera).unwrap().translation;
+    assert!((translation.x - expected).abs() < 1e-3, "{translation:?}");
+    assert_eq!(translation.y, 0.0);
+    assert_eq!(translation.z, 1000.0);
+}
@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use bevy::camera::RenderTargetInfo;
+use bevy::input::mouse::MouseWheel;
+use bevy::prelude::*;
+use balthazar::camera::{
+    camera_zoom, ease_scale, pixel_perfect_scale_at_most, step_zoom_scale, zoom_about, CameraBounds, CameraFollow,
+    CameraZoom,
+};
+
+mod common;
+use common::all_toggles_on;
+
+#[test]
+fn test_pixel_perfect_zoom_moves_between_whole_levels() {
+    assert_eq!(step_zoom_scale(1.0, 1.0, true), 0.5);
+    assert_eq!(step_zoom_scale(0.5, 1.0, true), 1.0 / 3.0);
+    assert_eq!(step_zoom_scale(1.0, -1.0, true), 2.0);
+    assert_eq!(step_zoom_scale(2.0, -2.0, true), 4.0);
+    // A scale between levels starts from the nearest one
+    assert_eq!(step_zoom_scale(0.7, 1.0, true), 1.0 / 3.0);
+    // The limits hold
+    assert_eq!(step_zoom_scale(0.1, 3.0, true), 0.1);
+    assert_eq!(step_zoom_scale(10.0, -3.0, true), 10.0);
+
+    assert!((step_zoom_scale(1.0, 1.0, false) - 1.0 / 1.1).abs() < 1e-6);
+
+    assert_eq!(pixel_perfect_scale_at_most(0.83), 0.5);
+    assert_eq!(pixel_perfect_scale_at_most(2.5), 2.0);
+    assert_eq!(pixel_perfect_scale_at_most(1.0), 1.0);
+}
+
+#[test]
+fn test_point_under_the_cursor_stays_put() {
+    let camera = Vec2::new(100.0, 50.0);
+    let anchor = Vec2::new(260.0, -30.0);
+    let moved = zoom_about(camera, anchor, 1.0, 0.5);
+
+    // Same offset on screen: world offset divided by world units per pixel
+    assert_eq!((anchor - camera) / 1.0, (anchor - moved) / 0.5);
+    assert_eq!(zoom_about(camera, camera, 1.0, 3.0), camera);
+}
+
+#[test]
+fn test_scale_eases_evenly_and_lands_on_target() {
+    let one_frame = ease_scale(1.0, 0.25, 12.0, 0.05);
+    let five_frames = (0..5).fold(1.0, |scale, _| ease_scale(scale, 0.25, 12.0, 0.01));
+    assert!((one_frame - five_frames).abs() < 1e-4);
+    assert!(one_frame < 1.0 && one_frame > 0.25);
+
+    assert_eq!(ease_scale(1.0, 0.25, 12.0, 2.0), 0.25);
+}
+
+#[test]
+fn test_keyboard_zoom_animates_to_the_next_level() {
+    let mut app = App::new();
+    app.insert_resource(all_toggles_on());
+    app.add_message::<MouseWheel>();
+    app.init_resource::<Time>();
+    app.init_resource::<ButtonInput<KeyCode>>();
+    app.init_resource::<CameraZoom>();
+    app.init_resource::<CameraFollow>();
+    app.init_resource::<CameraBounds>();
+    app.add_systems(Update, camera_zoom);
+
+    let mut camera = Camera::default();
+    camera.computed.target_info = Some(RenderTargetInfo { physical_size: UVec2::new(800, 600), scale_factor: 1.0 });
+    let camera = app
+        .world_mut()
+        .spawn((
+            Camera2d,
+            camera,
+            Projection::Orthographic(OrthographicProjection::default_2d()),
+            Transform::from_xyz(40.0, 20.0, 1000.0),
+        ))
+        .id();
+    let scale = |app: &App| match app.world().get::<Projection>(camera).unwrap() {
+        Projection::Orthographic(ortho) => ortho.scale,
+        _ => panic!("not orthographic"),
+    };
+
+    app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::Equal);
+    app.world_mut().resource_mut::<Time>().advance_by(Duration::from_millis(50));
+    app.update();
+    assert!(scale(&app) < 1.0 && scale(&app) > 0.5, "{}", scale(&app));
+    assert_eq!(app.world().resource::<CameraZoom>().target_scale, Some(0.5));
+
+    app.world_mut().resource_mut::<ButtonInput<KeyCode>>().clear();
+    app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(1));
+    app.update();
+    assert_eq!(scale(&app), 0.5);
+    // Zooming from the keyboard keeps the view centred
+    let translation = app.world().get::<Transform>(camera).unwrap().translation;
+    assert_eq!(translation, Vec3::new(40.0, 20.0, 1000.0));
+}
@@ -0,0 +1,29 @@
+//! Fixtures shared by the integration tests
+#![allow(dead_code)]
+
+use balthazar::components::SystemToggles;
+use balthazar::isometric::MapGrid;
+
+/// The isometric 64x32 grid the game starts with
+pub fn test_grid() -> MapGrid {
+    MapGrid::new(64.0, 32.0)
+}
+
+/// Every system switched on, as when the game starts
+pub fn all_toggles_on() -> SystemToggles {
+    SystemToggles {
+        player_movement: true,
+        cord_systems: true,
+        camera_follow: true,
+        camera_zoom: true,
+        player_rotation: true,
+    }
+}
+
+/// Everything on, with the camera set free of the robot
+pub fn free_toggles() -> SystemToggles {
+    SystemToggles {
+        camera_follow: false,
+        ..all_toggles_on()
+    }
+}
@@ -0,0 +1,134 @@
+use bevy::prelude::*;
+use balthazar::elevation::{elevate_cord_path, HeightMap, TileElevation, LEVEL_HEIGHT};
+use balthazar::isometric::{GridDirection, GridLayout, Stagger, StaggerAxis, TilePos};
+
+mod common;
+use common::test_grid;
+
+fn ramp_direction_from_name(name: &str) -> Option<IVec2> {
+    GridDirection::from_name(name).map(GridDirection::step)
+}
+
+const ISOMETRIC: GridLayout = GridLayout::Isometric;
+
+fn level(level: i32) -> TileElevation {
+    TileElevation { level, ramp: None }
+}
+
+#[test]
+fn test_ramp_directions_parse() {
+    assert_eq!(ramp_direction_from_name("NE"), Some(IVec2::new(0, -1)));
+    assert_eq!(ramp_direction_from_name("se"), Some(IVec2::new(1, 0)));
+    assert_eq!(ramp_direction_from_name("south_west"), Some(IVec2::new(0, 1)));
+    assert_eq!(ramp_direction_from_name("nw"), Some(IVec2::new(-1, 0)));
+    assert_eq!(ramp_direction_from_name("up"), None);
+}
+
+#[test]
+fn test_same_level_tiles_are_connected() {
+    let height_map = HeightMap::default();
+    assert!(height_map.can_step(&ISOMETRIC, TilePos::ZERO, TilePos::new(1, 0)));
+    assert_eq!(height_map.height_offset(TilePos::new(4, 4)), 0.0);
+}
+
+#[test]
+fn test_cliffs_block_movement_both_ways() {
+    let mut height_map = HeightMap::default();
+    height_map.tiles.insert(TilePos::new(1, 0), level(1));
+
+    assert!(!height_map.can_step(&ISOMETRIC, TilePos::ZERO, TilePos::new(1, 0)));
+    assert!(!height_map.can_step(&ISOMETRIC, TilePos::new(1, 0), TilePos::ZERO));
+}
+
+#[test]
+fn test_ramps_connect_adjacent_levels() {
+    let mut height_map = HeightMap::default();
+    // Ramp on level 0 rising towards the south-east, where level 1 starts
+    height_map.tiles.insert(TilePos::ZERO, TileElevation { level: 0, ramp: Some(GridDirection::SouthEast) });
+    height_map.tiles.insert(TilePos::new(1, 0), level(1));
+    height_map.tiles.insert(TilePos::new(0, 1), level(1));
+
+    assert!(height_map.can_step(&ISOMETRIC, TilePos::ZERO, TilePos::new(1, 0)), "Walk up the ramp");
+    assert!(height_map.can_step(&ISOMETRIC, TilePos::new(1, 0), TilePos::ZERO), "Walk down the ramp");
+    assert!(!height_map.can_step(&ISOMETRIC, TilePos::ZERO, TilePos::new(0, 1)), "Ramp doesn't face this way");
+}
+
+#[test]
+fn test_ramps_on_staggered_maps_work_on_every_row() {
+    let layout = GridLayout::Staggered(Stagger { axis: StaggerAxis::Y, odd: true, side_length: 0.0 });
+    let ramp = |level| TileElevation { level, ramp: Some(GridDirection::NorthEast) };
+    let mut height_map = HeightMap::default();
+    // The up-right neighbour is a different tile step on even and odd rows
+    let (even_ramp, even_top) = (TilePos::new(2, 2), TilePos::new(2, 1));
+    let (odd_ramp, odd_top) = (TilePos::new(2, 3), TilePos::new(3, 2));
+    assert_eq!(layout.neighbor_in(even_ramp, GridDirection::NorthEast), even_top);
+    assert_eq!(layout.neighbor_in(odd_ramp, GridDirection::NorthEast), odd_top);
+
+    for (bottom, top) in [(even_ramp, even_top), (odd_ramp, odd_top)] {
+        height_map.tiles.insert(bottom, ramp(0));
+        height_map.tiles.insert(top, level(1));
+    }
+    height_map.tiles.insert(TilePos::new(3, 4), level(1));
+
+    for (bottom, top) in [(even_ramp, even_top), (odd_ramp, odd_top)] {
+        assert!(height_map.can_step(&layout, bottom, top), "Up the ramp at {bottom:?}");
+        assert!(height_map.can_step(&layout, top, bottom), "Down the ramp at {bottom:?}");
+    }
+    assert!(!height_map.can_step(&layout, odd_ramp, TilePos::new(3, 4)), "Ramp doesn't face this way");
+}
+
+#[test]
+fn test_two_levels_need_more_than_a_ramp() {
+    let mut height_map = HeightMap::default();
+    height_map.tiles.insert(TilePos::ZERO, TileElevation { level: 0, ramp: Some(GridDirection::SouthEast) });
+    height_map.tiles.insert(TilePos::new(1, 0), level(2));
+
+    assert!(!height_map.can_step(&ISOMETRIC, TilePos::ZERO, TilePos::new(1, 0)));
+}
+
+#[test]
+fn test_cord_path_is_lifted_to_tile_height() {
+    let grid = test_grid();
+    let mut height_map = HeightMap::default();
+    height_map.tiles.insert(TilePos::ZERO, level(2));
+    height_map.tiles.insert(TilePos::new(1, 0), TileElevation { level: 2, ramp: None });
+
+    let path = [grid.tile_to_world(TilePos::ZERO), grid.tile_to_world(TilePos::new(1, 0))];
+    let elevated = elevate_cord_path(&path, &grid, &height_map);
+
+    assert_eq!(elevated.len(), 2);
+    assert_eq!(elevated[0], path[0] + Vec2::Y * 2.0 * LEVEL_HEIGHT);
+    assert_eq!(elevated[1], path[1] + Vec2::Y * 2.0 * LEVEL_HEIGHT);
+}
+
+#[test]
+fn test_cord_droops_down_cliffs() {
+    let grid = test_grid();
+    let mut height_map = HeightMap::default();
+    height_map.tiles.insert(TilePos::ZERO, level(1));
+
+    let top = grid.tile_to_world(TilePos::ZERO);
+    let bottom = grid.tile_to_world(TilePos::new(1, 0));
+    let elevated = elevate_cord_path(&[top, bottom], &grid, &height_map);
+
+    // Top of the cliff, edge at the top, edge at the bottom, then the lower tile
+    assert_eq!(elevated.len(), 4);
+    let edge = (top + bottom) / 2.0;
+    assert_eq!(elevated[1], edge + Vec2::Y * LEVEL_HEIGHT);
+    assert_eq!(elevated[2], edge);
+    assert_eq!(elevated[3], bottom);
+    assert_eq!(elevated[1].x, elevated[2].x, "Cord hangs straight down the cliff face");
+}
+
+#[test]
+fn test_cord_follows_ramps_without_droop() {
+    let grid = test_grid();
+    let mut height_map = HeightMap::default();
+    height_map.tiles.insert(TilePos::ZERO, TileElevation { level: 0, ramp: Some(GridDirection::SouthEast) });
+    height_map.tiles.insert(TilePos::new(1, 0), level(1));
+
+    let path = [grid.tile_to_world(TilePos::ZERO), grid.tile_to_world(TilePos::new(1, 0))];
+    let elevated = elevate_cord_path(&path, &grid, &height_map);
+
+    assert_eq!(elevated.len(), 2);
+}
@@ -0,0 +1,75 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use balthazar::elevation::{HeightMap, TileElevation};
+use balthazar::fog_of_war::{has_line_of_sight, visible_tiles, FogOfWar, FogState};
+use balthazar::isometric::TilePos;
+
+mod common;
+use common::test_grid;
+
+fn wall(tiles: &[TilePos], level: i32) -> HeightMap {
+    let mut height_map = HeightMap::default();
+    for tile in tiles {
+        height_map.tiles.insert(*tile, TileElevation { level, ramp: None });
+    }
+    height_map
+}
+
+#[test]
+fn test_light_radius_limits_what_is_visible() {
+    let grid = test_grid();
+    let visible = visible_tiles(&grid, &HeightMap::default(), TilePos::ZERO, 100.0);
+
+    assert!(visible.contains(&TilePos::ZERO));
+    for tile in &visible {
+        assert!(grid.tile_to_world(*tile).distance(grid.tile_to_world(TilePos::ZERO)) <= 100.0);
+    }
+    // Three steps along the x axis is 3 * 35.8 world units away
+    assert!(visible.contains(&TilePos::new(2, 0)));
+    assert!(!visible.contains(&TilePos::new(3, 0)));
+    // Straight down the screen, (2, 2) is only 64 units away
+    assert!(visible.contains(&TilePos::new(2, 2)));
+}
+
+#[test]
+fn test_tall_tiles_block_the_view() {
+    let grid = test_grid();
+    let height_map = wall(&[TilePos::new(2, -1), TilePos::new(2, 0), TilePos::new(2, 1)], 2);
+    let visible = visible_tiles(&grid, &height_map, TilePos::ZERO, 200.0);
+
+    // The wall itself can be seen, but not what's behind it
+    assert!(visible.contains(&TilePos::new(2, 0)));
+    assert!(!visible.contains(&TilePos::new(3, 0)));
+    assert!(!visible.contains(&TilePos::new(4, 0)));
+    assert!(visible.contains(&TilePos::new(0, 3)));
+
+    // Standing on top of the wall, the view is clear again
+    assert!(has_line_of_sight(&grid, &height_map, 2, TilePos::new(2, 0), TilePos::new(5, 0)));
+    assert!(!has_line_of_sight(&grid, &height_map, 0, TilePos::new(1, 0), TilePos::new(3, 0)));
+}
+
+#[test]
+fn test_tiles_stay_explored_after_the_robot_moves_on() {
+    let mut fog = FogOfWar::default();
+    assert_eq!(fog.state(TilePos::new(1, 1)), FogState::Unexplored);
+
+    fog.reveal(HashSet::from([TilePos::ZERO, TilePos::new(1, 0)]));
+    assert_eq!(fog.state(TilePos::new(1, 0)), FogState::Visible);
+
+    fog.reveal(HashSet::from([TilePos::new(1, 0), TilePos::new(2, 0)]));
+    assert_eq!(fog.state(TilePos::ZERO), FogState::Explored);
+    assert_eq!(fog.state(TilePos::new(2, 0)), FogState::Visible);
+    assert_eq!(fog.state(TilePos::new(5, 5)), FogState::Unexplored);
+}
+
+#[test]
+fn test_explored_tiles_survive_a_save() {
+    let mut fog = FogOfWar::default();
+    fog.reveal(HashSet::from([TilePos::new(-3, 7), TilePos::new(0, 0), TilePos::new(12, -1)]));
+    fog.reveal(HashSet::new());
+
+    let text = fog.to_save_string();
+    assert_eq!(FogOfWar::parse_explored(&text), Some(fog.explored.clone()));
+    assert_eq!(FogOfWar::parse_explored("3,4\n5,6\n"), None);
+}
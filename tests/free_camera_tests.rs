@@ -0,0 +1,98 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use balthazar::camera::{CameraBounds, CameraFollow};
+use balthazar::components::{GridMovement, Player, PlayerDirection, PlayerSprite, SystemToggles};
+use balthazar::free_camera::{drag_pan, edge_scroll_direction, free_camera, FreeCamera, RETURN_TO_ROBOT_KEY};
+use balthazar::player_movement::update_player_sprite_direction;
+
+mod common;
+use common::free_toggles;
+
+fn free_camera_app() -> (App, Entity) {
+    let mut app = App::new();
+    app.insert_resource(free_toggles());
+    app.init_resource::<Time>();
+    app.init_resource::<ButtonInput<KeyCode>>();
+    app.init_resource::<ButtonInput<MouseButton>>();
+    app.init_resource::<CameraBounds>();
+    app.init_resource::<FreeCamera>();
+    app.insert_resource(CameraFollow { pixel_snap: false, ..default() });
+    app.add_systems(Update, free_camera);
+
+    app.world_mut().spawn((Player, Transform::from_xyz(500.0, -200.0, 10.0)));
+    let camera = app.world_mut().spawn((Camera2d, Transform::from_xyz(0.0, 0.0, 1000.0))).id();
+    (app, camera)
+}
+
+fn step(app: &mut App, millis: u64) {
+    app.world_mut().resource_mut::<Time>().advance_by(Duration::from_millis(millis));
+    app.update();
+}
+
+#[test]
+fn test_cursor_at_the_edges_scrolls_the_view() {
+    let size = Vec2::new(800.0, 600.0);
+    assert_eq!(edge_scroll_direction(Vec2::new(400.0, 300.0), size, 16.0), Vec2::ZERO);
+    assert_eq!(edge_scroll_direction(Vec2::new(2.0, 300.0), size, 16.0), Vec2::NEG_X);
+    // The top of the window scrolls up the world
+    assert_eq!(edge_scroll_direction(Vec2::new(795.0, 3.0), size, 16.0), Vec2::new(1.0, 1.0));
+}
+
+#[test]
+fn test_dragging_keeps_the_world_under_the_cursor() {
+    // Dragging right and down moves the view left and up
+    assert_eq!(drag_pan(Vec2::new(10.0, 4.0), 0.5), Vec2::new(-5.0, 2.0));
+}
+
+#[test]
+fn test_movement_keys_pan_the_free_camera() {
+    let (mut app, camera) = free_camera_app();
+    app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::KeyD);
+    step(&mut app, 100);
+
+    // 600 pixels a second at scale 1
+    let translation = app.world().get::<Transform>(camera).unwrap().translation;
+    assert!((translation.x - 60.0).abs() < 1e-3, "{translation:?}");
+    assert_eq!(translation.y, 0.0);
+    assert_eq!(app.world().resource::<CameraFollow>().focus, Some(translation.truncate()));
+}
+
+#[test]
+fn test_robot_doesnt_turn_while_the_keys_pan() {
+    let mut app = App::new();
+    app.insert_resource(free_toggles());
+    app.init_resource::<ButtonInput<KeyCode>>();
+    app.add_systems(Update, update_player_sprite_direction);
+    let robot = app
+        .world_mut()
+        .spawn((
+            Player,
+            PlayerSprite { current_direction: PlayerDirection::Up },
+            Sprite::default(),
+            GridMovement { target_position: None, move_speed: 400.0, is_moving: false },
+        ))
+        .id();
+
+    app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::KeyD);
+    app.update();
+    assert!(app.world().get::<PlayerSprite>(robot).unwrap().current_direction == PlayerDirection::Up);
+}
+
+#[test]
+fn test_hotkey_glides_back_to_the_robot() {
+    let (mut app, camera) = free_camera_app();
+    app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(RETURN_TO_ROBOT_KEY);
+    step(&mut app, 50);
+    app.world_mut().resource_mut::<ButtonInput<KeyCode>>().clear();
+
+    let halfway = app.world().get::<Transform>(camera).unwrap().translation.truncate();
+    assert!(halfway.x > 0.0 && halfway.x < 500.0, "{halfway:?}");
+    assert!(app.world().resource::<FreeCamera>().returning);
+
+    step(&mut app, 2000);
+    let arrived = app.world().get::<Transform>(camera).unwrap().translation;
+    assert_eq!(arrived, Vec3::new(500.0, -200.0, 1000.0));
+    assert!(!app.world().resource::<FreeCamera>().returning);
+    assert!(app.world().resource::<SystemToggles>().camera_follow, "Following again, so the robot can move");
+}
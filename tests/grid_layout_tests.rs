@@ -0,0 +1,155 @@
+use bevy::prelude::*;
+use balthazar::isometric::{GridLayout, MapGrid, Stagger, StaggerAxis, TilePos};
+use balthazar::tiled_map::object_tile;
+use bevy_ecs_tiled::prelude::tiled;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const CASES: usize = 500;
+
+fn layouts() -> Vec<MapGrid> {
+    let pointy = Stagger { axis: StaggerAxis::Y, odd: true, side_length: 32.0 };
+    let flat = Stagger { axis: StaggerAxis::X, odd: false, side_length: 24.0 };
+    let staggered = Stagger { axis: StaggerAxis::Y, odd: true, side_length: 0.0 };
+    vec![
+        MapGrid::new(64.0, 32.0),
+        MapGrid::new(32.0, 32.0).with_layout(GridLayout::Orthogonal { diagonal_moves: false }),
+        MapGrid::new(48.0, 32.0).with_layout(GridLayout::Orthogonal { diagonal_moves: true }),
+        MapGrid::new(56.0, 64.0).with_layout(GridLayout::Hexagonal(pointy)),
+        MapGrid::new(64.0, 56.0).with_layout(GridLayout::Hexagonal(flat)),
+        MapGrid::new(64.0, 32.0).with_layout(GridLayout::Staggered(staggered)),
+    ]
+}
+
+fn random_tile(rng: &mut StdRng) -> TilePos {
+    TilePos::new(rng.gen_range(-200..200), rng.gen_range(-200..200))
+}
+
+#[test]
+fn test_every_layout_round_trips_tiles() {
+    let mut rng = StdRng::seed_from_u64(39);
+    for mut grid in layouts() {
+        grid.origin = Vec2::new(rng.gen_range(-500.0..500.0), rng.gen_range(-500.0..500.0));
+        for _ in 0..CASES {
+            let tile = random_tile(&mut rng);
+            assert_eq!(grid.world_to_tile(grid.tile_to_world(tile)), tile, "{grid:?}");
+
+            // Points inside the tile's outline belong to it
+            let center = grid.tile_to_world(tile);
+            let outline = grid.tile_outline(tile);
+            let corner = outline[rng.gen_range(0..outline.len())];
+            let point = center + (corner - center) * rng.gen_range(0.0..0.95);
+            assert_eq!(grid.world_to_tile(point), tile, "{grid:?} {point:?}");
+        }
+    }
+}
+
+#[test]
+fn test_ground_offset_stays_on_the_tile() {
+    assert_eq!(MapGrid::new(64.0, 32.0).ground_offset(), Vec2::new(0.0, -16.0));
+    assert_eq!(MapGrid::new(32.0, 32.0).ground_offset(), Vec2::new(0.0, -16.0));
+    for grid in layouts() {
+        // Just short of the tile's lower edge, whatever the tile size
+        let tile = TilePos::new(3, 4);
+        let ground = grid.tile_to_world(tile) + grid.ground_offset() * 0.9;
+        assert_eq!(grid.world_to_tile(ground), tile, "{grid:?}");
+    }
+}
+
+#[test]
+fn test_neighbors_touch_each_other() {
+    let mut rng = StdRng::seed_from_u64(40);
+    for grid in layouts() {
+        for _ in 0..CASES {
+            let tile = random_tile(&mut rng);
+            let neighbors = grid.neighbors(tile);
+            for neighbor in &neighbors {
+                assert_ne!(*neighbor, tile);
+                assert!(grid.neighbors(*neighbor).contains(&tile), "{grid:?} {tile:?} {neighbor:?}");
+                // Halfway between the centres is on the border of the two tiles
+                let halfway = (grid.tile_to_world(tile) + grid.tile_to_world(*neighbor)) / 2.0;
+                let nudge = (grid.tile_to_world(*neighbor) - halfway) * 0.05;
+                assert_eq!(grid.world_to_tile(halfway - nudge), tile, "{grid:?}");
+                assert_eq!(grid.world_to_tile(halfway + nudge), *neighbor, "{grid:?}");
+            }
+        }
+    }
+    let counts: Vec<usize> = layouts().iter().map(|grid| grid.neighbors(TilePos::ZERO).len()).collect();
+    assert_eq!(counts, vec![4, 4, 8, 6, 6, 4]);
+}
+
+#[test]
+fn test_keys_pick_the_neighbour_on_screen() {
+    let [iso, orthogonal, eight_way, pointy, flat, staggered] = layouts().try_into().unwrap();
+    let step = |grid: &MapGrid, tile: TilePos, keys: Vec2| {
+        grid.neighbor_toward(tile, grid.input_direction(keys))
+    };
+
+    // W follows the diamond's axis up and to the right
+    assert_eq!(step(&iso, TilePos::ZERO, Vec2::Y), Some(TilePos::new(0, -1)));
+    assert_eq!(step(&iso, TilePos::ZERO, Vec2::X), Some(TilePos::new(1, 0)));
+    assert_eq!(step(&iso, TilePos::ZERO, Vec2::ONE), None);
+    assert_eq!(step(&staggered, TilePos::new(2, 2), Vec2::Y), Some(TilePos::new(2, 1)));
+
+    assert_eq!(step(&orthogonal, TilePos::ZERO, Vec2::Y), Some(TilePos::new(0, -1)));
+    assert_eq!(step(&orthogonal, TilePos::ZERO, Vec2::ONE), None);
+    assert_eq!(step(&eight_way, TilePos::ZERO, Vec2::ONE), Some(TilePos::new(1, -1)));
+
+    // Straight up is between two hexagons; left and right aren't
+    assert_eq!(step(&pointy, TilePos::ZERO, Vec2::Y), None);
+    assert_eq!(step(&pointy, TilePos::ZERO, Vec2::new(1.0, 1.0)), Some(TilePos::new(0, -1)));
+    assert_eq!(step(&pointy, TilePos::new(0, 1), Vec2::new(1.0, 1.0)), Some(TilePos::new(1, 0)));
+    assert_eq!(step(&pointy, TilePos::ZERO, Vec2::X), Some(TilePos::new(1, 0)));
+    assert_eq!(step(&flat, TilePos::ZERO, Vec2::Y), Some(TilePos::new(0, -1)));
+    assert_eq!(step(&flat, TilePos::ZERO, Vec2::X), None);
+}
+
+#[test]
+fn test_continue_line_keeps_going_straight() {
+    let mut rng = StdRng::seed_from_u64(41);
+    for grid in layouts() {
+        for _ in 0..CASES {
+            let from = random_tile(&mut rng);
+            let neighbors = grid.neighbors(from);
+            let to = neighbors[rng.gen_range(0..neighbors.len())];
+            let beyond = grid.continue_line(from, to);
+
+            let first = grid.tile_to_world(to) - grid.tile_to_world(from);
+            let second = grid.tile_to_world(beyond) - grid.tile_to_world(to);
+            assert!(first.distance(second) < 1e-2, "{grid:?} {from:?} -> {to:?} -> {beyond:?}");
+        }
+    }
+}
+
+#[test]
+fn test_layout_and_objects_follow_the_map_orientation() {
+    let dir = std::env::temp_dir().join(format!("balthazar-layout-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("orthogonal.tmx");
+    std::fs::write(
+        &path,
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" renderorder="right-down" width="4" height="4" tilewidth="32" tileheight="16" infinite="0" nextlayerid="2" nextobjectid="1">
+ <properties>
+  <property name="diagonal_moves" type="bool" value="true"/>
+ </properties>
+ <layer id="1" name="Ground" width="4" height="4">
+  <data encoding="csv">
+0,0,0,0,
+0,0,0,0,
+0,0,0,0,
+0,0,0,0
+</data>
+ </layer>
+</map>
+"#,
+    )
+    .unwrap();
+    let map = tiled::Loader::new().load_tmx_map(&path).unwrap();
+
+    assert_eq!(GridLayout::from_map(&map), GridLayout::Orthogonal { diagonal_moves: true });
+    assert_eq!(MapGrid::for_map(&map).neighbors(TilePos::ZERO).len(), 8);
+    // Object positions are pixels from the top left
+    assert_eq!(object_tile(&map, 80.0, 20.0), TilePos::new(2, 1));
+    assert_eq!(object_tile(&map, 5.0, 5.0), TilePos::ZERO);
+}
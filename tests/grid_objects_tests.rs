@@ -0,0 +1,142 @@
+use bevy::prelude::*;
+use balthazar::components::{Battery, GridMovement, Player, PowerRoute};
+use balthazar::elevation::HeightMap;
+use balthazar::grid_objects::{activate_switches, Door, GridPlaced, PushableCrate, Switch};
+use balthazar::player_movement::{move_player, GridStepCompleted};
+use balthazar::terrain::TerrainMap;
+use balthazar::isometric::TilePos;
+
+mod common;
+use common::{all_toggles_on, test_grid};
+
+fn idle_movement() -> GridMovement {
+    GridMovement {
+        target_position: None,
+        move_speed: 400.0,
+        is_moving: false,
+    }
+}
+
+fn movement_app() -> App {
+    let mut app = App::new();
+    app.insert_resource(test_grid());
+    app.insert_resource(all_toggles_on());
+    app.init_resource::<TerrainMap>();
+    app.init_resource::<HeightMap>();
+    let mut keys = ButtonInput::<KeyCode>::default();
+    keys.press(KeyCode::KeyW); // Towards tile (0, -1)
+    app.insert_resource(keys);
+    app.add_systems(Update, move_player);
+    app
+}
+
+fn spawn_player(app: &mut App) -> Entity {
+    app.world_mut().spawn((
+        Player,
+        Transform::default(),
+        idle_movement(),
+        Battery {
+            max_charge: 100.0,
+            current_charge: 100.0,
+        },
+    )).id()
+}
+
+fn spawn_crate(app: &mut App, tile: TilePos) -> Entity {
+    let position = test_grid().tile_to_world(tile);
+    app.world_mut().spawn((
+        PushableCrate,
+        GridPlaced,
+        Transform::from_translation(position.extend(0.0)),
+        idle_movement(),
+    )).id()
+}
+
+fn spawn_door(app: &mut App, tile: TilePos, open: bool) -> Entity {
+    let position = test_grid().tile_to_world(tile);
+    app.world_mut().spawn((
+        Door {
+            channel: "gate".to_string(),
+            open,
+        },
+        GridPlaced,
+        Transform::from_translation(position.extend(0.0)),
+    )).id()
+}
+
+#[test]
+fn test_walking_into_crate_pushes_it() {
+    let mut app = movement_app();
+    let player = spawn_player(&mut app);
+    let crate_entity = spawn_crate(&mut app, TilePos::new(0, -1));
+
+    app.update();
+
+    let grid = test_grid();
+    let crate_movement = app.world().get::<GridMovement>(crate_entity).unwrap();
+    assert_eq!(crate_movement.target_position, Some(grid.tile_to_world(TilePos::new(0, -2))));
+    let player_movement = app.world().get::<GridMovement>(player).unwrap();
+    assert_eq!(player_movement.target_position, Some(grid.tile_to_world(TilePos::new(0, -1))));
+}
+
+#[test]
+fn test_crate_against_another_crate_does_not_move() {
+    let mut app = movement_app();
+    let player = spawn_player(&mut app);
+    let crate_entity = spawn_crate(&mut app, TilePos::new(0, -1));
+    spawn_crate(&mut app, TilePos::new(0, -2));
+
+    app.update();
+
+    assert!(!app.world().get::<GridMovement>(crate_entity).unwrap().is_moving);
+    assert!(!app.world().get::<GridMovement>(player).unwrap().is_moving);
+    let battery = app.world().get::<Battery>(player).unwrap();
+    assert_eq!(battery.current_charge, 100.0, "Blocked steps cost nothing");
+}
+
+#[test]
+fn test_closed_door_blocks_and_open_door_lets_through() {
+    let mut app = movement_app();
+    let player = spawn_player(&mut app);
+    let door = spawn_door(&mut app, TilePos::new(0, -1), false);
+
+    app.update();
+    assert!(!app.world().get::<GridMovement>(player).unwrap().is_moving);
+
+    app.world_mut().get_mut::<Door>(door).unwrap().open = true;
+    app.update();
+    assert!(app.world().get::<GridMovement>(player).unwrap().is_moving);
+}
+
+#[test]
+fn test_switch_toggles_doors_and_power_routes_on_its_channel() {
+    let mut app = App::new();
+    app.insert_resource(test_grid());
+    app.add_message::<GridStepCompleted>();
+    app.add_systems(Update, activate_switches);
+
+    let player = app.world_mut().spawn((Player, Transform::default())).id();
+    app.world_mut().spawn((
+        Switch { channel: "gate".to_string() },
+        Transform::default(),
+    ));
+    let door = spawn_door(&mut app, TilePos::new(2, 0), false);
+    let other_door = app.world_mut().spawn((
+        Door {
+            channel: "other".to_string(),
+            open: false,
+        },
+        Transform::default(),
+    )).id();
+    let route = app.world_mut().spawn(PowerRoute {
+        channel: "gate".to_string(),
+        enabled: true,
+    }).id();
+
+    app.world_mut().write_message(GridStepCompleted { entity: player, position: Vec2::ZERO });
+    app.update();
+
+    assert!(app.world().get::<Door>(door).unwrap().open);
+    assert!(!app.world().get::<Door>(other_door).unwrap().open);
+    assert!(!app.world().get::<PowerRoute>(route).unwrap().enabled);
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use balthazar::components::{Battery, CordSystem, GridMovement, Player, Pole};
+use balthazar::grid_objects::{Door, GridPlaced};
+use balthazar::hot_reload::{relink_pole, restore_state_after_reload, save_state_for_reload, MapReload, SavedObject};
+use balthazar::isometric::{MapGrid, TilePos};
+use balthazar::map_objects::{MapObjectId, MapObjectIndex, MapObjectInfo};
+use balthazar::undo::{MoveSnapshot, UndoHistory};
+use bevy_ecs_tiled::prelude::*;
+
+fn object(id: u32, class: &str, tile: TilePos) -> MapObjectInfo {
+    MapObjectInfo {
+        id,
+        name: String::new(),
+        class: class.to_string(),
+        tile,
+        area: IRect::from_corners(tile.as_ivec2(), tile.as_ivec2()),
+        properties: HashMap::new(),
+    }
+}
+
+fn spawn_pole(app: &mut App, grid: &MapGrid, id: u32, tile: TilePos) -> Entity {
+    let position = grid.tile_to_world(tile).extend(0.0);
+    app.world_mut()
+        .spawn((Pole, MapObjectId(id), GridPlaced, Transform::from_translation(position)))
+        .id()
+}
+
+fn spawn_door(app: &mut App, grid: &MapGrid, id: u32, tile: TilePos, open: bool) -> Entity {
+    let position = grid.tile_to_world(tile).extend(0.0);
+    let door = Door { channel: "gate".to_string(), open };
+    app.world_mut()
+        .spawn((door, MapObjectId(id), GridPlaced, Transform::from_translation(position)))
+        .id()
+}
+
+fn modify_map(app: &mut App) {
+    let id = Handle::<TiledMapAsset>::default().id();
+    app.world_mut().write_message(AssetEvent::Modified { id });
+    app.update();
+}
+
+#[test]
+fn test_pole_is_found_by_id_then_by_tile() {
+    let a = Entity::from_bits(10);
+    let b = Entity::from_bits(11);
+    let poles = [(a, Some(1), TilePos::new(0, 0)), (b, None, TilePos::new(3, 3))];
+
+    let moved = SavedObject { id: Some(1), tile: TilePos::new(5, 5) };
+    assert_eq!(relink_pole(moved, &poles), Some(a));
+    let unnamed = SavedObject { id: None, tile: TilePos::new(3, 3) };
+    assert_eq!(relink_pole(unnamed, &poles), Some(b));
+    let removed = SavedObject { id: Some(9), tile: TilePos::new(7, 7) };
+    assert_eq!(relink_pole(removed, &poles), None);
+}
+
+#[test]
+fn test_reload_keeps_player_cord_and_doors() {
+    let old_grid = MapGrid::new(64.0, 32.0);
+    let mut app = App::new();
+    app.add_message::<AssetEvent<TiledMapAsset>>();
+    app.insert_resource(old_grid.clone());
+    app.init_resource::<MapReload>();
+    app.init_resource::<UndoHistory>();
+    app.insert_resource(MapObjectIndex {
+        objects: vec![object(7, "balthazar::components::Pole", TilePos::new(1, 1))],
+    });
+    app.add_systems(Update, (save_state_for_reload, restore_state_after_reload).chain());
+
+    app.world_mut().spawn(TiledMap(Handle::default()));
+    let old_pole = spawn_pole(&mut app, &old_grid, 7, TilePos::new(1, 1));
+    let old_door = spawn_door(&mut app, &old_grid, 4, TilePos::new(2, 0), true);
+    let player = app
+        .world_mut()
+        .spawn((
+            Player,
+            Transform::from_translation(old_grid.tile_to_world(TilePos::new(3, 1)).extend(10.0)),
+            GridMovement { target_position: None, move_speed: 400.0, is_moving: false },
+            Battery { max_charge: 100.0, current_charge: 40.0 },
+        ))
+        .id();
+    let trail = [TilePos::new(1, 1), TilePos::new(2, 1), TilePos::new(3, 1)];
+    app.insert_resource(CordSystem {
+        visual_meshes: Vec::new(),
+        player_entity: player,
+        attached_pole: Some(old_pole),
+        attachment_range: 100.0,
+        trail_path: trail.iter().map(|tile| old_grid.tile_to_world(*tile)).collect(),
+        min_trail_segment_distance: 10.0,
+    });
+    app.world_mut().resource_mut::<UndoHistory>().record(MoveSnapshot {
+        tile: TilePos::new(2, 1),
+        trail_path: vec![old_grid.tile_to_world(TilePos::new(1, 1))],
+        attached_pole: Some(old_pole),
+        battery_charge: 50.0,
+        crates: Vec::new(),
+    });
+    app.update();
+
+    // The file changes: old objects go, and nothing is restored until the new ones arrive
+    modify_map(&mut app);
+    assert!(app.world().get_entity(old_pole).is_err());
+    assert!(app.world().get_entity(old_door).is_err());
+    assert!(app.world().resource::<MapReload>().is_pending());
+
+    // The edited map is wider, so it realigns, and the door comes back closed
+    let mut new_grid = old_grid.clone();
+    new_grid.origin += Vec2::new(-32.0, 16.0);
+    app.insert_resource(new_grid.clone());
+    let new_pole = spawn_pole(&mut app, &new_grid, 7, TilePos::new(1, 1));
+    let new_door = spawn_door(&mut app, &new_grid, 4, TilePos::new(2, 0), false);
+    app.update();
+
+    assert!(!app.world().resource::<MapReload>().is_pending());
+    let transform = app.world().get::<Transform>(player).unwrap();
+    assert_eq!(transform.translation.truncate(), new_grid.tile_to_world(TilePos::new(3, 1)));
+    assert_eq!(app.world().get::<Battery>(player).unwrap().current_charge, 40.0);
+    assert!(app.world().get::<Door>(new_door).unwrap().open);
+
+    let cord_system = app.world().resource::<CordSystem>();
+    assert_eq!(cord_system.attached_pole, Some(new_pole));
+    let new_trail: Vec<Vec2> = trail.iter().map(|tile| new_grid.tile_to_world(*tile)).collect();
+    assert_eq!(cord_system.trail_path, new_trail);
+
+    let history = app.world().resource::<UndoHistory>();
+    assert_eq!(history.snapshots[0].attached_pole, Some(new_pole));
+    assert_eq!(history.snapshots[0].trail_path, vec![new_grid.tile_to_world(TilePos::new(1, 1))]);
+}
+
+#[test]
+fn test_cord_is_dropped_when_its_pole_is_removed() {
+    let grid = MapGrid::new(64.0, 32.0);
+    let mut app = App::new();
+    app.add_message::<AssetEvent<TiledMapAsset>>();
+    app.insert_resource(grid.clone());
+    app.init_resource::<MapReload>();
+    app.init_resource::<UndoHistory>();
+    app.insert_resource(MapObjectIndex {
+        objects: vec![object(8, "balthazar::components::Pole", TilePos::new(4, 4))],
+    });
+    app.add_systems(Update, (save_state_for_reload, restore_state_after_reload).chain());
+
+    app.world_mut().spawn(TiledMap(Handle::default()));
+    let pole = spawn_pole(&mut app, &grid, 7, TilePos::new(1, 1));
+    let player = app.world_mut().spawn((Player, Transform::default())).id();
+    app.insert_resource(CordSystem {
+        visual_meshes: Vec::new(),
+        player_entity: player,
+        attached_pole: Some(pole),
+        attachment_range: 100.0,
+        trail_path: vec![grid.tile_to_world(TilePos::new(1, 1))],
+        min_trail_segment_distance: 10.0,
+    });
+    app.update();
+
+    modify_map(&mut app);
+    spawn_pole(&mut app, &grid, 8, TilePos::new(4, 4));
+    app.update();
+
+    let cord_system = app.world().resource::<CordSystem>();
+    assert_eq!(cord_system.attached_pole, None);
+    assert!(cord_system.trail_path.is_empty());
+}
@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+use balthazar::components::{Battery, CordSystem, Player, Pole};
+use balthazar::cord_system::{cord_tiles_used, CordReel};
+use balthazar::day_night_cycle::DayNightCycle;
+use balthazar::isometric::{MapGrid, TilePos};
+use balthazar::power_system::{battery_time_left, BatteryFlow};
+use balthazar::ui::{battery_text, charge_rate_text, cord_text, format_duration, update_cord_display, CordDisplay};
+
+fn battery(charge: f32) -> Battery {
+    Battery { max_charge: 100.0, current_charge: charge }
+}
+
+fn cord_along(grid: &MapGrid, tiles: &[TilePos], attached: bool) -> CordSystem {
+    CordSystem {
+        visual_meshes: Vec::new(),
+        player_entity: Entity::PLACEHOLDER,
+        attached_pole: attached.then_some(Entity::PLACEHOLDER),
+        attachment_range: 100.0,
+        trail_path: tiles.iter().map(|tile| grid.tile_to_world(*tile)).collect(),
+        min_trail_segment_distance: 10.0,
+    }
+}
+
+#[test]
+fn test_clock_follows_the_day() {
+    let mut cycle = DayNightCycle::default();
+    assert_eq!(cycle.clock(), (12, 0));
+    cycle.time_of_day = 0.0;
+    assert_eq!(cycle.clock(), (0, 0));
+    cycle.time_of_day = 30.0 + 120.0 / 24.0 / 4.0;
+    assert_eq!(cycle.clock(), (6, 15));
+}
+
+#[test]
+fn test_battery_time_left_at_the_net_rate() {
+    assert_eq!(battery_time_left(&battery(50.0), 2.0), Some((25.0, true)));
+    assert_eq!(battery_time_left(&battery(50.0), -5.0), Some((10.0, false)));
+    assert_eq!(battery_time_left(&battery(50.0), 0.0), None);
+    // Already full or flat
+    assert_eq!(battery_time_left(&battery(100.0), 2.0), None);
+    assert_eq!(battery_time_left(&battery(0.0), -1.0), None);
+
+    assert_eq!(format_duration(125.4), "2:05");
+    assert_eq!(battery_text(&battery(50.0), 2.0), "Battery: 50% (full in 0:25)");
+    assert_eq!(battery_text(&battery(50.0), -5.0), "Battery: 50% (empty in 0:10)");
+    assert_eq!(battery_text(&battery(50.0), 0.0), "Battery: 50%");
+}
+
+#[test]
+fn test_charge_rates_settle_on_what_flows() {
+    let mut flow = BatteryFlow::default();
+    let frame = 1.0 / 60.0;
+    for _ in 0..600 {
+        flow.solar_in += 1.0 * frame;
+        flow.pole_in += 5.0 * frame;
+        flow.update_rates(frame);
+    }
+    // A step costing 3 every second drains about 3 per second on average
+    for frame_index in 0..600 {
+        if frame_index % 60 == 0 {
+            flow.drained += 3.0;
+        }
+        flow.solar_in += 1.0 * frame;
+        flow.pole_in += 5.0 * frame;
+        flow.update_rates(frame);
+    }
+    assert!((flow.solar_rate - 1.0).abs() < 0.01);
+    assert!((flow.pole_rate - 5.0).abs() < 0.01);
+    assert!((flow.drain_rate - 3.0).abs() < 1.5);
+    assert!((flow.net_rate() - 3.0).abs() < 1.5);
+
+    let text = charge_rate_text(&BatteryFlow { solar_rate: 1.0, pole_rate: 5.0, drain_rate: 2.5, ..default() });
+    assert_eq!(text, "Charge: +3.5/s (solar +1.0, pole +5.0, drain -2.5)");
+}
+
+#[test]
+fn test_cord_use_counts_steps_from_the_pole() {
+    let grid = MapGrid::default();
+    let tiles: Vec<TilePos> = (0..4).map(|x| TilePos::new(x, 0)).collect();
+    assert_eq!(cord_tiles_used(&cord_along(&grid, &tiles, true)), 3);
+    assert_eq!(cord_tiles_used(&cord_along(&grid, &[], false)), 0);
+}
+
+#[test]
+fn test_cord_display_names_the_pole_and_its_distance() {
+    assert_eq!(cord_text(0, 40, None), "Cord: 0/40 tiles, not plugged in");
+
+    let grid = MapGrid::default();
+    let mut app = App::new();
+    app.insert_resource(grid.clone());
+    app.init_resource::<CordReel>();
+    app.add_systems(Update, update_cord_display);
+
+    let pole = app
+        .world_mut()
+        .spawn((Pole, Name::new("North pole"), Transform::from_translation(grid.tile_to_world(TilePos::ZERO).extend(0.0))))
+        .id();
+    let player_tile = TilePos::new(3, 0);
+    app.world_mut()
+        .spawn((Player, Transform::from_translation(grid.tile_to_world(player_tile).extend(0.0))));
+    let text = app.world_mut().spawn((Text::new(""), CordDisplay)).id();
+
+    let mut cord = cord_along(&grid, &[TilePos::ZERO, TilePos::new(1, 0), TilePos::new(2, 0), player_tile], true);
+    cord.attached_pole = Some(pole);
+    app.insert_resource(cord);
+    app.update();
+
+    assert_eq!(
+        app.world().get::<Text>(text).unwrap().0,
+        "Cord: 3/40 tiles to North pole (3.0 tiles away)"
+    );
+}
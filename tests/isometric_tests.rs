@@ -0,0 +1,165 @@
+use bevy::prelude::*;
+use bevy_ecs_tiled::prelude::{
+    tiled, IsoCoordSystem, TilemapAnchor, TilemapGridSize, TilemapSize, TilemapTileSize, TilemapType,
+};
+use balthazar::isometric::{GridDirection, MapGrid, TilePos};
+use balthazar::tiled_map::map_tile_bounds;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+// Property tests run a fixed number of random cases from a fixed seed so failures reproduce
+const CASES: usize = 2000;
+
+fn random_grid(rng: &mut StdRng) -> MapGrid {
+    let tile_height = rng.gen_range(4..=64) as f32;
+    let mut grid = MapGrid::new(tile_height * 2.0, tile_height);
+    grid.origin = Vec2::new(rng.gen_range(-5000.0..5000.0), rng.gen_range(-5000.0..5000.0));
+    grid
+}
+
+fn random_tile(rng: &mut StdRng) -> TilePos {
+    TilePos::new(rng.gen_range(-500..500), rng.gen_range(-500..500))
+}
+
+/// Whether a point lies inside (or on the edge of) a tile's diamond
+fn diamond_contains(grid: &MapGrid, tile: TilePos, point: Vec2) -> bool {
+    let offset = point - grid.tile_to_world(tile);
+    offset.x.abs() / (grid.tile_width / 2.0) + offset.y.abs() / (grid.tile_height / 2.0) <= 1.0 + 1e-3
+}
+
+#[test]
+fn test_tile_to_world_round_trips() {
+    let mut rng = StdRng::seed_from_u64(32);
+    for _ in 0..CASES {
+        let grid = random_grid(&mut rng);
+        let tile = random_tile(&mut rng);
+        assert_eq!(grid.world_to_tile(grid.tile_to_world(tile)), tile, "{grid:?} {tile:?}");
+    }
+}
+
+#[test]
+fn test_world_to_tile_picks_the_containing_diamond() {
+    let mut rng = StdRng::seed_from_u64(33);
+    for _ in 0..CASES {
+        let grid = random_grid(&mut rng);
+        let tile = random_tile(&mut rng);
+        // Stay just inside the diamond so rounding on shared edges doesn't matter
+        let along_x = rng.gen_range(-0.49..0.49);
+        let along_y = rng.gen_range(-0.49..0.49);
+        let point = grid.tile_to_world(tile)
+            + grid.step_offset(IVec2::X) * along_x
+            + grid.step_offset(IVec2::Y) * along_y;
+
+        let picked = grid.world_to_tile(point);
+        assert_eq!(picked, tile, "{grid:?} {point:?}");
+        assert!(diamond_contains(&grid, picked, point));
+        assert_eq!(grid.snap_to_tile_center(point), grid.tile_to_world(tile));
+    }
+}
+
+#[test]
+fn test_any_world_point_lies_in_its_tile() {
+    let mut rng = StdRng::seed_from_u64(34);
+    for _ in 0..CASES {
+        let grid = random_grid(&mut rng);
+        let point = grid.origin + Vec2::new(rng.gen_range(-10000.0..10000.0), rng.gen_range(-10000.0..10000.0));
+        let tile = grid.world_to_tile(point);
+        assert!(diamond_contains(&grid, tile, point), "{grid:?} {point:?} -> {tile:?}");
+    }
+}
+
+#[test]
+fn test_neighbors_are_one_step_away() {
+    let mut rng = StdRng::seed_from_u64(35);
+    for _ in 0..CASES {
+        let grid = random_grid(&mut rng);
+        let tile = random_tile(&mut rng);
+        let neighbors: Vec<TilePos> = tile.neighbors().collect();
+        assert_eq!(neighbors.len(), 4);
+
+        for (neighbor, direction) in neighbors.into_iter().zip(GridDirection::ALL) {
+            assert_eq!(tile.manhattan_distance(neighbor), 1);
+            assert_eq!(GridDirection::from_step(neighbor - tile), Some(direction));
+            // Neighbouring diamonds share an edge, half a tile away along each screen axis
+            let offset = grid.tile_to_world(neighbor) - grid.tile_to_world(tile);
+            assert!(offset.distance(grid.step_offset(direction.step())) < 1e-2);
+            assert!(offset.abs().distance(Vec2::new(grid.tile_width / 2.0, grid.tile_height / 2.0)) < 1e-2);
+        }
+    }
+}
+
+#[test]
+fn test_directions_match_screen_diagonals() {
+    let grid = MapGrid::new(64.0, 32.0);
+    let screen = |direction: GridDirection| grid.step_offset(direction.step());
+    assert_eq!(screen(GridDirection::NorthEast), Vec2::new(32.0, 16.0));
+    assert_eq!(screen(GridDirection::SouthEast), Vec2::new(32.0, -16.0));
+    assert_eq!(screen(GridDirection::SouthWest), Vec2::new(-32.0, -16.0));
+    assert_eq!(screen(GridDirection::NorthWest), Vec2::new(-32.0, 16.0));
+
+    assert_eq!(GridDirection::from_name("NE"), Some(GridDirection::NorthEast));
+    assert_eq!(GridDirection::from_name("south_west"), Some(GridDirection::SouthWest));
+    assert_eq!(GridDirection::from_name("up"), None);
+}
+
+#[test]
+fn test_tile_corners_surround_the_centre() {
+    let grid = MapGrid::new(64.0, 32.0);
+    let tile = TilePos::new(3, -2);
+    let center = grid.tile_to_world(tile);
+    let [top, right, bottom, left] = grid.tile_corners(tile);
+    assert_eq!(top, center + Vec2::new(0.0, 16.0));
+    assert_eq!(right, center + Vec2::new(32.0, 0.0));
+    assert_eq!(bottom, center + Vec2::new(0.0, -16.0));
+    assert_eq!(left, center + Vec2::new(-32.0, 0.0));
+}
+
+#[test]
+fn test_grid_is_centred_on_the_map_like_the_tilemap_anchor() {
+    let dir = std::env::temp_dir().join(format!("balthazar-isometric-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("centred.tmx");
+    std::fs::write(
+        &path,
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="isometric" renderorder="right-down" width="6" height="4" tilewidth="64" tileheight="32" infinite="0" nextlayerid="2" nextobjectid="1">
+ <tileset firstgid="1" name="ground" tilewidth="64" tileheight="32" tilecount="2" columns="2">
+  <image source="ground.png" width="128" height="32"/>
+ </tileset>
+ <layer id="1" name="Ground" width="6" height="4">
+  <data encoding="csv">
+1,1,2,1,1,1,
+1,2,1,1,2,1,
+1,1,1,2,1,1,
+2,1,1,1,1,2
+</data>
+ </layer>
+</map>
+"#,
+    )
+    .unwrap();
+    let map = tiled::Loader::new().load_tmx_map(&path).unwrap();
+    let bounds = map_tile_bounds(&map);
+    assert!(bounds.min.cmple(bounds.max).all());
+
+    let map_translation = Vec3::new(120.0, -40.0, 0.0);
+    let grid = MapGrid::for_centered_map(&map, &GlobalTransform::from_translation(map_translation));
+    assert_eq!(grid.tile_width, map.tile_width as f32);
+    assert_eq!(grid.tile_height, map.tile_height as f32);
+
+    // Every tile sits where the tilemap itself draws it under `TilemapAnchor::Center`.
+    // bevy_ecs_tiled numbers tilemap rows from the bottom, so Tiled's y is flipped.
+    let size = TilemapSize { x: map.width, y: map.height };
+    let grid_size = TilemapGridSize { x: map.tile_width as f32, y: map.tile_height as f32 };
+    let tile_size = TilemapTileSize { x: map.tile_width as f32, y: map.tile_height as f32 };
+    let map_type = TilemapType::Isometric(IsoCoordSystem::Diamond);
+    for y in bounds.min.y..=bounds.max.y {
+        for x in bounds.min.x..=bounds.max.x {
+            let tilemap_pos = bevy_ecs_tiled::prelude::TilePos::new(x as u32, map.height - 1 - y as u32);
+            let drawn = map_translation.truncate()
+                + tilemap_pos.center_in_world(&size, &grid_size, &tile_size, &map_type, &TilemapAnchor::Center);
+            let tile = TilePos::new(x, y);
+            assert!(grid.tile_to_world(tile).distance(drawn) < 1e-3, "{tile:?}: {:?} vs {drawn:?}", grid.tile_to_world(tile));
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+
+use bevy::ecs::message::Messages;
+use bevy::prelude::*;
+use balthazar::components::{CordSystem, GridMovement, Player};
+use balthazar::hot_reload::MapReload;
+use balthazar::isometric::TilePos;
+use balthazar::levels::{move_player_to_spawn, use_level_exits, LevelRegistry, LevelTransition};
+use balthazar::map_objects::{MapObjectIndex, MapObjectInfo, PlayerSpawn};
+use balthazar::player_movement::GridStepCompleted;
+use balthazar::undo::UndoHistory;
+use bevy_ecs_tiled::prelude::tiled;
+
+mod common;
+use common::test_grid;
+
+fn exit_zone(min: TilePos, max: TilePos, level: &str, spawn: &str) -> MapObjectInfo {
+    let mut properties = HashMap::new();
+    properties.insert("level".to_string(), tiled::PropertyValue::StringValue(level.to_string()));
+    properties.insert("spawn".to_string(), tiled::PropertyValue::StringValue(spawn.to_string()));
+    MapObjectInfo {
+        id: 9,
+        name: "Cave Entrance".to_string(),
+        class: "balthazar::levels::LevelExit".to_string(),
+        tile: min,
+        area: IRect::from_corners(min.as_ivec2(), max.as_ivec2()),
+        properties,
+    }
+}
+
+fn exit_app() -> (App, Entity) {
+    let mut app = App::new();
+    app.add_message::<GridStepCompleted>();
+    app.add_message::<LevelTransition>();
+    app.insert_resource(test_grid());
+    app.insert_resource(LevelRegistry::default().with_level("caves", "caves.tmx"));
+    app.init_resource::<UndoHistory>();
+    app.insert_resource(MapObjectIndex {
+        objects: vec![exit_zone(TilePos::new(2, 2), TilePos::new(3, 4), "caves", "entrance")],
+    });
+    app.add_systems(Update, use_level_exits);
+    let player = app.world_mut().spawn(Player).id();
+    (app, player)
+}
+
+fn step_onto(app: &mut App, player: Entity, tile: TilePos) {
+    let position = test_grid().tile_to_world(tile);
+    app.world_mut().write_message(GridStepCompleted { entity: player, position });
+    app.update();
+}
+
+fn transitions(app: &App) -> Vec<LevelTransition> {
+    app.world()
+        .resource::<Messages<LevelTransition>>()
+        .iter_current_update_messages()
+        .cloned()
+        .collect()
+}
+
+#[test]
+fn test_registry_finds_levels_by_name() {
+    let registry = LevelRegistry::default().with_level("caves", "caves.tmx");
+    assert_eq!(registry.current_level().map_path, "map.tmx");
+    assert_eq!(registry.index_of("caves"), Some(1));
+    assert_eq!(registry.index_of("moon"), None);
+}
+
+#[test]
+fn test_stepping_into_exit_zone_requests_transition() {
+    let (mut app, player) = exit_app();
+
+    step_onto(&mut app, player, TilePos::new(1, 1));
+    assert!(transitions(&app).is_empty());
+
+    // Anywhere inside the rectangle counts
+    step_onto(&mut app, player, TilePos::new(3, 4));
+    assert_eq!(
+        transitions(&app),
+        vec![LevelTransition { level: "caves".to_string(), spawn: "entrance".to_string() }]
+    );
+}
+
+#[test]
+fn test_exit_refused_while_cord_attached() {
+    let (mut app, player) = exit_app();
+    app.insert_resource(CordSystem {
+        visual_meshes: Vec::new(),
+        player_entity: player,
+        attached_pole: Some(Entity::from_bits(200)),
+        attachment_range: 100.0,
+        trail_path: Vec::new(),
+        min_trail_segment_distance: 10.0,
+    });
+
+    step_onto(&mut app, player, TilePos::new(2, 2));
+    assert!(transitions(&app).is_empty());
+}
+
+#[test]
+fn test_exit_to_unknown_level_is_ignored() {
+    let (mut app, player) = exit_app();
+    app.insert_resource(MapObjectIndex {
+        objects: vec![exit_zone(TilePos::new(2, 2), TilePos::new(2, 2), "moon", "start")],
+    });
+
+    step_onto(&mut app, player, TilePos::new(2, 2));
+    assert!(transitions(&app).is_empty());
+}
+
+#[test]
+fn test_player_moves_to_named_spawn() {
+    let grid = test_grid();
+    let mut app = App::new();
+    app.insert_resource(grid.clone());
+    app.init_resource::<UndoHistory>();
+    app.init_resource::<MapReload>();
+    let mut registry = LevelRegistry::default();
+    registry.pending_spawn = Some("east".to_string());
+    app.insert_resource(registry);
+    app.add_systems(Update, move_player_to_spawn);
+
+    let spawn_at = |tile: TilePos| GlobalTransform::from_translation(grid.tile_to_world(tile).extend(0.0));
+    app.world_mut().spawn((PlayerSpawn { name: "start".to_string() }, spawn_at(TilePos::new(0, 0))));
+    app.world_mut().spawn((PlayerSpawn { name: "east".to_string() }, spawn_at(TilePos::new(5, -2))));
+    let player = app
+        .world_mut()
+        .spawn((
+            Player,
+            Transform::default(),
+            GridMovement {
+                target_position: Some(Vec2::new(100.0, 0.0)),
+                move_speed: 400.0,
+                is_moving: true,
+            },
+        ))
+        .id();
+
+    app.update();
+
+    let transform = app.world().get::<Transform>(player).unwrap();
+    assert_eq!(grid.world_to_tile(transform.translation.truncate()), TilePos::new(5, -2));
+    let movement = app.world().get::<GridMovement>(player).unwrap();
+    assert!(!movement.is_moving);
+    assert!(app.world().resource::<LevelRegistry>().pending_spawn.is_none());
+}
@@ -0,0 +1,65 @@
+use bevy::asset::UntypedAssetLoadFailedEvent;
+use bevy::prelude::*;
+use balthazar::loading::{checkerboard_tile_image, track_asset_failures, LoadingText, MapLoadReport};
+
+fn pixel(image: &bevy::image::Image, x: u32, y: u32) -> [u8; 4] {
+    let data = image.data.as_ref().unwrap();
+    let index = ((y * image.width() + x) * 4) as usize;
+    data[index..index + 4].try_into().unwrap()
+}
+
+#[test]
+fn test_report_lists_each_missing_path_once() {
+    let mut report = MapLoadReport::default();
+    assert!(!report.has_failures());
+    assert_eq!(report.summary(), "Loading map...");
+
+    report.record("map.tmx", "tileset 'tilesets/ForestSet.tsx' not found");
+    report.record("map.tmx", "tileset 'tilesets/ForestSet.tsx' not found");
+    report.record("tilesets/rocks.png", "file not found");
+
+    assert!(report.has_failures());
+    assert_eq!(report.failures.len(), 2);
+    let summary = report.summary();
+    assert!(summary.contains("map.tmx: tileset 'tilesets/ForestSet.tsx' not found"));
+    assert!(summary.contains("tilesets/rocks.png: file not found"));
+}
+
+#[test]
+fn test_checkerboard_tile_is_a_diamond() {
+    let image = checkerboard_tile_image(64, 32, 4);
+    assert_eq!((image.width(), image.height()), (64, 32));
+
+    // Corners of the bounding box are outside the diamond
+    assert_eq!(pixel(&image, 0, 0)[3], 0);
+    assert_eq!(pixel(&image, 63, 31)[3], 0);
+    // The middle and points along the axes are filled
+    assert_eq!(pixel(&image, 32, 16)[3], 255);
+    assert_eq!(pixel(&image, 4, 16)[3], 255);
+    assert_eq!(pixel(&image, 32, 2)[3], 255);
+}
+
+#[test]
+fn test_checkerboard_alternates_colours() {
+    let image = checkerboard_tile_image(64, 32, 4);
+    // Neighbouring cells across the middle row of the diamond
+    let first = pixel(&image, 16, 16);
+    let second = pixel(&image, 24, 16);
+    assert_eq!(first[3], 255);
+    assert_eq!(second[3], 255);
+    assert_ne!(first, second);
+}
+
+#[test]
+fn test_loading_text_shows_the_report() {
+    let mut app = App::new();
+    app.init_resource::<MapLoadReport>();
+    app.add_message::<UntypedAssetLoadFailedEvent>();
+    app.add_systems(Update, track_asset_failures);
+    let text = app.world_mut().spawn((Text::new("Loading map..."), LoadingText)).id();
+
+    app.world_mut().resource_mut::<MapLoadReport>().record("tilesets/rocks.png", "file not found");
+    app.update();
+    let shown = app.world().get::<Text>(text).unwrap();
+    assert!(shown.0.contains("tilesets/rocks.png: file not found"), "{}", shown.0);
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_ecs_tiled::prelude::tiled;
+use balthazar::components::{Pole, PowerSource};
+use balthazar::grid_objects::GridPlaced;
+use balthazar::map_objects::{
+use balthazar::isometric::TilePos;
+
+mod common;
+use common::test_grid;
+    float_property, place_map_objects, short_type_name, MapObjectId, MapObjectIndex, MapObjectInfo,
+};
+
+fn powered_pole(id: u32, tile: TilePos, max_output: f32) -> MapObjectInfo {
+    let mut power_fields = HashMap::new();
+    power_fields.insert("max_output".to_string(), tiled::PropertyValue::FloatValue(max_output));
+    let mut properties = HashMap::new();
+    properties.insert(
+        "balthazar::components::PowerSource".to_string(),
+        tiled::PropertyValue::ClassValue {
+            property_type: "balthazar::components::PowerSource".to_string(),
+            properties: power_fields,
+        },
+    );
+    MapObjectInfo {
+        id,
+        name: "Generator Pole".to_string(),
+        class: "balthazar::components::Pole".to_string(),
+        tile,
+        area: IRect::from_corners(tile.as_ivec2(), tile.as_ivec2()),
+        properties,
+    }
+}
+
+#[test]
+fn test_short_type_name_strips_module_path() {
+    assert_eq!(short_type_name("balthazar::components::Pole"), "Pole");
+    assert_eq!(short_type_name("Pole"), "Pole");
+}
+
+#[test]
+fn test_float_property_accepts_ints() {
+    let mut properties = HashMap::new();
+    properties.insert("a".to_string(), tiled::PropertyValue::FloatValue(2.5));
+    properties.insert("b".to_string(), tiled::PropertyValue::IntValue(3));
+    properties.insert("c".to_string(), tiled::PropertyValue::StringValue("x".to_string()));
+
+    assert_eq!(float_property(&properties, "a"), Some(2.5));
+    assert_eq!(float_property(&properties, "b"), Some(3.0));
+    assert_eq!(float_property(&properties, "c"), None);
+    assert_eq!(float_property(&properties, "missing"), None);
+}
+
+#[test]
+fn test_index_finds_objects_by_class_and_tile() {
+    let index = MapObjectIndex {
+        objects: vec![powered_pole(7, TilePos::new(-12, 4), 10.0)],
+    };
+
+    let found = index.find("Pole", TilePos::new(-12, 4)).unwrap();
+    assert_eq!(found.id, 7);
+    assert!(index.find("Pole", TilePos::new(0, 0)).is_none());
+    assert!(index.find("Door", TilePos::new(-12, 4)).is_none());
+    assert_eq!(index.get(7).map(|object| object.short_class()), Some("Pole"));
+
+    let power = found.class_property("PowerSource").unwrap();
+    assert_eq!(float_property(power, "max_output"), Some(10.0));
+}
+
+#[test]
+fn test_placed_pole_is_snapped_and_powered() {
+    let mut app = App::new();
+    let grid = test_grid();
+    let tile = TilePos::new(3, -2);
+    let tile_center = grid.tile_to_world(tile);
+    app.insert_resource(grid);
+    app.insert_resource(MapObjectIndex {
+        objects: vec![powered_pole(1, tile, 10.0)],
+    });
+    app.add_systems(Update, place_map_objects);
+
+    // Slightly off-centre, the way an object dropped by hand in Tiled would be
+    let pole = app.world_mut().spawn((
+        Pole,
+        Transform::default(),
+        GlobalTransform::from_translation((tile_center + Vec2::new(5.0, -3.0)).extend(0.0)),
+    )).id();
+
+    app.update();
+
+    let world = app.world();
+    assert!(world.get::<GridPlaced>(pole).is_some());
+    assert_eq!(world.get::<MapObjectId>(pole), Some(&MapObjectId(1)));
+    assert_eq!(world.get::<PowerSource>(pole).map(|power| power.max_output), Some(10.0));
+    assert_eq!(world.get::<Transform>(pole).unwrap().translation.truncate(), tile_center);
+}
@@ -0,0 +1,161 @@
+use std::path::PathBuf;
+
+use balthazar::mapcheck::{check_map_file, referenced_tilesets};
+
+// Rows 0-1 and row 3 are ground level; row 2 is a wall two levels high that splits them
+const TILESET: &str = r#"
+ <tileset firstgid="1" name="ground" tilewidth="64" tileheight="32" tilecount="2" columns="0">
+  <grid orientation="isometric" width="64" height="32"/>
+  <tile id="0"/>
+  <tile id="1">
+   <properties>
+    <property name="height" type="int" value="2"/>
+   </properties>
+  </tile>
+ </tileset>
+ <layer id="1" name="Ground" width="4" height="4">
+  <data encoding="csv">
+1,1,1,1,
+1,1,1,1,
+2,2,2,2,
+1,1,1,1
+</data>
+ </layer>"#;
+
+const SPAWN: &str = r#"
+  <object id="1" name="Start" type="balthazar::map_objects::PlayerSpawn" x="16" y="16"><point/></object>"#;
+
+const REACHABLE_POLE: &str = r#"
+  <object id="2" name="Near Pole" type="balthazar::components::Pole" x="112" y="48">
+   <properties>
+    <property name="balthazar::components::PowerSource" type="class" propertytype="balthazar::components::PowerSource">
+     <properties>
+      <property name="max_output" type="float" value="10"/>
+     </properties>
+    </property>
+   </properties>
+   <point/>
+  </object>"#;
+
+fn write_map(name: &str, tilesets: &str, objects: &[&str]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("balthazar-mapcheck-{}-{name}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("map.tmx");
+    let tmx = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="isometric" renderorder="right-down" width="4" height="4" tilewidth="64" tileheight="32" infinite="0" nextlayerid="3" nextobjectid="10">{tilesets}
+ <objectgroup id="2" name="Objects">{}
+ </objectgroup>
+</map>
+"#,
+        objects.concat()
+    );
+    std::fs::write(&path, tmx).unwrap();
+    path
+}
+
+#[test]
+fn test_referenced_tilesets_are_listed() {
+    let tmx = r#"<map>
+ <tileset firstgid="1" source="tilesets/Rocks.tsx"/>
+ <tileset firstgid="9" source="tilesets/Forest.tsx"/>
+ <tileset firstgid="41" name="inline" tilewidth="64" tileheight="32"></tileset>
+</map>"#;
+    assert_eq!(referenced_tilesets(tmx), vec!["tilesets/Rocks.tsx", "tilesets/Forest.tsx"]);
+}
+
+#[test]
+fn test_missing_tileset_is_reported_with_its_path() {
+    let path = write_map(
+        "missing-tileset",
+        r#"
+ <tileset firstgid="1" source="tilesets/Nowhere.tsx"/>"#,
+        &[SPAWN],
+    );
+    let report = check_map_file(&path);
+    assert!(!report.is_ok());
+    assert!(report.errors[0].contains("tileset not found"));
+    assert!(report.errors[0].contains("Nowhere.tsx"));
+}
+
+#[test]
+fn test_valid_map_passes() {
+    let path = write_map("valid", TILESET, &[SPAWN, REACHABLE_POLE]);
+    let report = check_map_file(&path);
+    assert!(report.is_ok(), "{report}");
+}
+
+#[test]
+fn test_map_without_spawn_fails() {
+    let path = write_map("no-spawn", TILESET, &[REACHABLE_POLE]);
+    let report = check_map_file(&path);
+    assert!(report.errors.iter().any(|error| error.contains("no player spawn")), "{report}");
+}
+
+#[test]
+fn test_pole_behind_a_cliff_is_unreachable() {
+    let far_pole = r#"
+  <object id="3" name="Far Pole" type="balthazar::components::Pole" x="48" y="112"><point/></object>"#;
+    let path = write_map("unreachable", TILESET, &[SPAWN, REACHABLE_POLE, far_pole]);
+    let report = check_map_file(&path);
+    assert_eq!(report.errors.len(), 1, "{report}");
+    assert!(report.errors[0].contains("Far Pole"));
+    assert!(report.errors[0].contains("cannot be reached"));
+}
+
+#[test]
+fn test_unknown_classes_and_wrong_field_types_are_errors() {
+    let short_class = r#"
+  <object id="4" name="Typo" type="Pole" x="80" y="16"><point/></object>"#;
+    let bad_field = r#"
+  <object id="5" name="Bad Solar" type="balthazar::components::SolarPanel" x="80" y="48">
+   <properties>
+    <property name="max_output" value="lots"/>
+   </properties>
+   <point/>
+  </object>"#;
+    let path = write_map("classes", TILESET, &[SPAWN, short_class, bad_field]);
+    let report = check_map_file(&path);
+
+    assert!(report
+        .errors
+        .iter()
+        .any(|error| error.contains("`Pole` is not a map component") && error.contains("balthazar::components::Pole")));
+    assert!(report
+        .errors
+        .iter()
+        .any(|error| error.contains("Bad Solar") && error.contains("max_output")));
+}
+
+#[test]
+fn test_camera_rails_are_checked() {
+    let rail = r#"
+  <object id="6" name="Intro" type="balthazar::camera_effects::CameraRail" x="16" y="16">
+   <properties>
+    <property name="intro" type="bool" value="true"/>
+    <property name="speed" type="float" value="120"/>
+   </properties>
+   <polyline points="0,0 64,32 96,0"/>
+  </object>"#;
+    let path = write_map("rail", TILESET, &[SPAWN, REACHABLE_POLE, rail]);
+    let report = check_map_file(&path);
+    assert!(report.is_ok(), "{report}");
+    assert!(!report.warnings.iter().any(|warning| warning.contains("Intro")), "{report}");
+
+    let point_rail = r#"
+  <object id="7" name="Not A Line" type="balthazar::camera_effects::CameraRail" x="16" y="16"><point/></object>"#;
+    let bad_properties = r#"
+  <object id="8" name="Bad Rail" type="balthazar::camera_effects::CameraRail" x="16" y="16">
+   <properties>
+    <property name="intro" value="yes"/>
+    <property name="speed" type="float" value="-5"/>
+   </properties>
+   <polyline points="0,0 64,32"/>
+  </object>"#;
+    let path = write_map("bad-rails", TILESET, &[SPAWN, point_rail, bad_properties]);
+    let report = check_map_file(&path);
+    assert_eq!(report.errors.len(), 3, "{report}");
+    assert!(report.errors.iter().any(|error| error.contains("Not A Line") && error.contains("polyline")));
+    assert!(report.errors.iter().any(|error| error.contains("Bad Rail") && error.contains("`intro`")));
+    assert!(report.errors.iter().any(|error| error.contains("Bad Rail") && error.contains("`speed`")));
+}
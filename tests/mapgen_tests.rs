@@ -0,0 +1,122 @@
+use balthazar::isometric::TilePos;
+use balthazar::mapcheck::referenced_tilesets;
+use balthazar::fog_of_war::fog_save_path;
+use balthazar::mapgen::{generate_map, generated_level, starting_levels, MapGenConfig};
+
+fn args(list: &[&str]) -> Vec<String> {
+    list.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[test]
+fn test_same_seed_gives_same_map() {
+    let config = MapGenConfig::with_seed(42);
+    assert_eq!(generate_map(&config), generate_map(&config));
+    assert_ne!(generate_map(&config).tiles, generate_map(&MapGenConfig::with_seed(43)).tiles);
+}
+
+#[test]
+fn test_every_pole_is_reachable_from_the_spawn() {
+    for seed in 0..50 {
+        let config = MapGenConfig::with_seed(seed);
+        let map = generate_map(&config);
+        let reachable = map.reachable_from(map.spawn);
+
+        assert!(map.is_ground(map.spawn), "seed {seed}");
+        // Ground cut off from the spawn is removed entirely
+        let ground = map.tiles.iter().filter(|gid| **gid != 0).count();
+        assert_eq!(reachable.len(), ground, "seed {seed}");
+
+        assert_eq!(map.poles.len(), config.pole_count, "seed {seed}");
+        for pole in &map.poles {
+            assert!(reachable.contains(&pole.tile), "seed {seed}: pole at {:?}", pole.tile);
+            assert_ne!(pole.tile, map.spawn);
+        }
+        let powered = map.poles.iter().filter(|pole| pole.power.is_some()).count();
+        assert_eq!(powered, config.power_source_count, "seed {seed}");
+    }
+}
+
+#[test]
+fn test_crowded_maps_still_get_every_pole() {
+    for seed in 0..50 {
+        let config = MapGenConfig {
+            width: 4,
+            height: 3,
+            pole_count: 9,
+            power_source_count: 4,
+            ..MapGenConfig::with_seed(seed)
+        };
+        let map = generate_map(&config);
+        let reachable = map.reachable_from(map.spawn);
+
+        assert_eq!(map.poles.len(), config.pole_count, "seed {seed}");
+        let mut tiles: Vec<TilePos> = map.poles.iter().map(|pole| pole.tile).collect();
+        tiles.push(map.spawn);
+        tiles.sort();
+        tiles.dedup();
+        assert_eq!(tiles.len(), config.pole_count + 1, "seed {seed}: poles share a tile");
+        assert!(tiles.iter().all(|tile| reachable.contains(tile)), "seed {seed}");
+        let powered = map.poles.iter().filter(|pole| pole.power.is_some()).count();
+        assert_eq!(powered, config.power_source_count, "seed {seed}");
+    }
+}
+
+#[test]
+fn test_generated_tiles_come_from_the_shared_tilesets() {
+    let map = generate_map(&MapGenConfig::with_seed(7));
+    for gid in map.tiles.iter().filter(|gid| **gid != 0) {
+        assert!(matches!(gid, 1..=8 | 9..=16 | 41..=48), "unexpected gid {gid}");
+    }
+}
+
+#[test]
+fn test_tmx_output_describes_the_map() {
+    let map = generate_map(&MapGenConfig::with_seed(3));
+    let tmx = map.to_tmx("../tilesets");
+
+    assert!(tmx.contains(r#"orientation="isometric""#));
+    assert_eq!(referenced_tilesets(&tmx).len(), 3);
+    assert!(referenced_tilesets(&tmx).iter().all(|path| path.starts_with("../tilesets/")));
+    assert!(tmx.contains("balthazar::map_objects::PlayerSpawn"));
+    assert_eq!(tmx.matches("balthazar::components::Pole\"").count(), map.poles.len());
+
+    // Spawn object sits in the middle of its tile, measured in tile heights
+    let spawn = map.spawn;
+    let position = format!(r#"x="{}" y="{}""#, spawn.x * 32 + 16, spawn.y * 32 + 16);
+    assert!(tmx.contains(&position), "{position}");
+}
+
+#[test]
+fn test_command_line_flags() {
+    assert_eq!(MapGenConfig::from_args(args(&[])), Ok(None));
+    assert_eq!(MapGenConfig::from_args(args(&["--fullscreen"])), Ok(None));
+    assert_eq!(MapGenConfig::from_args(args(&["--seed", "99"])), Ok(Some(MapGenConfig::with_seed(99))));
+    assert_eq!(
+        MapGenConfig::from_args(args(&["--seed", "5", "--generate"])),
+        Ok(Some(MapGenConfig::with_seed(5)))
+    );
+    assert!(MapGenConfig::from_args(args(&["--generate"])).unwrap().is_some());
+}
+
+#[test]
+fn test_bad_seed_is_an_error_naming_it() {
+    let error = MapGenConfig::from_args(args(&["--seed", "abc"])).unwrap_err();
+    assert!(error.contains("'abc'"), "{error}");
+    assert!(MapGenConfig::from_args(args(&["--generate", "--seed"])).is_err());
+    assert!(starting_levels(args(&["--seed", "-3"])).is_err());
+}
+
+#[test]
+fn test_each_seed_keeps_its_own_explored_tiles() {
+    let first = generated_level(&MapGenConfig::with_seed(7));
+    let second = generated_level(&MapGenConfig::with_seed(8));
+    assert_eq!(first.map_path, second.map_path);
+    assert_ne!(fog_save_path(&first.name), fog_save_path(&second.name));
+}
+
+#[test]
+fn test_spawn_is_inside_the_map() {
+    let map = generate_map(&MapGenConfig { seed: 11, width: 8, height: 6, ..Default::default() });
+    assert!((0..8).contains(&map.spawn.x) && (0..6).contains(&map.spawn.y));
+    assert!(!map.is_ground(TilePos::new(-1, 0)));
+}
@@ -0,0 +1,88 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use balthazar::isometric::{GridLayout, MapGrid, TilePos};
+use balthazar::minimap::{
+    render_minimap, tile_color, MinimapScene, MinimapView, OUTSIDE_COLOR, POLE_COLOR, ROBOT_COLOR, TRAIL_COLOR,
+    UNEXPLORED_COLOR,
+};
+use balthazar::terrain::TerrainType;
+
+/// 8x8 square tiles, tile (0, 0) in the top left corner at the world origin
+fn test_grid() -> MapGrid {
+    let mut grid = MapGrid::new(8.0, 8.0).with_layout(GridLayout::Orthogonal { diagonal_moves: false });
+    grid.origin = Vec2::new(4.0, -4.0);
+    grid
+}
+
+fn pixel(data: &[u8], view: &MinimapView, x: u32, y: u32) -> [u8; 4] {
+    let index = ((y * view.size.x + x) * 4) as usize;
+    data[index..index + 4].try_into().unwrap()
+}
+
+#[test]
+fn test_view_keeps_the_map_aspect_ratio() {
+    let view = MinimapView::fit(Rect::new(0.0, -24.0, 32.0, 0.0), 16);
+    assert_eq!(view.size, UVec2::new(16, 12));
+
+    assert_eq!(view.pixel_to_world(Vec2::ZERO), Vec2::new(0.0, 0.0));
+    assert_eq!(view.pixel_to_world(Vec2::new(16.0, 12.0)), Vec2::new(32.0, -24.0));
+    assert_eq!(view.world_to_pixel(Vec2::new(12.0, -12.0)), Some(UVec2::new(6, 6)));
+    assert_eq!(view.world_to_pixel(Vec2::new(40.0, -12.0)), None);
+}
+
+#[test]
+fn test_minimap_shows_explored_tiles_and_markers() {
+    let grid = test_grid();
+    let view = MinimapView::fit(grid.tiles_world_rect(IRect::new(0, 0, 3, 2)), 16);
+    assert_eq!(view.size, UVec2::new(16, 12));
+
+    // Every tile but the bottom right one is part of the map
+    let mut tiles = HashMap::new();
+    for y in 0..3 {
+        for x in 0..4 {
+            let terrain = if x == 0 { TerrainType::Road } else { TerrainType::Plain };
+            tiles.insert(TilePos::new(x, y), tile_color(terrain, 0));
+        }
+    }
+    tiles.remove(&TilePos::new(3, 2));
+    let explored = HashSet::from([TilePos::new(0, 0), TilePos::new(1, 0), TilePos::new(0, 2), TilePos::new(1, 1)]);
+
+    let trail = [grid.tile_to_world(TilePos::new(0, 0)), grid.tile_to_world(TilePos::new(3, 0))];
+    let poles = [grid.tile_to_world(TilePos::new(0, 0)), grid.tile_to_world(TilePos::new(2, 2))];
+    let scene = MinimapScene {
+        tiles: &tiles,
+        explored: &explored,
+        trail: &trail,
+        poles: &poles,
+        power_sources: &[],
+        robot: Some(grid.tile_to_world(TilePos::new(1, 1))),
+    };
+    let data = render_minimap(&view, &grid, &scene);
+    assert_eq!(data.len(), 16 * 12 * 4);
+
+    // Tiles are four pixels across
+    assert_eq!(pixel(&data, &view, 1, 10), tile_color(TerrainType::Road, 0));
+    assert_eq!(pixel(&data, &view, 9, 9), UNEXPLORED_COLOR);
+    assert_eq!(pixel(&data, &view, 14, 10), OUTSIDE_COLOR);
+
+    // The cord runs along the top row of tiles, under the pole it starts from
+    assert_eq!(pixel(&data, &view, 8, 2), TRAIL_COLOR);
+    assert_eq!(pixel(&data, &view, 14, 2), TRAIL_COLOR);
+    assert_eq!(pixel(&data, &view, 2, 2), POLE_COLOR);
+    // A pole on an unexplored tile stays hidden
+    assert_eq!(pixel(&data, &view, 10, 10), UNEXPLORED_COLOR);
+
+    for (x, y) in [(5, 5), (6, 6), (7, 7)] {
+        assert_eq!(pixel(&data, &view, x, y), ROBOT_COLOR);
+    }
+    assert_eq!(pixel(&data, &view, 4, 6), tile_color(TerrainType::Plain, 0));
+}
+
+#[test]
+fn test_higher_tiles_are_lighter() {
+    let low = tile_color(TerrainType::Plain, 0);
+    let high = tile_color(TerrainType::Plain, 2);
+    assert!(high[..3].iter().zip(&low[..3]).all(|(high, low)| high > low));
+    assert_eq!(high[3], 255);
+}
@@ -0,0 +1,70 @@
+use bevy::prelude::*;
+use balthazar::elevation::{HeightMap, TileElevation, LEVEL_HEIGHT};
+use balthazar::isometric::TilePos;
+use balthazar::picking::{pick_tile, viewport_to_world};
+
+mod common;
+use common::test_grid;
+
+fn viewport() -> Rect {
+    Rect::new(0.0, 0.0, 800.0, 600.0)
+}
+
+fn projection(scale: f32) -> OrthographicProjection {
+    let mut projection = OrthographicProjection::default_2d();
+    projection.scale = scale;
+    projection
+}
+
+#[test]
+fn test_window_centre_projects_onto_the_camera() {
+    let camera = GlobalTransform::from_translation(Vec3::new(100.0, -50.0, 0.0));
+    let world = viewport_to_world(Vec2::new(400.0, 300.0), viewport(), &projection(0.25), &camera);
+    assert!(world.distance(Vec2::new(100.0, -50.0)) < 1e-3);
+}
+
+#[test]
+fn test_projection_scale_and_y_flip() {
+    let camera = GlobalTransform::IDENTITY;
+
+    // A quarter scale shows 200x150 world units; the top left corner is up and to the left
+    let top_left = viewport_to_world(Vec2::ZERO, viewport(), &projection(0.25), &camera);
+    assert!(top_left.distance(Vec2::new(-100.0, 75.0)) < 1e-3, "{top_left:?}");
+
+    let zoomed_out = viewport_to_world(Vec2::new(800.0, 600.0), viewport(), &projection(2.0), &camera);
+    assert!(zoomed_out.distance(Vec2::new(800.0, -600.0)) < 1e-3, "{zoomed_out:?}");
+}
+
+#[test]
+fn test_projection_follows_camera_rotation() {
+    let camera = GlobalTransform::from(Transform::from_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2)));
+    // Right of centre on screen is up in the world once the camera is turned a quarter left
+    let world = viewport_to_world(Vec2::new(500.0, 300.0), viewport(), &projection(1.0), &camera);
+    assert!(world.distance(Vec2::new(0.0, 100.0)) < 1e-3, "{world:?}");
+}
+
+#[test]
+fn test_picking_respects_diamond_edges() {
+    let grid = test_grid();
+    let height_map = HeightMap::default();
+    let center = grid.tile_to_world(TilePos::ZERO);
+
+    // Inside the bounding box of tile (0, 0) but past its top-right edge
+    assert_eq!(pick_tile(&grid, &height_map, center + Vec2::new(2.0, 2.0)), TilePos::ZERO);
+    assert_eq!(pick_tile(&grid, &height_map, center + Vec2::new(28.0, 14.0)), TilePos::new(0, -1));
+    assert_eq!(pick_tile(&grid, &height_map, center + Vec2::new(-28.0, -14.0)), TilePos::new(0, 1));
+}
+
+#[test]
+fn test_picking_prefers_raised_tiles_in_front() {
+    let grid = test_grid();
+    let mut height_map = HeightMap::default();
+    let raised = TilePos::new(2, 2);
+    height_map.tiles.insert(raised, TileElevation { level: 1, ramp: None });
+
+    // The raised tile is drawn one level higher, over whatever is behind it
+    let drawn_at = grid.tile_to_world(raised) + Vec2::Y * LEVEL_HEIGHT;
+    assert_eq!(pick_tile(&grid, &height_map, drawn_at), raised);
+    // Just above its raised top corner is the ground tile behind it
+    assert_eq!(pick_tile(&grid, &height_map, drawn_at + Vec2::Y * 20.0), TilePos::new(1, 1));
+}
@@ -0,0 +1,138 @@
+use bevy::ecs::message::Messages;
+use bevy::prelude::*;
+use bevy_ecs_tiled::prelude::TiledMap;
+use balthazar::components::{Battery, CordSystem, GridMovement, Player};
+use balthazar::cord_system::CordMeshSegment;
+use balthazar::day_night_cycle::DayNightCycle;
+use balthazar::grid_objects::GridPlaced;
+use balthazar::hot_reload::MapReload;
+use balthazar::isometric::{MapGrid, TilePos};
+use balthazar::levels::{move_player_to_spawn, LevelRegistry};
+use balthazar::map_objects::PlayerSpawn;
+use balthazar::power_system::BatteryFlow;
+use balthazar::reset::{request_reset, reset_world, ResetButton, ResetWorld, RESET_KEY};
+use balthazar::undo::UndoHistory;
+use balthazar::view_rotation::ViewRotated;
+
+mod common;
+use common::test_grid;
+
+fn reset_app(grid: MapGrid) -> App {
+    let mut app = App::new();
+    app.insert_resource(grid);
+    app.insert_resource(DayNightCycle::default());
+    app.init_resource::<UndoHistory>();
+    app.init_resource::<BatteryFlow>();
+    app.init_resource::<MapReload>();
+    app.init_resource::<LevelRegistry>();
+    app.add_message::<ResetWorld>();
+    app.add_message::<ViewRotated>();
+    app.add_systems(Update, reset_world);
+    app.add_systems(PostUpdate, move_player_to_spawn);
+    app
+}
+
+fn send_reset(app: &mut App) {
+    app.world_mut().write_message(ResetWorld);
+    app.update();
+}
+
+#[test]
+fn test_reset_puts_the_world_back() {
+    let grid = test_grid();
+    let mut app = reset_app(grid.clone());
+
+    let player = app
+        .world_mut()
+        .spawn((
+            Player,
+            Transform::from_translation(grid.tile_to_world(TilePos::new(4, 2)).extend(0.0)),
+            GridMovement {
+                target_position: Some(grid.tile_to_world(TilePos::new(5, 2))),
+                move_speed: 400.0,
+                is_moving: true,
+            },
+            Battery { max_charge: 100.0, current_charge: 12.0 },
+        ))
+        .id();
+    let tracked_mesh = app.world_mut().spawn(CordMeshSegment).id();
+    let stray_mesh = app.world_mut().spawn(CordMeshSegment).id();
+    app.insert_resource(CordSystem {
+        visual_meshes: vec![tracked_mesh],
+        player_entity: player,
+        attached_pole: Some(Entity::PLACEHOLDER),
+        attachment_range: 100.0,
+        trail_path: vec![Vec2::ZERO, grid.tile_to_world(TilePos::new(4, 2))],
+        min_trail_segment_distance: 10.0,
+    });
+    app.world_mut().resource_mut::<DayNightCycle>().time_of_day = 100.0;
+    app.world_mut().resource_mut::<BatteryFlow>().drain_rate = 3.0;
+    let map = app.world_mut().spawn(TiledMap(Handle::default())).id();
+    let pushed_crate = app.world_mut().spawn(GridPlaced).id();
+
+    send_reset(&mut app);
+
+    let cord_system = app.world().resource::<CordSystem>();
+    assert_eq!(cord_system.attached_pole, None);
+    assert!(cord_system.trail_path.is_empty());
+    assert!(cord_system.visual_meshes.is_empty());
+    for entity in [tracked_mesh, stray_mesh, map, pushed_crate] {
+        assert!(app.world().get_entity(entity).is_err());
+    }
+    let battery = app.world().get::<Battery>(player).unwrap();
+    assert_eq!(battery.current_charge, battery.max_charge);
+    assert!(!app.world().get::<GridMovement>(player).unwrap().is_moving);
+    assert_eq!(app.world().resource::<DayNightCycle>().time_of_day, DayNightCycle::default().time_of_day);
+    assert_eq!(app.world().resource::<BatteryFlow>().drain_rate, 0.0);
+
+    // The map comes back from the same asset...
+    let mut maps = app.world_mut().query::<&TiledMap>();
+    assert_eq!(maps.iter(app.world()).count(), 1);
+
+    // ...and the robot goes to the spawn point once its objects are in
+    app.world_mut().spawn((
+        PlayerSpawn { name: "start".to_string() },
+        GlobalTransform::from_translation(grid.tile_to_world(TilePos::new(1, 1)).extend(0.0)),
+    ));
+    app.update();
+    let transform = app.world().get::<Transform>(player).unwrap();
+    assert_eq!(grid.world_to_tile(transform.translation.truncate()), TilePos::new(1, 1));
+}
+
+#[test]
+fn test_reset_turns_the_view_back() {
+    let mut app = reset_app(test_grid().rotated(1));
+    send_reset(&mut app);
+
+    assert_eq!(app.world().resource::<MapGrid>().quarter_turns, 0);
+    let rotations: Vec<ViewRotated> = app
+        .world()
+        .resource::<Messages<ViewRotated>>()
+        .iter_current_update_messages()
+        .cloned()
+        .collect();
+    assert_eq!(rotations.len(), 1);
+    assert_eq!(rotations[0].previous.quarter_turns, 1);
+}
+
+#[test]
+fn test_key_and_button_ask_for_a_reset() {
+    let mut app = App::new();
+    app.init_resource::<ButtonInput<KeyCode>>();
+    app.add_message::<ResetWorld>();
+    app.add_systems(Update, request_reset);
+    let button = app.world_mut().spawn((Button, Interaction::None, ResetButton)).id();
+    let resets = |app: &App| app.world().resource::<Messages<ResetWorld>>().iter_current_update_messages().count();
+
+    app.update();
+    assert_eq!(resets(&app), 0);
+
+    app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(RESET_KEY);
+    app.update();
+    assert_eq!(resets(&app), 1);
+
+    app.world_mut().resource_mut::<ButtonInput<KeyCode>>().clear();
+    app.world_mut().entity_mut(button).insert(Interaction::Pressed);
+    app.update();
+    assert_eq!(resets(&app), 1);
+}
@@ -0,0 +1,151 @@
+use bevy::prelude::*;
+use balthazar::components::{CordSystem, GridMovement, Player};
+use balthazar::elevation::HeightMap;
+use balthazar::player_movement::GridStepCompleted;
+use balthazar::special_tiles::{apply_tile_behaviors, run_conveyors, SpecialTileMap, TileBehavior, TileMotion};
+use balthazar::undo::UndoHistory;
+use balthazar::isometric::{GridDirection, GridLayout, Stagger, StaggerAxis, TilePos};
+
+mod common;
+use common::{all_toggles_on, test_grid};
+
+fn special_tiles_app(special_tiles: SpecialTileMap) -> App {
+    let mut app = App::new();
+    app.insert_resource(test_grid());
+    app.insert_resource(all_toggles_on());
+    app.insert_resource(special_tiles);
+    app.init_resource::<HeightMap>();
+    app.init_resource::<UndoHistory>();
+    app.init_resource::<Time>();
+    app.add_message::<GridStepCompleted>();
+    app.add_systems(Update, (apply_tile_behaviors, run_conveyors).chain());
+    app
+}
+
+fn spawn_player(app: &mut App, tile: TilePos, previous_tile: Option<TilePos>) -> Entity {
+    let position = test_grid().tile_to_world(tile);
+    app.world_mut().spawn((
+        Player,
+        Transform::from_translation(position.extend(0.0)),
+        GridMovement {
+            target_position: None,
+            move_speed: 400.0,
+            is_moving: false,
+        },
+        TileMotion {
+            previous_tile,
+            ..default()
+        },
+    )).id()
+}
+
+fn pads() -> SpecialTileMap {
+    let mut special_tiles = SpecialTileMap::default();
+    special_tiles.tiles.insert(TilePos::new(0, 0), TileBehavior::Teleport { target: "b".to_string() });
+    special_tiles.tiles.insert(TilePos::new(9, 9), TileBehavior::Teleport { target: "a".to_string() });
+    special_tiles.pads.insert("a".to_string(), TilePos::new(0, 0));
+    special_tiles.pads.insert("b".to_string(), TilePos::new(9, 9));
+    special_tiles
+}
+
+#[test]
+fn test_teleport_destinations_follow_pad_links() {
+    let special_tiles = pads();
+    assert_eq!(special_tiles.teleport_destination(TilePos::new(0, 0)), Some(TilePos::new(9, 9)));
+    assert_eq!(special_tiles.teleport_destination(TilePos::new(9, 9)), Some(TilePos::new(0, 0)));
+    assert_eq!(special_tiles.teleport_destination(TilePos::new(1, 1)), None);
+}
+
+#[test]
+fn test_ice_keeps_robot_sliding_in_step_direction() {
+    let mut special_tiles = SpecialTileMap::default();
+    special_tiles.tiles.insert(TilePos::new(1, 0), TileBehavior::Ice);
+    let mut app = special_tiles_app(special_tiles);
+
+    let player = spawn_player(&mut app, TilePos::new(1, 0), Some(TilePos::new(0, 0)));
+    let position = test_grid().tile_to_world(TilePos::new(1, 0));
+    app.world_mut().write_message(GridStepCompleted { entity: player, position });
+    app.update();
+
+    let movement = app.world().get::<GridMovement>(player).unwrap();
+    assert_eq!(movement.target_position, Some(test_grid().tile_to_world(TilePos::new(2, 0))));
+}
+
+#[test]
+fn test_teleport_moves_robot_to_linked_pad() {
+    let mut app = special_tiles_app(pads());
+    let player = spawn_player(&mut app, TilePos::new(0, 0), Some(TilePos::new(0, 1)));
+    app.world_mut().write_message(GridStepCompleted { entity: player, position: Vec2::ZERO });
+    app.update();
+
+    let destination = test_grid().tile_to_world(TilePos::new(9, 9));
+    let transform = app.world().get::<Transform>(player).unwrap();
+    assert_eq!(transform.translation.truncate(), destination);
+    assert!(app.world().get::<TileMotion>(player).unwrap().teleported);
+}
+
+#[test]
+fn test_teleport_refused_while_cord_is_attached() {
+    let mut app = special_tiles_app(pads());
+    let player = spawn_player(&mut app, TilePos::new(0, 0), Some(TilePos::new(0, 1)));
+    app.insert_resource(CordSystem {
+        visual_meshes: Vec::new(),
+        player_entity: player,
+        attached_pole: Some(Entity::from_bits(200)),
+        attachment_range: 100.0,
+        trail_path: Vec::new(),
+        min_trail_segment_distance: 10.0,
+    });
+    app.world_mut().write_message(GridStepCompleted { entity: player, position: Vec2::ZERO });
+    app.update();
+
+    let transform = app.world().get::<Transform>(player).unwrap();
+    assert_eq!(transform.translation.truncate(), Vec2::ZERO);
+    assert!(!app.world().get::<GridMovement>(player).unwrap().is_moving);
+}
+
+#[test]
+fn test_conveyor_carries_robot_after_interval() {
+    let mut special_tiles = SpecialTileMap::default();
+    special_tiles.tiles.insert(
+        TilePos::ZERO,
+        TileBehavior::Conveyor { direction: GridDirection::SouthEast, interval: 0.5 },
+    );
+    let mut app = special_tiles_app(special_tiles);
+    let player = spawn_player(&mut app, TilePos::ZERO, None);
+
+    app.world_mut().resource_mut::<Time>().advance_by(std::time::Duration::from_secs_f32(0.3));
+    app.update();
+    assert!(!app.world().get::<GridMovement>(player).unwrap().is_moving);
+
+    app.world_mut().resource_mut::<Time>().advance_by(std::time::Duration::from_secs_f32(0.3));
+    app.update();
+    let movement = app.world().get::<GridMovement>(player).unwrap();
+    assert_eq!(movement.target_position, Some(test_grid().tile_to_world(TilePos::new(1, 0))));
+}
+
+#[test]
+fn test_conveyors_on_staggered_maps_keep_their_heading() {
+    let stagger = Stagger { axis: StaggerAxis::Y, odd: true, side_length: 0.0 };
+    let grid = test_grid().with_layout(GridLayout::Staggered(stagger));
+    // A line of north-east conveyors crossing an even and an odd row
+    let belt = [TilePos::new(2, 2), TilePos::new(2, 1), TilePos::new(3, 0)];
+    let mut special_tiles = SpecialTileMap::default();
+    for tile in &belt[..2] {
+        special_tiles.tiles.insert(*tile, TileBehavior::Conveyor { direction: GridDirection::NorthEast, interval: 0.5 });
+    }
+    let mut app = special_tiles_app(special_tiles);
+    app.insert_resource(grid.clone());
+    let player = spawn_player(&mut app, TilePos::ZERO, None);
+
+    for step in belt.windows(2) {
+        let from = grid.tile_to_world(step[0]);
+        app.world_mut().get_mut::<Transform>(player).unwrap().translation = from.extend(0.0);
+        app.world_mut().get_mut::<GridMovement>(player).unwrap().is_moving = false;
+        app.world_mut().resource_mut::<Time>().advance_by(std::time::Duration::from_secs_f32(0.6));
+        app.update();
+
+        let movement = app.world().get::<GridMovement>(player).unwrap();
+        assert_eq!(movement.target_position, Some(grid.tile_to_world(step[1])), "Carried on from {:?}", step[0]);
+    }
+}
@@ -0,0 +1,102 @@
+use bevy::prelude::*;
+use balthazar::components::{Battery, GridMovement, Player};
+use balthazar::elevation::HeightMap;
+use balthazar::player_movement::move_player;
+use balthazar::terrain::{TerrainMap, TerrainType, BASE_STEP_COST};
+use balthazar::isometric::TilePos;
+
+mod common;
+use common::{all_toggles_on, test_grid};
+
+#[test]
+fn test_terrain_names_parse() {
+    assert_eq!(TerrainType::from_name("rock"), Some(TerrainType::Rock));
+    assert_eq!(TerrainType::from_name(" Forest "), Some(TerrainType::Forest));
+    assert_eq!(TerrainType::from_name("ROAD"), Some(TerrainType::Road));
+    assert_eq!(TerrainType::from_name("mud"), Some(TerrainType::Mud));
+    assert_eq!(TerrainType::from_name("lava"), None);
+}
+
+#[test]
+fn test_terrain_guessed_from_tileset_name() {
+    assert_eq!(
+        TerrainType::from_tileset_name("RocksAndMountainsSet_02_8Tiles_256x256"),
+        Some(TerrainType::Rock)
+    );
+    assert_eq!(
+        TerrainType::from_tileset_name("ForestSet_01_8Tiles_256x256"),
+        Some(TerrainType::Forest)
+    );
+    assert_eq!(
+        TerrainType::from_tileset_name("ColonyBuildings_Tier1_Set_01_8Tiles_256x256"),
+        Some(TerrainType::Road)
+    );
+    assert_eq!(TerrainType::from_tileset_name("Water"), None);
+}
+
+#[test]
+fn test_road_is_fastest_and_cheapest() {
+    let others = [TerrainType::Plain, TerrainType::Forest, TerrainType::Rock, TerrainType::Mud];
+    for terrain in others {
+        assert!(TerrainType::Road.speed_multiplier() > terrain.speed_multiplier());
+        assert!(TerrainType::Road.step_cost() < terrain.step_cost());
+    }
+    assert_eq!(TerrainType::Plain.step_cost(), BASE_STEP_COST);
+    assert_eq!(TerrainType::Plain.speed_multiplier(), 1.0);
+}
+
+#[test]
+fn test_unknown_tiles_default_to_plain() {
+    let terrain = TerrainMap::default();
+    assert_eq!(terrain.get(TilePos::new(5, -3)), TerrainType::Plain);
+}
+
+#[test]
+fn test_terrain_lookup_from_world_position() {
+    let grid = test_grid();
+    let mut terrain = TerrainMap::default();
+    terrain.tiles.insert(TilePos::new(0, -1), TerrainType::Mud);
+
+    // W moves up-right by half a tile width and a quarter tile height
+    let world_pos = Vec2::new(32.0, 16.0);
+    assert_eq!(grid.world_to_tile(world_pos), TilePos::new(0, -1));
+    assert_eq!(terrain.at_world(&grid, world_pos), TerrainType::Mud);
+    assert_eq!(terrain.at_world(&grid, Vec2::ZERO), TerrainType::Plain);
+}
+
+#[test]
+fn test_step_drains_battery_by_terrain_cost() {
+    let mut app = App::new();
+    app.insert_resource(test_grid());
+    app.insert_resource(all_toggles_on());
+    let mut terrain = TerrainMap::default();
+    terrain.tiles.insert(TilePos::new(0, -1), TerrainType::Rock);
+    app.insert_resource(terrain);
+    app.init_resource::<HeightMap>();
+
+    let mut keys = ButtonInput::<KeyCode>::default();
+    keys.press(KeyCode::KeyW);
+    app.insert_resource(keys);
+
+    let player = app.world_mut().spawn((
+        Player,
+        Transform::default(),
+        GridMovement {
+            target_position: None,
+            move_speed: 400.0,
+            is_moving: false,
+        },
+        Battery {
+            max_charge: 100.0,
+            current_charge: 100.0,
+        },
+    )).id();
+
+    app.add_systems(Update, move_player);
+    app.update();
+
+    let battery = app.world().get::<Battery>(player).unwrap();
+    assert_eq!(battery.current_charge, 100.0 - TerrainType::Rock.step_cost());
+    let movement = app.world().get::<GridMovement>(player).unwrap();
+    assert_eq!(movement.target_position, Some(Vec2::new(32.0, 16.0)));
+}
@@ -0,0 +1,148 @@
+use bevy::prelude::*;
+use balthazar::components::{SystemToggles, ToggleButton};
+use balthazar::toggles::{system_toggles_plugin, toggle_enabled, RegisterToggle, RegisteredToggle, ToggleRegistry, ToggleValue};
+use balthazar::ui::{setup_ui, sync_toggle_buttons, update_ui};
+
+mod common;
+use common::all_toggles_on;
+
+fn app_with_toggles() -> App {
+    let mut app = App::new();
+    app.insert_resource(all_toggles_on());
+    app.add_plugins(system_toggles_plugin);
+    app.register_toggle("power", "Power", true);
+    app.register_toggle("lighting", "Lighting", false);
+    app
+}
+
+#[derive(Resource, Default)]
+struct Runs(u32);
+
+/// Label of each toggle button, by toggle name
+fn button_labels(app: &mut App) -> Vec<(String, String)> {
+    let mut query = app.world_mut().query::<(&ToggleButton, &Children)>();
+    let buttons: Vec<(String, Entity)> = query
+        .iter(app.world())
+        .map(|(button, children)| (button.0.clone(), children[0]))
+        .collect();
+    buttons
+        .into_iter()
+        .map(|(name, text)| (name, app.world().get::<Text>(text).unwrap().0.clone()))
+        .collect()
+}
+
+fn label_of(app: &mut App, name: &str) -> String {
+    button_labels(app).into_iter().find(|(button, _)| button == name).unwrap().1
+}
+
+#[test]
+fn test_registry_reads_and_sets_both_kinds_of_toggle() {
+    let mut registry = ToggleRegistry::default();
+    registry.register(RegisteredToggle {
+        name: "camera_follow".to_string(),
+        label: "Camera Follow".to_string(),
+        value: ToggleValue::Field {
+            get: |toggles| toggles.camera_follow,
+            set: |toggles, enabled| toggles.camera_follow = enabled,
+        },
+    });
+    registry.register(RegisteredToggle {
+        name: "lighting".to_string(),
+        label: "Lighting".to_string(),
+        value: ToggleValue::Stored(false),
+    });
+    let mut toggles = all_toggles_on();
+
+    assert_eq!(registry.is_enabled("camera_follow", &toggles), Some(true));
+    assert!(registry.set_enabled("camera_follow", &mut toggles, false));
+    assert!(!toggles.camera_follow);
+    assert_eq!(registry.is_enabled("camera_follow", &toggles), Some(false));
+
+    assert_eq!(registry.is_enabled("lighting", &toggles), Some(false));
+    assert!(registry.set_enabled("lighting", &mut toggles, true));
+    assert_eq!(registry.is_enabled("lighting", &toggles), Some(true));
+
+    // Unknown names are reported rather than taken to be on
+    assert_eq!(registry.is_enabled("teleporters", &toggles), None);
+    assert!(!registry.set_enabled("teleporters", &mut toggles, true));
+}
+
+#[test]
+fn test_run_condition_follows_the_toggle() {
+    let mut app = app_with_toggles();
+    app.init_resource::<Runs>();
+    app.add_systems(Update, (|mut runs: ResMut<Runs>| runs.0 += 1).run_if(toggle_enabled("power")));
+
+    app.update();
+    assert_eq!(app.world().resource::<Runs>().0, 1);
+
+    app.world_mut().resource_scope(|world, mut registry: Mut<ToggleRegistry>| {
+        registry.set_enabled("power", &mut world.resource_mut::<SystemToggles>(), false);
+    });
+    app.update();
+    assert_eq!(app.world().resource::<Runs>().0, 1);
+}
+
+#[test]
+fn test_unregistered_toggle_keeps_systems_off() {
+    let mut app = app_with_toggles();
+    app.init_resource::<Runs>();
+    app.add_systems(Update, (|mut runs: ResMut<Runs>| runs.0 += 1).run_if(toggle_enabled("missing")));
+    app.update();
+    assert_eq!(app.world().resource::<Runs>().0, 0);
+}
+
+#[test]
+fn test_panel_has_a_button_for_every_registered_toggle() {
+    let mut app = app_with_toggles();
+    app.add_systems(Startup, setup_ui);
+    app.update();
+
+    let labels = button_labels(&mut app);
+    let names: Vec<&str> = labels.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(
+        names,
+        ["player_movement", "cord_systems", "camera_follow", "camera_zoom", "player_rotation", "power", "lighting"]
+    );
+    assert_eq!(label_of(&mut app, "power"), "ON");
+    assert_eq!(label_of(&mut app, "lighting"), "OFF");
+}
+
+#[test]
+fn test_buttons_show_toggles_changed_from_code() {
+    let mut app = app_with_toggles();
+    app.add_systems(Startup, setup_ui);
+    app.add_systems(Update, (update_ui, sync_toggle_buttons).chain());
+    app.update();
+    assert_eq!(label_of(&mut app, "camera_follow"), "ON");
+
+    app.world_mut().resource_mut::<SystemToggles>().camera_follow = false;
+    app.update();
+    assert_eq!(label_of(&mut app, "camera_follow"), "OFF");
+
+    app.world_mut().resource_scope(|world, mut registry: Mut<ToggleRegistry>| {
+        registry.set_enabled("lighting", &mut world.resource_mut::<SystemToggles>(), true);
+    });
+    app.update();
+    assert_eq!(label_of(&mut app, "lighting"), "ON");
+}
+
+#[test]
+fn test_pressing_a_button_flips_its_toggle() {
+    let mut app = app_with_toggles();
+    app.add_systems(Startup, setup_ui);
+    app.add_systems(Update, (update_ui, sync_toggle_buttons).chain());
+    app.update();
+
+    let mut query = app.world_mut().query::<(Entity, &ToggleButton)>();
+    let button = query
+        .iter(app.world())
+        .find(|(_, button)| button.0 == "cord_systems")
+        .map(|(entity, _)| entity)
+        .unwrap();
+    app.world_mut().entity_mut(button).insert(Interaction::Pressed);
+    app.update();
+
+    assert!(!app.world().resource::<SystemToggles>().cord_systems);
+    assert_eq!(label_of(&mut app, "cord_systems"), "OFF");
+}
@@ -0,0 +1,216 @@
+use bevy::prelude::*;
+use balthazar::components::{Battery, CordSystem, GridMovement, Player};
+use balthazar::grid_objects::PushableCrate;
+use balthazar::undo::{handle_undo_input, record_move_snapshots, MoveSnapshot, UndoHistory};
+use balthazar::isometric::{MapGrid, TilePos};
+use balthazar::player_movement::GridStepCompleted;
+
+mod common;
+use common::all_toggles_on;
+
+fn snapshot(x: i32, charge: f32) -> MoveSnapshot {
+    MoveSnapshot {
+        tile: TilePos::new(x, 0),
+        trail_path: vec![Vec2::new(x as f32, 0.0)],
+        attached_pole: None,
+        battery_charge: charge,
+        crates: Vec::new(),
+    }
+}
+
+#[test]
+fn test_empty_history_cannot_undo_or_redo() {
+    let mut history = UndoHistory::default();
+    assert!(!history.can_undo());
+    assert!(!history.can_redo());
+    assert!(history.undo().is_none());
+    assert!(history.redo().is_none());
+}
+
+#[test]
+fn test_undo_and_redo_walk_through_snapshots() {
+    let mut history = UndoHistory::default();
+    history.record(snapshot(0, 100.0));
+    history.record(snapshot(1, 98.0));
+    history.record(snapshot(2, 96.0));
+
+    assert_eq!(history.undo().map(|s| s.tile), Some(TilePos::new(1, 0)));
+    assert_eq!(history.undo().map(|s| s.tile), Some(TilePos::new(0, 0)));
+    assert!(history.undo().is_none(), "Can't undo past the starting state");
+
+    assert_eq!(history.redo().map(|s| s.battery_charge), Some(98.0));
+    assert_eq!(history.redo().map(|s| s.battery_charge), Some(96.0));
+    assert!(history.redo().is_none());
+}
+
+#[test]
+fn test_new_step_discards_redo_branch() {
+    let mut history = UndoHistory::default();
+    history.record(snapshot(0, 100.0));
+    history.record(snapshot(1, 98.0));
+    history.record(snapshot(2, 96.0));
+
+    history.undo();
+    history.undo();
+    history.record(snapshot(-1, 98.0));
+
+    assert_eq!(history.snapshots.len(), 2);
+    assert!(!history.can_redo());
+    assert_eq!(history.undo().map(|s| s.tile), Some(TilePos::new(0, 0)));
+}
+
+#[test]
+fn test_undo_limit_is_enforced() {
+    let mut history = UndoHistory {
+        max_undos: Some(1),
+        ..default()
+    };
+    history.record(snapshot(0, 100.0));
+    history.record(snapshot(1, 98.0));
+    history.record(snapshot(2, 96.0));
+
+    assert!(history.undo().is_some());
+    assert!(!history.can_undo(), "Only one undo allowed");
+    assert!(history.undo().is_none());
+
+    // Redo is still allowed after running out of undos
+    assert!(history.redo().is_some());
+}
+
+#[test]
+fn test_history_is_capped() {
+    let mut history = UndoHistory {
+        max_history: 3,
+        ..default()
+    };
+    for x in 0..10 {
+        history.record(snapshot(x, 100.0));
+    }
+
+    assert_eq!(history.snapshots.len(), 3);
+    assert_eq!(history.cursor, 2);
+    assert_eq!(history.snapshots[0].tile, TilePos::new(7, 0));
+}
+
+#[test]
+fn test_clear_resets_everything() {
+    let mut history = UndoHistory::default();
+    history.record(snapshot(0, 100.0));
+    history.record(snapshot(1, 98.0));
+    history.undo();
+    history.pending_restore = Some(0);
+
+    history.clear();
+
+    assert!(history.snapshots.is_empty());
+    assert_eq!(history.cursor, 0);
+    assert_eq!(history.undos_used, 0);
+    assert!(history.pending_restore.is_none());
+}
+
+fn still() -> GridMovement {
+    GridMovement {
+        target_position: None,
+        move_speed: 400.0,
+        is_moving: false,
+    }
+}
+
+/// Finish whatever step `entity` is on, as `grid_movement_system` would
+fn arrive(app: &mut App, entity: Entity) {
+    let mut movement = app.world_mut().get_mut::<GridMovement>(entity).unwrap();
+    let target = movement.target_position.take().unwrap();
+    movement.is_moving = false;
+    app.world_mut().get_mut::<Transform>(entity).unwrap().translation = target.extend(0.0);
+    app.world_mut().write_message(GridStepCompleted { entity, position: target });
+}
+
+#[test]
+fn test_undoing_a_push_slides_the_crate_back() {
+    let grid = MapGrid::new(64.0, 32.0);
+    let at = |x: i32| Transform::from_translation(grid.tile_to_world(TilePos::new(x, 0)).extend(0.0));
+    let mut app = App::new();
+    app.insert_resource(grid.clone());
+    app.init_resource::<UndoHistory>();
+    app.init_resource::<ButtonInput<KeyCode>>();
+    app.insert_resource(all_toggles_on());
+    app.add_message::<GridStepCompleted>();
+    app.add_systems(Update, (handle_undo_input, record_move_snapshots).chain());
+
+    let player = app
+        .world_mut()
+        .spawn((Player, at(0), still(), Battery { max_charge: 100.0, current_charge: 100.0 }))
+        .id();
+    let pushed_crate = app.world_mut().spawn((PushableCrate, at(1), still())).id();
+    app.insert_resource(CordSystem {
+        visual_meshes: Vec::new(),
+        player_entity: player,
+        attached_pole: None,
+        attachment_range: 100.0,
+        trail_path: Vec::new(),
+        min_trail_segment_distance: 10.0,
+    });
+    app.update();
+
+    // The robot steps onto the crate's tile and pushes it one further
+    for (entity, x) in [(player, 1), (pushed_crate, 2)] {
+        let mut movement = app.world_mut().get_mut::<GridMovement>(entity).unwrap();
+        movement.target_position = Some(grid.tile_to_world(TilePos::new(x, 0)));
+        movement.is_moving = true;
+    }
+    arrive(&mut app, pushed_crate);
+    arrive(&mut app, player);
+    app.update();
+    assert_eq!(app.world().resource::<UndoHistory>().snapshots.len(), 2);
+
+    // Undo walks both back
+    app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::KeyZ);
+    app.update();
+    let crate_target = app.world().get::<GridMovement>(pushed_crate).unwrap().target_position;
+    assert_eq!(crate_target, Some(grid.tile_to_world(TilePos::new(1, 0))));
+
+    app.world_mut().resource_mut::<ButtonInput<KeyCode>>().clear();
+    arrive(&mut app, player);
+    app.update();
+    let crate_position = app.world().get::<Transform>(pushed_crate).unwrap().translation.truncate();
+    assert_eq!(grid.world_to_tile(crate_position), TilePos::new(1, 0));
+    assert!(!app.world().get::<GridMovement>(pushed_crate).unwrap().is_moving);
+}
+
+#[test]
+fn test_undoing_a_teleport_puts_the_robot_straight_back() {
+    let grid = MapGrid::new(64.0, 32.0);
+    let mut app = App::new();
+    app.insert_resource(grid.clone());
+    app.init_resource::<UndoHistory>();
+    app.init_resource::<ButtonInput<KeyCode>>();
+    app.insert_resource(all_toggles_on());
+    app.add_message::<GridStepCompleted>();
+    app.add_systems(Update, (handle_undo_input, record_move_snapshots).chain());
+
+    let start = grid.tile_to_world(TilePos::ZERO);
+    let player = app
+        .world_mut()
+        .spawn((Player, Transform::from_translation(start.extend(0.0)), still(), Battery { max_charge: 100.0, current_charge: 100.0 }))
+        .id();
+    app.insert_resource(CordSystem {
+        visual_meshes: Vec::new(),
+        player_entity: player,
+        attached_pole: None,
+        attachment_range: 100.0,
+        trail_path: Vec::new(),
+        min_trail_segment_distance: 10.0,
+    });
+    app.update();
+
+    // A teleport drops the robot on a pad far across the map
+    app.world_mut().get_mut::<GridMovement>(player).unwrap().target_position = Some(grid.tile_to_world(TilePos::new(9, 9)));
+    arrive(&mut app, player);
+    app.update();
+
+    app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::KeyZ);
+    app.update();
+    let transform = app.world().get::<Transform>(player).unwrap();
+    assert_eq!(transform.translation.truncate(), start, "No walking through whatever lies between");
+    assert!(app.world().resource::<UndoHistory>().pending_restore.is_some());
+}
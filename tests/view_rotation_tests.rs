@@ -0,0 +1,117 @@
+use bevy::prelude::*;
+use balthazar::components::{CordSystem, GridMovement, Player};
+use balthazar::elevation::HeightMap;
+use balthazar::isometric::{GridLayout, MapGrid, TilePos};
+use balthazar::undo::UndoHistory;
+use balthazar::view_rotation::{reproject_world, rotate_view, ViewRotated, ROTATE_RIGHT_KEY};
+
+fn map_grid() -> MapGrid {
+    let mut grid = MapGrid::new(64.0, 32.0);
+    grid.origin = Vec2::new(-40.0, 120.0);
+    // Middle of a 10x7 map
+    grid.pivot = Vec2::new(4.5, 3.0);
+    grid
+}
+
+#[test]
+fn test_turned_grid_round_trips_tiles() {
+    let grid = map_grid();
+    for turns in 1..4 {
+        let turned = grid.rotated(turns);
+        assert_eq!(turned.quarter_turns, turns as u8);
+        for x in -3..12 {
+            for y in -3..9 {
+                let tile = TilePos::new(x, y);
+                assert_eq!(turned.world_to_tile(turned.tile_to_world(tile)), tile);
+                let coords = Vec2::new(x as f32 + 0.25, y as f32 - 0.4);
+                assert!(turned.world_to_tile_coords(turned.tile_coords_to_world(coords)).distance(coords) < 1e-4);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_turning_moves_tiles_around_the_middle_of_the_map() {
+    let grid = map_grid();
+    let turned = grid.rotated(1);
+
+    // The middle of the map stays put and the top corner swings round to the right
+    let middle = grid.tile_coords_to_world(grid.pivot);
+    assert_eq!(turned.tile_coords_to_world(turned.pivot), middle);
+    assert_eq!(turned.tile_to_world(TilePos::ZERO), grid.tile_coords_to_world(Vec2::new(7.5, -1.5)));
+    let bounds = IRect::new(0, 0, 9, 6);
+    assert!(grid.tiles_world_rect(bounds).center().distance(turned.tiles_world_rect(bounds).center()) < 1e-3);
+
+    assert_eq!(grid.rotated(4), grid);
+    assert_eq!(grid.rotated(1).rotated(-1), grid);
+    assert_eq!(grid.rotated(-1).quarter_turns, 3);
+}
+
+#[test]
+fn test_only_isometric_maps_turn() {
+    let grid = MapGrid::new(32.0, 32.0).with_layout(GridLayout::Orthogonal { diagonal_moves: false });
+    assert!(!grid.can_rotate());
+    assert_eq!(grid.rotated(1), grid);
+}
+
+#[test]
+fn test_movement_keys_stay_relative_to_the_screen() {
+    let keys = [Vec2::Y, Vec2::NEG_X, Vec2::NEG_Y, Vec2::X];
+    let tile = TilePos::new(4, 3);
+    for turns in 0..4 {
+        let grid = map_grid().rotated(turns);
+        for key in keys {
+            let neighbor = grid.neighbor_toward(tile, grid.input_direction(key)).expect("a neighbour");
+            let step = grid.tile_to_world(neighbor) - grid.tile_to_world(tile);
+            assert!(step.normalize().dot(grid.input_direction(key).normalize()) > 0.99, "turns {turns}, key {key}");
+        }
+    }
+}
+
+#[test]
+fn test_turning_keeps_the_robot_and_cord_on_their_tiles() {
+    let mut app = App::new();
+    let grid = map_grid();
+    app.insert_resource(grid.clone());
+    app.init_resource::<HeightMap>();
+    app.init_resource::<UndoHistory>();
+    app.init_resource::<ButtonInput<KeyCode>>();
+    app.add_message::<ViewRotated>();
+    app.add_systems(Update, (rotate_view, reproject_world).chain());
+
+    let tile = TilePos::new(2, 5);
+    let target = TilePos::new(3, 5);
+    let player = app
+        .world_mut()
+        .spawn((
+            Player,
+            Transform::from_translation(grid.tile_to_world(tile).extend(10.0)),
+            GridMovement { target_position: Some(grid.tile_to_world(target)), move_speed: 200.0, is_moving: true },
+        ))
+        .id();
+    let trail_tiles = [TilePos::new(0, 5), TilePos::new(1, 5), tile];
+    app.insert_resource(CordSystem {
+        visual_meshes: Vec::new(),
+        player_entity: player,
+        attached_pole: None,
+        attachment_range: 50.0,
+        trail_path: trail_tiles.iter().map(|tile| grid.tile_to_world(*tile)).collect(),
+        min_trail_segment_distance: 10.0,
+    });
+
+    app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(ROTATE_RIGHT_KEY);
+    app.update();
+
+    let turned = app.world().resource::<MapGrid>().clone();
+    assert_eq!(turned.quarter_turns, 1);
+    let transform = app.world().get::<Transform>(player).unwrap();
+    assert!(transform.translation.truncate().distance(turned.tile_to_world(tile)) < 1e-3);
+    assert_eq!(transform.translation.z, 10.0);
+    let movement = app.world().get::<GridMovement>(player).unwrap();
+    assert_eq!(turned.world_to_tile(movement.target_position.unwrap()), target);
+
+    let trail = &app.world().resource::<CordSystem>().trail_path;
+    let trail_after: Vec<TilePos> = trail.iter().map(|point| turned.world_to_tile(*point)).collect();
+    assert_eq!(trail_after, trail_tiles);
+    assert!(trail[0].distance(turned.tile_to_world(trail_tiles[0])) < 1e-3);
+}